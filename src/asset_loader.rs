@@ -1,10 +1,26 @@
-use bevy::prelude::*;
+use bevy::{asset::io::file::FileAssetReader, prelude::*};
 
 #[derive(Resource, Debug, Default)]
 pub struct SceneAssets {
     pub asteroid: Handle<Scene>,
     pub spaceship: Handle<Scene>,
     pub missiles: Handle<Scene>,
+    // 敌方飞船暂时没有单独的模型，复用飞船模型
+    pub enemy: Handle<Scene>,
+}
+
+// `AudioAssets` 资源保存游戏中所有的音效和音乐
+// 音频文件是可选的，`assets/audio` 目录下不存在的文件对应的字段为 `None`，音频插件会跳过这些声音
+#[derive(Resource, Debug, Default)]
+pub struct AudioAssets {
+    pub fire: Option<Handle<AudioSource>>,
+    pub explosion: Option<Handle<AudioSource>>,
+    pub shield: Option<Handle<AudioSource>>,
+    pub pickup: Option<Handle<AudioSource>>,
+    pub engine: Option<Handle<AudioSource>>,
+    // 音乐分为两层，激烈层的音量随波数增加
+    pub music: Option<Handle<AudioSource>>,
+    pub music_intense: Option<Handle<AudioSource>>,
 }
 
 pub struct AssetLoaderPlugin;
@@ -12,14 +28,42 @@ pub struct AssetLoaderPlugin;
 impl Plugin for AssetLoaderPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<SceneAssets>()
+            .init_resource::<AudioAssets>()
             .add_systems(Startup, load_assets);
     }
 }
 
-fn load_assets(mut scene_assets: ResMut<SceneAssets>, asset_server: Res<AssetServer>) {
+fn load_assets(
+    mut scene_assets: ResMut<SceneAssets>,
+    mut audio_assets: ResMut<AudioAssets>,
+    asset_server: Res<AssetServer>,
+) {
     *scene_assets = SceneAssets {
         asteroid: asset_server.load("Asteroid.glb#Scene0"),
         spaceship: asset_server.load("Spaceship.glb#Scene0"),
         missiles: asset_server.load("Missiles.glb#Scene0"),
+        enemy: asset_server.load("Spaceship.glb#Scene0"),
+    };
+    *audio_assets = AudioAssets {
+        fire: load_audio(&asset_server, "audio/fire.ogg"),
+        explosion: load_audio(&asset_server, "audio/explosion.ogg"),
+        shield: load_audio(&asset_server, "audio/shield.ogg"),
+        pickup: load_audio(&asset_server, "audio/pickup.ogg"),
+        engine: load_audio(&asset_server, "audio/engine.ogg"),
+        music: load_audio(&asset_server, "audio/music.ogg"),
+        music_intense: load_audio(&asset_server, "audio/music_intense.ogg"),
+    };
+}
+
+// 这个函数只在音频文件存在时加载它，避免资源服务器为缺少的文件报错
+fn load_audio(asset_server: &AssetServer, path: &str) -> Option<Handle<AudioSource>> {
+    let exists = FileAssetReader::get_base_path()
+        .join("assets")
+        .join(path)
+        .is_file();
+    if !exists {
+        info!("没有找到音频文件 {}，对应的声音将被跳过", path);
+        return None;
     }
+    Some(asset_server.load(path.to_string()))
 }
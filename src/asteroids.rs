@@ -1,13 +1,25 @@
-use std::ops::Range;
+use std::{f32::consts::TAU, ops::Range, time::Duration};
 
-use bevy::prelude::*;
+use bevy::{
+    ecs::event::ManualEventReader,
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
 use rand::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     asset_loader::SceneAssets,
-    collision_detection::Collider,
-    movement::{Acceleration, MovingObjectBundle, Velocity},
+    collision_detection::{
+        handle_collisions, Collider, DestroyedKind, EntityDestroyed, SpawnGrace,
+    },
+    console::{parse_arg, parse_optional_arg, ConsoleCommandsExt},
+    difficulty::DifficultyTuning,
+    health::Health,
+    movement::{Acceleration, ExternalVelocity, MovementSet, MovingObjectBundle, Velocity},
+    rng::GameRng,
     schedule::InGameSet,
+    spaceship::Spaceship,
 };
 
 // 定义一个常量 `VELOCITY_SCALAR`，表示速度的缩放因子，初始值为 5.0
@@ -16,56 +28,440 @@ const VELOCITY_SCALAR: f32 = 5.0;
 // 定义一个常量 `ACCELERATION_SCALAR`，表示加速度的缩放因子，初始值为 1.0
 const ACCELERATION_SCALAR: f32 = 1.0;
 
-// 定义一个常量 `SPAWN_RANGE_X`，表示在 X 轴上生成实体的范围，范围为 -25.0 到 25.0
-const SPAWN_RANGE_X: Range<f32> = -25.0..25.0;
+// 定义固定摄像机能看到的场地范围的一半，摄像机在 80.0 的高度以 45 度的垂直视角俯视原点，窗口的宽高比为 16:9
+const VISIBLE_HALF_EXTENTS: Vec2 = Vec2::new(60.0, 34.0);
 
-// 定义一个常量 `SPAWN_RANGE_Z`，表示在 Z 轴上生成实体的范围，范围为 0.0 到 25.0
-const SPAWN_RANGE_Z: Range<f32> = 0.0..25.0;
+// 定义小行星在可见范围之外多远的地方生成
+const SPAWN_MARGIN: f32 = 6.0;
 
-// 定义一个常量 `SPAWN_TIME_SECONDS`，表示生成新实体的时间间隔，单位为秒，初始值为 1.0
-const SPAWN_TIME_SECONDS: f32 = 1.0;
+// 定义小行星飞向的目标点的范围，目标点在可见范围的中央区域内随机选择
+const TARGET_RANGE: Range<f32> = -20.0..20.0;
+
+// 定义环形生成的半径，它大于可见范围对角线的一半，并且小于销毁距离
+const RING_RADIUS: f32 = 72.0;
+
+// 定义成团生成时小行星之间的最大距离
+const CLUSTER_SPREAD: f32 = 8.0;
 
-// 定义一个常量 `ROTATE_SPEED`，表示旋转速度，初始值为 2.5
-const ROTATE_SPEED: f32 = 2.5;
+// 定义成行生成时相邻小行星之间的距离
+const LINE_SPACING: f32 = 7.0;
+
+// 定义为一颗小行星寻找安全位置的最多尝试次数，全部失败时这颗小行星不会生成
+const MAX_PLACEMENT_ATTEMPTS: usize = 8;
+
+// 定义一个常量 `SPAWN_TIME_SECONDS`，表示普通难度下生成新实体的时间间隔，单位为秒，初始值为 1.0
+const SPAWN_TIME_SECONDS: f32 = 1.0;
 
-// 定义一个常量 `RADIUS`，表示半径，初始值为 2.5
+// 定义一个常量 `RADIUS`，表示缩放为 1.0 的小行星的半径，初始值为 2.5
 const RADIUS: f32 = 2.5;
 
-#[derive(Component, Debug)]
+// 定义内置的小行星种类数据
+const VARIANTS_RON: &str = include_str!("../assets/asteroids.ron");
+
+// 定义分裂出的碎片向外飞散的速度
+const SHARD_SPEED: f32 = 8.0;
+
+// 定义碎片生成后不会被摧毁的时间，单位为秒，足够让摧毁母体的导弹飞离碎片
+const SHARD_GRACE_SECONDS: f32 = 0.5;
+
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
 pub struct Asteroid;
 
-#[derive(Resource, Debug)]
+// `AsteroidKind` 组件表示小行星的种类，小行星的颜色由种类决定
+#[derive(
+    Component, Reflect, Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize,
+)]
+#[reflect(Component)]
+pub enum AsteroidKind {
+    // 普通的岩石小行星
+    #[default]
+    Rock,
+    // 铁质小行星，需要多发导弹才能摧毁
+    Iron,
+    // 冰质小行星，被摧毁时分裂成许多碎片
+    Ice,
+    // 爆炸小行星，被摧毁时炸毁附近的小行星
+    Explosive,
+    // 磁性小行星，把附近的飞船拉向自己
+    Magnetic,
+}
+
+impl AsteroidKind {
+    pub const ALL: [AsteroidKind; 5] = [
+        AsteroidKind::Rock,
+        AsteroidKind::Iron,
+        AsteroidKind::Ice,
+        AsteroidKind::Explosive,
+        AsteroidKind::Magnetic,
+    ];
+}
+
+// `AsteroidBehavior` 组件表示小行星的特殊行为
+#[derive(Component, Reflect, Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[reflect(Component)]
+pub enum AsteroidBehavior {
+    // 没有特殊行为
+    #[default]
+    Inert,
+    // 被摧毁时分裂成 `count` 块碎片，碎片的缩放是原来的 `scale` 倍
+    Split {
+        count: usize,
+        scale: f32,
+    },
+    // 被摧毁时对 `radius` 范围内的其他小行星造成 `damage` 点伤害，被炸毁的爆炸小行星会继续引爆
+    Explode {
+        radius: f32,
+        damage: f32,
+    },
+    // 把 `radius` 范围内的飞船拉向自己，距离越近拉得越快，`strength` 是最大的拉力加速度
+    Magnet {
+        radius: f32,
+        strength: f32,
+    },
+}
+
+// `Tumble` 组件表示小行星绕本地 X、Y、Z 轴翻滚的角速度，单位为弧度每秒
+#[derive(Component, Reflect, Debug, Default, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub struct Tumble {
+    pub angular_velocity: Vec3,
+}
+
+impl Tumble {
+    // 返回每个轴的角速度都在 `-max..max` 之间随机选择的翻滚
+    fn random(rng: &mut impl Rng, max: f32) -> Self {
+        let mut axis = || {
+            if max > 0.0 {
+                rng.gen_range(-max..max)
+            } else {
+                0.0
+            }
+        };
+        Self {
+            angular_velocity: Vec3::new(axis(), axis(), axis()),
+        }
+    }
+}
+
+// `AsteroidVariant` 描述一种小行星，各个字段的含义见 `assets/asteroids.ron`
+#[derive(Reflect, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AsteroidVariant {
+    pub kind: AsteroidKind,
+    pub weight: f32,
+    pub tint: Color,
+    pub scale: (f32, f32),
+    pub tumble: f32,
+    pub health: f32,
+    pub behavior: AsteroidBehavior,
+}
+
+// `AsteroidVariants` 资源保存所有的小行星种类，默认值是编译时嵌入的 `assets/asteroids.ron`
+#[derive(Resource, Reflect, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[reflect(Resource)]
+pub struct AsteroidVariants {
+    pub variants: Vec<AsteroidVariant>,
+}
+
+impl Default for AsteroidVariants {
+    fn default() -> Self {
+        ron::from_str(VARIANTS_RON).expect("内置的小行星数据应当总是有效的")
+    }
+}
+
+impl AsteroidVariants {
+    // 按照权重随机选择一种小行星，没有任何种类时返回 `None`
+    pub fn choose(&self, rng: &mut impl Rng) -> Option<&AsteroidVariant> {
+        self.variants
+            .choose_weighted(rng, |variant| variant.weight)
+            .ok()
+    }
+
+    // 返回指定种类的小行星
+    pub fn get(&self, kind: AsteroidKind) -> Option<&AsteroidVariant> {
+        self.variants.iter().find(|variant| variant.kind == kind)
+    }
+}
+
+// `SpawnEdge` 表示可见范围的一条边，摄像机的上方向为 +Z，因此 +Z 是屏幕的上边，+X 是屏幕的左边
+#[derive(Reflect, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpawnEdge {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+impl SpawnEdge {
+    pub const ALL: [SpawnEdge; 4] = [
+        SpawnEdge::Top,
+        SpawnEdge::Bottom,
+        SpawnEdge::Left,
+        SpawnEdge::Right,
+    ];
+
+    // 返回这条边上的一个随机位置，位置在可见范围之外 `SPAWN_MARGIN` 处
+    fn random_point(&self, rng: &mut impl Rng) -> Vec3 {
+        let x = VISIBLE_HALF_EXTENTS.x + SPAWN_MARGIN;
+        let z = VISIBLE_HALF_EXTENTS.y + SPAWN_MARGIN;
+        match self {
+            SpawnEdge::Top => Vec3::new(rng.gen_range(-x..x), 0.0, z),
+            SpawnEdge::Bottom => Vec3::new(rng.gen_range(-x..x), 0.0, -z),
+            SpawnEdge::Left => Vec3::new(x, 0.0, rng.gen_range(-z..z)),
+            SpawnEdge::Right => Vec3::new(-x, 0.0, rng.gen_range(-z..z)),
+        }
+    }
+
+    // 返回沿着这条边的方向
+    fn tangent(&self) -> Vec3 {
+        match self {
+            SpawnEdge::Top | SpawnEdge::Bottom => Vec3::X,
+            SpawnEdge::Left | SpawnEdge::Right => Vec3::Z,
+        }
+    }
+}
+
+// `SpawnPattern` 表示小行星的生成方式
+#[derive(Reflect, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpawnPattern {
+    // 每次在一条边外生成一颗小行星
+    Edge,
+    // 在场地周围的圆环上同时生成多颗小行星，全部飞向场地中央
+    Ring,
+    // 沿着一条边生成一排平行飞行的小行星
+    Line,
+    // 在一条边外生成一团飞向同一个方向的小行星
+    Cluster,
+    // 每次随机选择上面的一种方式
+    Mixed,
+}
+
+impl SpawnPattern {
+    pub const ALL: [SpawnPattern; 5] = [
+        SpawnPattern::Edge,
+        SpawnPattern::Ring,
+        SpawnPattern::Line,
+        SpawnPattern::Cluster,
+        SpawnPattern::Mixed,
+    ];
+}
+
+// `AsteroidSpawnConfig` 资源配置小行星从哪些边、以什么方式生成，以及与飞船之间的安全距离
+#[derive(Resource, Reflect, Debug, Clone, PartialEq)]
+#[reflect(Resource)]
+pub struct AsteroidSpawnConfig {
+    pub pattern: SpawnPattern,
+    // 允许生成小行星的边
+    pub edges: Vec<SpawnEdge>,
+    // 小行星生成的位置与任何飞船之间的最小距离
+    pub safe_radius: f32,
+    // 环形、成行和成团生成时每次生成的小行星数量
+    pub ring_count: usize,
+    pub line_count: usize,
+    pub cluster_count: usize,
+}
+
+impl Default for AsteroidSpawnConfig {
+    fn default() -> Self {
+        Self {
+            pattern: SpawnPattern::Edge,
+            edges: SpawnEdge::ALL.to_vec(),
+            safe_radius: 20.0,
+            ring_count: 8,
+            line_count: 4,
+            cluster_count: 4,
+        }
+    }
+}
+
+// 这个函数返回从 `from` 飞向可见范围中央区域内一个随机点的方向
+fn inward_heading(rng: &mut impl Rng, from: Vec3) -> Vec3 {
+    let target = Vec3::new(
+        rng.gen_range(TARGET_RANGE),
+        0.0,
+        rng.gen_range(TARGET_RANGE),
+    );
+    (target - from).normalize_or_zero()
+}
+
+// `AsteroidSpawn` 表示一颗即将生成的小行星的位置和飞行方向
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct AsteroidSpawn {
+    translation: Vec3,
+    direction: Vec3,
+}
+
+impl AsteroidSpawnConfig {
+    // 按照配置的方式返回一次生成的所有小行星，距离飞船太近的小行星会被重新放置或者跳过
+    fn placements(&self, rng: &mut impl Rng, ships: &[Vec3]) -> Vec<AsteroidSpawn> {
+        let is_safe = |translation: Vec3| {
+            ships
+                .iter()
+                .all(|ship| ship.distance(translation) >= self.safe_radius)
+        };
+        let pattern = match self.pattern {
+            SpawnPattern::Mixed => *SpawnPattern::ALL[..SpawnPattern::ALL.len() - 1]
+                .choose(rng)
+                .unwrap(),
+            pattern => pattern,
+        };
+        let Some(&edge) = self.edges.choose(rng) else {
+            return Vec::new();
+        };
+
+        match pattern {
+            SpawnPattern::Edge | SpawnPattern::Mixed => (0..MAX_PLACEMENT_ATTEMPTS)
+                .map(|_| self.edges.choose(rng).unwrap_or(&edge).random_point(rng))
+                .find(|&translation| is_safe(translation))
+                .map(|translation| AsteroidSpawn {
+                    translation,
+                    direction: inward_heading(rng, translation),
+                })
+                .into_iter()
+                .collect(),
+            SpawnPattern::Ring => {
+                let offset = rng.gen_range(0.0..std::f32::consts::TAU);
+                (0..self.ring_count)
+                    .map(|index| {
+                        let angle =
+                            offset + std::f32::consts::TAU * index as f32 / self.ring_count as f32;
+                        let translation = Vec3::new(angle.cos(), 0.0, angle.sin()) * RING_RADIUS;
+                        AsteroidSpawn {
+                            translation,
+                            direction: inward_heading(rng, translation),
+                        }
+                    })
+                    .filter(|spawn| is_safe(spawn.translation))
+                    .collect()
+            }
+            SpawnPattern::Line => {
+                let center = edge.random_point(rng);
+                let direction = inward_heading(rng, center);
+                let start = -(self.line_count as f32 - 1.0) / 2.0;
+                (0..self.line_count)
+                    .map(|index| AsteroidSpawn {
+                        translation: center
+                            + edge.tangent() * (start + index as f32) * LINE_SPACING,
+                        direction,
+                    })
+                    .filter(|spawn| is_safe(spawn.translation))
+                    .collect()
+            }
+            SpawnPattern::Cluster => {
+                let center = edge.random_point(rng);
+                let direction = inward_heading(rng, center);
+                (0..self.cluster_count)
+                    .map(|_| {
+                        let offset = Vec3::new(
+                            rng.gen_range(-CLUSTER_SPREAD..CLUSTER_SPREAD),
+                            0.0,
+                            rng.gen_range(-CLUSTER_SPREAD..CLUSTER_SPREAD),
+                        );
+                        AsteroidSpawn {
+                            // 团中的小行星只向远离场地的方向散开，保证它们都在可见范围之外
+                            translation: center + offset - direction * offset.dot(direction).abs(),
+                            direction,
+                        }
+                    })
+                    .filter(|spawn| is_safe(spawn.translation))
+                    .collect()
+            }
+        }
+    }
+}
+
+#[derive(Resource, Reflect, Debug)]
+#[reflect(Resource)]
 pub struct SpawnTimer {
     timer: Timer,
 }
 
+impl Default for SpawnTimer {
+    fn default() -> Self {
+        Self {
+            // 设置计时器为每 `SPAWN_TIME_SECONDS` 秒重复一次
+            timer: Timer::from_seconds(SPAWN_TIME_SECONDS, TimerMode::Repeating),
+        }
+    }
+}
+
 pub struct AsteroidPlugin;
 
 // 为 `AsteroidPlugin` 实现 `Plugin` trait
 impl Plugin for AsteroidPlugin {
     // 在 `build` 方法中，将 `SpawnTimer` 资源插入到应用中，设置其计时器为每 `SPAWN_TIME_SECONDS` 秒重复一次
-    // 并在更新阶段添加 `spawn_asteroid` 和 `rotate_asteroids` 系统
+    // 并在更新阶段添加生成、翻滚、磁力吸引、被摧毁时触发行为以及染色的系统
     fn build(&self, app: &mut App) {
-        app.insert_resource(SpawnTimer {
-            // 设置计时器为每 `SPAWN_TIME_SECONDS` 秒重复一次
-            timer: Timer::from_seconds(SPAWN_TIME_SECONDS, TimerMode::Repeating),
-        })
-        .add_systems(
-            Update,
-            // 在更新阶段的 `InGameSet::EntityUpdates` 集合中添加 `spawn_asteroid` 和 `rotate_asteroids` 系统
-            (spawn_asteroid, rotate_asteroids).in_set(InGameSet::EntityUpdates),
-        );
+        app.register_type::<Asteroid>()
+            .register_type::<AsteroidKind>()
+            .register_type::<AsteroidBehavior>()
+            .register_type::<Tumble>()
+            .register_type::<Health>()
+            .register_type::<AsteroidVariant>()
+            .register_type::<AsteroidVariants>()
+            .register_type::<Vec<AsteroidVariant>>()
+            .register_type::<(f32, f32)>()
+            .register_type::<SpawnTimer>()
+            .register_type::<AsteroidSpawnConfig>()
+            .register_type::<SpawnPattern>()
+            .register_type::<SpawnEdge>()
+            .register_type::<Vec<SpawnEdge>>()
+            .register_type::<GameRng>()
+            .register_type::<TimerMode>()
+            .init_resource::<GameRng>()
+            .init_resource::<SpawnTimer>()
+            .init_resource::<AsteroidSpawnConfig>()
+            .init_resource::<DifficultyTuning>()
+            .init_resource::<AsteroidVariants>()
+            .add_event::<EntityDestroyed>()
+            .add_systems(
+                Update,
+                // 在更新阶段的 `InGameSet::EntityUpdates` 集合中添加 `spawn_asteroid` 和 `rotate_asteroids` 系统
+                (spawn_asteroid, rotate_asteroids).in_set(InGameSet::EntityUpdates),
+            )
+            .add_systems(
+                Update,
+                apply_magnetic_pull.in_set(MovementSet::ApplyForces),
+            )
+            .add_systems(
+                Update,
+                // 被摧毁的小行星在命令执行之前仍然存在，因此这里不等待命令执行
+                trigger_destroyed_asteroids
+                    .in_set(InGameSet::DespawnEntities)
+                    .after_ignore_deferred(handle_collisions::<Asteroid>),
+            )
+            .add_systems(Update, tint_asteroids)
+            .add_console_command(
+                "spawn asteroid",
+                "spawn asteroid [count] [rock|iron|ice|explosive|magnetic] - spawn asteroids outside the visible area",
+                spawn_asteroid_command,
+            )
+            .add_console_command(
+                "asteroid pattern",
+                "asteroid pattern <edge|ring|line|cluster|mixed> - change how asteroids spawn",
+                asteroid_pattern_command,
+            );
     }
 }
 
-// 定义一个名为 `spawn_asteroid` 的函数，它接受四个参数：一个可变的 `Commands` 类型参数、一个可变的 `SpawnTimer` 资源引用、一个 `Time` 资源引用和一个 `SceneAssets` 资源引用
-// 这个函数用于生成新的小行星实体
+// 定义一个名为 `spawn_asteroid` 的函数，它接受的参数包括一个可变的 `Commands` 类型参数、一个可变的 `SpawnTimer` 资源引用、一个 `Time` 资源引用和一个 `SceneAssets` 资源引用
+// 这个函数用于在可见范围之外生成飞向场地的小行星，生成间隔和小行星的速度随难度变化，每颗小行星的种类按照权重随机选择
+#[allow(clippy::too_many_arguments)]
 fn spawn_asteroid(
     mut commands: Commands,
     mut spawn_timer: ResMut<SpawnTimer>,
     time: Res<Time>,
     scene_assets: Res<SceneAssets>,
+    mut rng: ResMut<GameRng>,
+    tuning: Res<DifficultyTuning>,
+    config: Res<AsteroidSpawnConfig>,
+    variants: Res<AsteroidVariants>,
+    spaceship_query: Query<&Transform, With<Spaceship>>,
 ) {
+    let duration = Duration::from_secs_f32(SPAWN_TIME_SECONDS * tuning.spawn_interval_scale);
+    if spawn_timer.timer.duration() != duration {
+        spawn_timer.timer.set_duration(duration);
+    }
     // 更新 `spawn_timer` 的计时器
     spawn_timer.timer.tick(time.delta());
     // 如果计时器没有刚刚结束，那么直接返回，不生成新的小行星
@@ -73,47 +469,339 @@ fn spawn_asteroid(
         return;
     }
 
-    // 创建一个随机数生成器
-    let mut rng = rand::thread_rng();
+    let ships: Vec<Vec3> = spaceship_query
+        .iter()
+        .map(|transform| transform.translation)
+        .collect();
+    for spawn in config.placements(rng.as_mut(), &ships) {
+        let Some(variant) = variants.choose(rng.as_mut()) else {
+            return;
+        };
+        commands.spawn(spawned_asteroid_bundle(
+            scene_assets.asteroid.clone(),
+            variant,
+            spawn,
+            tuning.asteroid_speed_scale,
+            rng.as_mut(),
+        ));
+    }
+}
 
-    // 生成一个随机的位置，位置的 X 和 Z 坐标在 `SPAWN_RANGE_X` 和 `SPAWN_RANGE_Z` 范围内，Y 坐标为 0
-    let translation = Vec3::new(
-        rng.gen_range(SPAWN_RANGE_X),
-        0.,
-        rng.gen_range(SPAWN_RANGE_Z),
-    );
+// 这个函数返回一颗从可见范围之外飞来的小行星，小行星的速度和加速度都沿着飞行方向，并乘以 `speed_scale`
+fn spawned_asteroid_bundle(
+    scene: Handle<Scene>,
+    variant: &AsteroidVariant,
+    spawn: AsteroidSpawn,
+    speed_scale: f32,
+    rng: &mut impl Rng,
+) -> impl Bundle {
+    asteroid_bundle(
+        scene,
+        variant,
+        spawn.translation,
+        spawn.direction * VELOCITY_SCALAR * speed_scale,
+        spawn.direction * ACCELERATION_SCALAR * speed_scale,
+        rng,
+    )
+}
+
+// 这个函数返回一颗小行星所需的组件，缩放和翻滚在 `variant` 给出的范围内随机选择，碰撞器的半径随缩放变化
+pub fn asteroid_bundle(
+    scene: Handle<Scene>,
+    variant: &AsteroidVariant,
+    translation: Vec3,
+    velocity: Vec3,
+    acceleration: Vec3,
+    rng: &mut impl Rng,
+) -> impl Bundle {
+    let (min_scale, max_scale) = variant.scale;
+    let scale = rng.gen_range(min_scale..=max_scale.max(min_scale));
 
-    // 定义一个函数，用于生成一个随机的单位向量
-    let mut random_unit_vector =
-        || Vec3::new(rng.gen_range(-1.0..1.0), 0., rng.gen_range(-1.0..1.0)).normalize_or_zero();
-    // 生成一个随机的速度，速度的方向为一个随机的单位向量，大小为 `VELOCITY_SCALAR`
-    let velocity = random_unit_vector() * VELOCITY_SCALAR;
-    // 生成一个随机的加速度，加速度的方向为一个随机的单位向量，大小为 `ACCELERATION_SCALAR`
-    let acceleration = random_unit_vector() * ACCELERATION_SCALAR;
-
-    // 使用 `commands` 的 `spawn` 方法来创建一个新的小行星实体
-    // 这个新实体拥有 `MovingObjectBundle` 组件和 `Asteroid` 组件
-    // `MovingObjectBundle` 组件包含一个 `Acceleration`，其值为生成的随机加速度，一个 `Velocity`，其值为生成的随机速度，一个 `Collider`，其半径为 `RADIUS`，和一个 `SceneBundle`，其场景为 `scene_assets.asteroid`，位置为生成的随机位置
-    commands.spawn((
+    // 这个组件组合包含 `MovingObjectBundle` 组件、`Asteroid` 组件和描述小行星种类的组件
+    // `MovingObjectBundle` 组件包含一个 `Acceleration`，一个 `Velocity`，一个 `Collider`，其半径为 `RADIUS` 乘以缩放，和一个 `SceneBundle`，其场景为小行星模型
+    (
         MovingObjectBundle {
             acceleration: Acceleration::new(acceleration),
             velocity: Velocity::new(velocity),
-            collider: Collider::new(RADIUS),
+            collider: Collider::new(RADIUS * scale),
             model: SceneBundle {
-                scene: scene_assets.asteroid.clone(),
-                transform: Transform::from_translation(translation),
+                scene,
+                transform: Transform::from_translation(translation).with_scale(Vec3::splat(scale)),
                 ..default()
             },
         },
         Asteroid,
-    ));
+        variant.kind,
+        variant.behavior,
+        Tumble::random(rng, variant.tumble),
+        Health::new(variant.health),
+    )
 }
 
-// 这个函数用于旋转所有的小行星
-fn rotate_asteroids(mut query: Query<&mut Transform, With<Asteroid>>, time: Res<Time>) {
-    // 对查询结果进行迭代，每次迭代得到一个小行星的变换
-    for mut transform in query.iter_mut() {
-        // 使用 `Transform` 的 `rotate_local_z` 方法来旋转小行星，旋转的速度为 `ROTATE_SPEED`，旋转的时间为 `time.delta_seconds()`
-        transform.rotate_local_z(ROTATE_SPEED * time.delta_seconds());
+// `spawn asteroid` 控制台命令立即在可见范围的边缘之外生成若干颗小行星
+fn spawn_asteroid_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let count: usize = parse_optional_arg(args, 0, "count", 1)?;
+    let kind = args
+        .get(1)
+        .map(|name| {
+            AsteroidKind::ALL
+                .into_iter()
+                .find(|kind| format!("{kind:?}").eq_ignore_ascii_case(name))
+                .ok_or_else(|| format!("invalid value `{name}` for <kind>"))
+        })
+        .transpose()?;
+    let variants = world.resource::<AsteroidVariants>().clone();
+    if let Some(kind) = kind.filter(|&kind| variants.get(kind).is_none()) {
+        return Err(format!("no asteroid variant `{kind:?}` is defined"));
+    }
+    let scene = world.resource::<SceneAssets>().asteroid.clone();
+    let speed_scale = world.resource::<DifficultyTuning>().asteroid_speed_scale;
+    let config = AsteroidSpawnConfig {
+        pattern: SpawnPattern::Edge,
+        ..world.resource::<AsteroidSpawnConfig>().clone()
+    };
+    let ships: Vec<Vec3> = world
+        .query_filtered::<&Transform, With<Spaceship>>()
+        .iter(world)
+        .map(|transform| transform.translation)
+        .collect();
+    let mut spawned = 0;
+    world.resource_scope(|world, mut rng: Mut<GameRng>| {
+        for _ in 0..count {
+            for spawn in config.placements(rng.as_mut(), &ships) {
+                let variant = match kind {
+                    Some(kind) => variants.get(kind),
+                    None => variants.choose(rng.as_mut()),
+                };
+                let Some(variant) = variant else {
+                    continue;
+                };
+                world.spawn(spawned_asteroid_bundle(
+                    scene.clone(),
+                    variant,
+                    spawn,
+                    speed_scale,
+                    rng.as_mut(),
+                ));
+                spawned += 1;
+            }
+        }
+    });
+    Ok(format!("spawned {spawned} asteroids"))
+}
+
+// `asteroid pattern` 控制台命令修改小行星的生成方式
+fn asteroid_pattern_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let name: String = parse_arg(args, 0, "pattern")?;
+    let pattern = SpawnPattern::ALL
+        .into_iter()
+        .find(|pattern| format!("{pattern:?}").eq_ignore_ascii_case(&name))
+        .ok_or_else(|| format!("invalid value `{name}` for <pattern>"))?;
+    world.resource_mut::<AsteroidSpawnConfig>().pattern = pattern;
+    Ok(format!("asteroid pattern set to {pattern:?}"))
+}
+
+// 这个函数按照每颗小行星的 `Tumble` 让它绕三个轴翻滚
+fn rotate_asteroids(mut query: Query<(&mut Transform, &Tumble), With<Asteroid>>, time: Res<Time>) {
+    // 对查询结果进行迭代，每次迭代得到一个小行星的变换和翻滚的角速度
+    for (mut transform, tumble) in query.iter_mut() {
+        let angle = tumble.angular_velocity * time.delta_seconds();
+        transform.rotate_local(Quat::from_euler(EulerRot::XYZ, angle.x, angle.y, angle.z));
+    }
+}
+
+// 这个函数让磁性小行星把附近的飞船拉向自己，拉力产生的加速度随距离线性减小，在 `radius` 处为零
+// 拉力和其他外力一样改变飞船的外力速度，之后的积分、连续碰撞检测和速度倍数都会作用于它
+fn apply_magnetic_pull(
+    asteroid_query: Query<(&Transform, &AsteroidBehavior), With<Asteroid>>,
+    mut spaceship_query: Query<(&Transform, &mut ExternalVelocity), With<Spaceship>>,
+    time: Res<Time>,
+) {
+    for (asteroid_transform, behavior) in asteroid_query.iter() {
+        let AsteroidBehavior::Magnet { radius, strength } = *behavior else {
+            continue;
+        };
+        for (spaceship_transform, mut external) in spaceship_query.iter_mut() {
+            let offset = asteroid_transform.translation - spaceship_transform.translation;
+            let distance = offset.length();
+            if distance >= radius {
+                continue;
+            }
+            let acceleration = strength * (1.0 - distance / radius);
+            external.value += offset.normalize_or_zero() * acceleration * time.delta_seconds();
+        }
+    }
+}
+
+// `DestroyedAsteroidQuery` 查询触发被摧毁的小行星的行为所需的组件
+type DestroyedAsteroidQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static Transform,
+        &'static Velocity,
+        &'static Collider,
+        &'static AsteroidKind,
+        &'static AsteroidBehavior,
+        Option<&'static mut Health>,
+    ),
+    With<Asteroid>,
+>;
+
+// 这个函数在小行星被摧毁时触发它的行为：分裂的小行星生成向外飞散的碎片，爆炸的小行星伤害附近的小行星
+// 被炸毁的小行星同样发送 `EntityDestroyed` 事件并在这里继续处理，因此爆炸可以连锁，每颗小行星只处理一次
+// 连锁摧毁的小行星算作引爆它的玩家击毁的
+fn trigger_destroyed_asteroids(
+    mut commands: Commands,
+    mut query: DestroyedAsteroidQuery,
+    mut events: ResMut<Events<EntityDestroyed>>,
+    mut reader: Local<ManualEventReader<EntityDestroyed>>,
+    mut rng: ResMut<GameRng>,
+    variants: Res<AsteroidVariants>,
+    scene_assets: Res<SceneAssets>,
+) {
+    let mut handled = HashSet::new();
+    loop {
+        let batch: Vec<EntityDestroyed> = reader
+            .read(&events)
+            .filter(|event| event.kind == DestroyedKind::Asteroid && handled.insert(event.entity))
+            .copied()
+            .collect();
+        if batch.is_empty() {
+            break;
+        }
+        for event in batch {
+            let Ok((_, transform, velocity, collider, &kind, &behavior, _)) =
+                query.get(event.entity)
+            else {
+                continue;
+            };
+            let (translation, velocity, radius, scale) = (
+                transform.translation,
+                velocity.value,
+                collider.radius,
+                transform.scale.x,
+            );
+            match behavior {
+                AsteroidBehavior::Split {
+                    count,
+                    scale: shard_scale,
+                } => {
+                    let Some(variant) = variants.get(kind) else {
+                        continue;
+                    };
+                    // 碎片和原来的小行星是同一种类，但没有特殊行为，只需要一发导弹就能摧毁
+                    let shard = AsteroidVariant {
+                        scale: (scale * shard_scale, scale * shard_scale),
+                        health: 1.0,
+                        behavior: AsteroidBehavior::Inert,
+                        ..variant.clone()
+                    };
+                    let offset = rng.gen_range(0.0..TAU);
+                    for index in 0..count {
+                        let angle = offset + TAU * index as f32 / count as f32;
+                        let outward = Vec3::new(angle.cos(), 0.0, angle.sin());
+                        commands.spawn((
+                            asteroid_bundle(
+                                scene_assets.asteroid.clone(),
+                                &shard,
+                                translation + outward * radius * 0.5,
+                                velocity + outward * SHARD_SPEED,
+                                Vec3::ZERO,
+                                rng.as_mut(),
+                            ),
+                            SpawnGrace::from_seconds(SHARD_GRACE_SECONDS),
+                        ));
+                    }
+                }
+                AsteroidBehavior::Explode {
+                    radius: blast_radius,
+                    damage,
+                } => {
+                    let neighbors: Vec<Entity> = query
+                        .iter()
+                        .filter(|(entity, neighbor_transform, _, neighbor_collider, ..)| {
+                            !handled.contains(entity)
+                                && neighbor_transform.translation.distance(translation)
+                                    - neighbor_collider.radius
+                                    <= blast_radius
+                        })
+                        .map(|(entity, ..)| entity)
+                        .collect();
+                    for neighbor in neighbors {
+                        let Ok((_, neighbor_transform, _, neighbor_collider, _, _, health)) =
+                            query.get_mut(neighbor)
+                        else {
+                            continue;
+                        };
+                        if let Some(mut health) = health {
+                            if !health.damage(damage) {
+                                continue;
+                            }
+                        }
+                        commands.entity(neighbor).despawn_recursive();
+                        events.send(EntityDestroyed {
+                            entity: neighbor,
+                            kind: DestroyedKind::Asteroid,
+                            translation: neighbor_transform.translation,
+                            radius: neighbor_collider.radius,
+                            by_player: event.by_player,
+                        });
+                    }
+                }
+                AsteroidBehavior::Inert | AsteroidBehavior::Magnet { .. } => {}
+            }
+        }
+    }
+}
+
+// `TintedMaterials` 记录每种原始材质染成各个种类的颜色后的材质，使同一种小行星共用材质
+type TintedMaterials = HashMap<(AssetId<StandardMaterial>, AsteroidKind), Handle<StandardMaterial>>;
+
+// 这个函数在小行星模型的网格加载完成后，把网格的材质替换为乘以种类颜色的材质
+// 没有材质资源（例如无窗口模拟）时不做任何事，网络客户端也用它为同步来的小行星染色
+pub fn tint_asteroids(
+    mut commands: Commands,
+    query: Query<(Entity, &Handle<StandardMaterial>), Added<Handle<StandardMaterial>>>,
+    parent_query: Query<&Parent>,
+    kind_query: Query<&AsteroidKind>,
+    variants: Res<AsteroidVariants>,
+    materials: Option<ResMut<Assets<StandardMaterial>>>,
+    mut tinted: Local<TintedMaterials>,
+) {
+    let Some(mut materials) = materials else {
+        return;
+    };
+    for (entity, material) in query.iter() {
+        let Some(&kind) = parent_query
+            .iter_ancestors(entity)
+            .find_map(|ancestor| kind_query.get(ancestor).ok())
+        else {
+            continue;
+        };
+        // 替换后的材质也会触发 `Added`，已经染过色的材质不再处理
+        if tinted.values().any(|handle| handle.id() == material.id()) {
+            continue;
+        }
+        let Some(variant) = variants.get(kind) else {
+            continue;
+        };
+        let handle = match tinted.get(&(material.id(), kind)) {
+            Some(handle) => handle.clone(),
+            None => {
+                let Some(original) = materials.get(material) else {
+                    continue;
+                };
+                let tinted_material = StandardMaterial {
+                    base_color: original.base_color * variant.tint.as_rgba_f32(),
+                    ..original.clone()
+                };
+                let handle = materials.add(tinted_material);
+                tinted.insert((material.id(), kind), handle.clone());
+                handle
+            }
+        };
+        commands.entity(entity).insert(handle);
     }
 }
@@ -0,0 +1,339 @@
+use bevy::{
+    audio::{SpatialScale, Volume},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    asset_loader::AudioAssets,
+    camera::{MainCamera, PlayerCamera},
+    collision_detection::{DestroyedKind, EntityDestroyed},
+    enemy::EnemyProjectile,
+    pickup::PickupCollected,
+    player::{PlayerId, PlayerInput},
+    schedule::InGameSet,
+    spaceship::{Spaceship, SpaceshipMissile, SpaceshipShield},
+    wave::Wave,
+};
+
+// 定义声音位置的缩放比例，游戏世界的单位较大，缩小后左右声道的差别才比较自然
+const SPATIAL_SCALE: f32 = 0.05;
+
+// 定义听者左右耳之间的距离，单位为游戏世界的单位
+const EAR_GAP: f32 = 30.0;
+
+// 定义发射子弹和导弹的音量
+const FIRE_VOLUME: f32 = 0.4;
+
+// 定义小行星等普通实体爆炸的音量，飞船和首领爆炸的音量更大
+const EXPLOSION_VOLUME: f32 = 0.6;
+const LARGE_EXPLOSION_VOLUME: f32 = 1.0;
+
+// 定义开启护盾的音量
+const SHIELD_VOLUME: f32 = 0.5;
+
+// 定义拾取道具的音量
+const PICKUP_VOLUME: f32 = 0.7;
+
+// 定义引擎在没有推力时的音量和推力最大时的音量
+const ENGINE_IDLE_VOLUME: f32 = 0.1;
+const ENGINE_MAX_VOLUME: f32 = 0.5;
+
+// 定义引擎推力最大时的播放速度，使引擎声音的音调随推力升高
+const ENGINE_MAX_SPEED: f32 = 1.3;
+
+// 定义激烈层音乐的音量达到最大时的波数
+const MUSIC_FULL_INTENSITY_WAVE: u32 = 8;
+
+// `AudioVolume` 资源保存各类声音的音量，最终的音量为总音量乘以对应类别的音量
+#[derive(Resource, Reflect, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[reflect(Resource)]
+pub struct AudioVolume {
+    pub master: f32,
+    pub music: f32,
+    pub effects: f32,
+}
+
+impl Default for AudioVolume {
+    fn default() -> Self {
+        Self {
+            master: 1.0,
+            music: 0.6,
+            effects: 1.0,
+        }
+    }
+}
+
+impl AudioVolume {
+    // 返回音乐的最终音量
+    pub fn music(&self) -> f32 {
+        self.master * self.music
+    }
+
+    // 返回音效的最终音量
+    pub fn effects(&self) -> f32 {
+        self.master * self.effects
+    }
+}
+
+// `FiredFilter` 筛选刚刚发射的导弹和敌方子弹
+type FiredFilter = Or<(Added<SpaceshipMissile>, Added<EnemyProjectile>)>;
+
+// `MusicLayer` 组件标记播放音乐的实体，基础层一直播放，激烈层的音量随波数增加
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq, Eq)]
+#[reflect(Component)]
+pub enum MusicLayer {
+    Base,
+    Intense,
+}
+
+// `EngineSound` 组件标记飞船的子实体，它循环播放飞船的引擎声音
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+pub struct EngineSound;
+
+// 定义一个名为 `GameAudioPlugin` 的结构体
+// 它在发射、爆炸、开启护盾和拾取道具时播放音效，为每艘飞船循环播放随推力变化的引擎声音，并播放随波数变得激烈的音乐
+// 音效根据相对于 `MainCamera` 的位置在左右声道之间移动，分屏模式下以玩家一的摄像机为准
+// 使用它的应用需要提供 `AudioAssets` 资源
+pub struct GameAudioPlugin;
+
+impl Plugin for GameAudioPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<AudioVolume>()
+            .register_type::<MusicLayer>()
+            .register_type::<EngineSound>()
+            .init_resource::<AudioVolume>()
+            .add_event::<EntityDestroyed>()
+            .add_event::<PickupCollected>()
+            .add_systems(Startup, start_music)
+            .add_systems(
+                Update,
+                (
+                    add_spatial_listener,
+                    (
+                        play_fire_sounds,
+                        play_shield_sounds,
+                        play_explosion_sounds,
+                        play_pickup_sounds,
+                        add_engine_sounds,
+                    ),
+                    update_engine_sounds,
+                    update_music,
+                )
+                    .chain()
+                    .after(InGameSet::CollisionDetection),
+            );
+    }
+}
+
+// 这个函数返回空间音效的播放设置
+fn spatial_settings(mode: PlaybackSettings, volume: f32) -> PlaybackSettings {
+    PlaybackSettings {
+        spatial: true,
+        spatial_scale: Some(SpatialScale::new(SPATIAL_SCALE)),
+        ..mode.with_volume(Volume::new(volume))
+    }
+}
+
+// 这个函数在 `translation` 处播放一次音效，播放结束后音效实体会被删除，没有音频文件时什么都不做
+fn play_sound(
+    commands: &mut Commands,
+    source: &Option<Handle<AudioSource>>,
+    volume: f32,
+    translation: Vec3,
+) {
+    let Some(source) = source else {
+        return;
+    };
+    commands.spawn((
+        AudioBundle {
+            source: source.clone(),
+            settings: spatial_settings(PlaybackSettings::DESPAWN, volume),
+        },
+        TransformBundle::from_transform(Transform::from_translation(translation)),
+    ));
+}
+
+// 这个函数开始循环播放两层音乐，激烈层一开始是静音的，没有音频文件的层被跳过
+fn start_music(mut commands: Commands, audio_assets: Res<AudioAssets>, volume: Res<AudioVolume>) {
+    for (layer, source, layer_volume) in [
+        (MusicLayer::Base, &audio_assets.music, volume.music()),
+        (MusicLayer::Intense, &audio_assets.music_intense, 0.0),
+    ] {
+        let Some(source) = source else {
+            continue;
+        };
+        commands.spawn((
+            AudioBundle {
+                source: source.clone(),
+                settings: PlaybackSettings::LOOP.with_volume(Volume::new(layer_volume)),
+            },
+            layer,
+        ));
+    }
+}
+
+// 这个函数为主摄像机添加听者，只有一台摄像机可以作为听者，分屏模式下选择玩家一的摄像机
+fn add_spatial_listener(
+    mut commands: Commands,
+    cameras: Query<(Entity, Option<&PlayerCamera>), With<MainCamera>>,
+    listeners: Query<(), With<SpatialListener>>,
+) {
+    if !listeners.is_empty() {
+        return;
+    }
+    let camera = cameras
+        .iter()
+        .find(|(_, player_camera)| player_camera.map_or(true, |camera| camera.0 == PlayerId(0)));
+    if let Some((entity, _)) = camera {
+        commands
+            .entity(entity)
+            .insert(SpatialListener::new(EAR_GAP));
+    }
+}
+
+// 这个函数在飞船发射导弹或敌人发射子弹时播放发射音效
+fn play_fire_sounds(
+    mut commands: Commands,
+    query: Query<&Transform, FiredFilter>,
+    audio_assets: Res<AudioAssets>,
+    volume: Res<AudioVolume>,
+) {
+    for transform in query.iter() {
+        play_sound(
+            &mut commands,
+            &audio_assets.fire,
+            FIRE_VOLUME * volume.effects(),
+            transform.translation,
+        );
+    }
+}
+
+// 这个函数在飞船开启护盾时播放护盾音效
+fn play_shield_sounds(
+    mut commands: Commands,
+    query: Query<&GlobalTransform, Added<SpaceshipShield>>,
+    audio_assets: Res<AudioAssets>,
+    volume: Res<AudioVolume>,
+) {
+    for transform in query.iter() {
+        play_sound(
+            &mut commands,
+            &audio_assets.shield,
+            SHIELD_VOLUME * volume.effects(),
+            transform.translation(),
+        );
+    }
+}
+
+// 这个函数在实体被摧毁时播放爆炸音效，飞船和首领的爆炸声音更大
+fn play_explosion_sounds(
+    mut commands: Commands,
+    mut destroyed_events: EventReader<EntityDestroyed>,
+    audio_assets: Res<AudioAssets>,
+    volume: Res<AudioVolume>,
+) {
+    for event in destroyed_events.read() {
+        let explosion_volume = match event.kind {
+            DestroyedKind::Spaceship | DestroyedKind::Boss => LARGE_EXPLOSION_VOLUME,
+            _ => EXPLOSION_VOLUME,
+        };
+        play_sound(
+            &mut commands,
+            &audio_assets.explosion,
+            explosion_volume * volume.effects(),
+            event.translation,
+        );
+    }
+}
+
+// 这个函数在玩家拾取道具时播放拾取音效
+fn play_pickup_sounds(
+    mut commands: Commands,
+    mut pickup_events: EventReader<PickupCollected>,
+    audio_assets: Res<AudioAssets>,
+    volume: Res<AudioVolume>,
+) {
+    for event in pickup_events.read() {
+        play_sound(
+            &mut commands,
+            &audio_assets.pickup,
+            PICKUP_VOLUME * volume.effects(),
+            event.translation,
+        );
+    }
+}
+
+// 这个函数为新生成的飞船添加循环播放引擎声音的子实体，没有引擎音频文件时什么都不做
+fn add_engine_sounds(
+    mut commands: Commands,
+    query: Query<Entity, Added<Spaceship>>,
+    audio_assets: Res<AudioAssets>,
+    volume: Res<AudioVolume>,
+) {
+    let Some(engine) = &audio_assets.engine else {
+        return;
+    };
+    for spaceship in query.iter() {
+        let sound = commands
+            .spawn((
+                AudioBundle {
+                    source: engine.clone(),
+                    settings: spatial_settings(
+                        PlaybackSettings::LOOP,
+                        ENGINE_IDLE_VOLUME * volume.effects(),
+                    ),
+                },
+                SpatialBundle::default(),
+                EngineSound,
+            ))
+            .id();
+        commands.entity(spaceship).add_child(sound);
+    }
+}
+
+// 这个函数根据飞船的推力调整引擎声音的音量和音调，飞船不存在时删除引擎声音
+fn update_engine_sounds(
+    mut commands: Commands,
+    sounds: Query<(Entity, &Parent, Option<&SpatialAudioSink>), With<EngineSound>>,
+    spaceships: Query<Option<&PlayerInput>, With<Spaceship>>,
+    volume: Res<AudioVolume>,
+) {
+    for (entity, parent, sink) in sounds.iter() {
+        let Ok(input) = spaceships.get(parent.get()) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+        // 音频资源加载完成后才会创建 `SpatialAudioSink`
+        let Some(sink) = sink else {
+            continue;
+        };
+        let thrust = input.map_or(0.0, |input| input.thrust.abs());
+        let engine_volume = ENGINE_IDLE_VOLUME + (ENGINE_MAX_VOLUME - ENGINE_IDLE_VOLUME) * thrust;
+        sink.set_volume(engine_volume * volume.effects());
+        sink.set_speed(1.0 + (ENGINE_MAX_SPEED - 1.0) * thrust);
+    }
+}
+
+// 这个函数返回激烈层音乐的相对音量，从第一波的 0.0 逐渐增加到 `MUSIC_FULL_INTENSITY_WAVE` 波的 1.0
+pub fn music_intensity(wave: u32) -> f32 {
+    (wave.saturating_sub(1) as f32 / (MUSIC_FULL_INTENSITY_WAVE - 1) as f32).min(1.0)
+}
+
+// 这个函数根据当前的波数和音量设置调整两层音乐的音量
+// 没有波数资源（例如网络客户端）时只播放基础层
+fn update_music(
+    query: Query<(&MusicLayer, &AudioSink)>,
+    wave: Option<Res<Wave>>,
+    volume: Res<AudioVolume>,
+) {
+    let intensity = wave.map_or(0.0, |wave| music_intensity(wave.number));
+    for (layer, sink) in query.iter() {
+        let layer_volume = match layer {
+            MusicLayer::Base => 1.0,
+            MusicLayer::Intense => intensity,
+        };
+        sink.set_volume(layer_volume * volume.music());
+    }
+}
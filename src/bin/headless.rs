@@ -0,0 +1,64 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+
+use spaceship_game::{
+    asset_loader::SceneAssets,
+    player::PlayerConfig,
+    simulation::{InputScript, SimulationPlugin, SimulationStats},
+    telemetry::{TelemetryConfig, TelemetryPlugin},
+    GameplayPlugins,
+};
+
+// 定义默认运行的帧数
+const DEFAULT_TICKS: u64 = 600;
+
+// 定义每一帧的固定时长，单位为秒
+const TICK_SECONDS: f64 = 1.0 / 60.0;
+
+// 无窗口的游戏模拟，不需要显卡，可以用于自动化测试和机器人
+// 用法：`cargo run --bin headless -- [--ticks <帧数>] [--script "<输入脚本>"]`
+// 输入脚本的格式见 `InputScript::parse`，模拟结束后把统计数据输出到标准输出
+// 遥测参数（例如 `--telemetry asteroid --telemetry-out trajectories.csv`）见 `TelemetryConfig::from_args`
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let ticks = arg_value(&args, "--ticks")
+        .map(|ticks| ticks.parse().expect("`--ticks` 必须是一个非负整数"))
+        .unwrap_or(DEFAULT_TICKS);
+    let script = arg_value(&args, "--script")
+        .map(|script| InputScript::parse(script).unwrap_or_else(|error| panic!("{}", error)))
+        .unwrap_or_default();
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugins((TransformPlugin, HierarchyPlugin))
+        // 每一帧都前进固定的时长，使模拟结果与机器的运行速度无关
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+            TICK_SECONDS,
+        )))
+        // 模拟不加载模型，使用空的场景句柄
+        .init_resource::<SceneAssets>()
+        .init_resource::<PlayerConfig>()
+        .insert_resource(script)
+        .add_plugins(GameplayPlugins)
+        .add_plugins(SimulationPlugin);
+    if let Some(config) =
+        TelemetryConfig::from_args(&args).unwrap_or_else(|error| panic!("{}", error))
+    {
+        app.insert_resource(config).add_plugins(TelemetryPlugin);
+    }
+
+    for _ in 0..ticks {
+        app.update();
+    }
+
+    println!("{}", app.world.resource::<SimulationStats>());
+}
+
+// 从命令行参数中读取 `name` 后面的值
+fn arg_value<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|arg| arg == name)
+        .and_then(|index| args.get(index + 1))
+        .map(String::as_str)
+}
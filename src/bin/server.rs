@@ -0,0 +1,45 @@
+use std::time::Duration;
+
+use bevy::{app::ScheduleRunnerPlugin, prelude::*};
+
+use spaceship_game::{
+    asset_loader::SceneAssets,
+    network::{NetworkServerPlugin, ServerSocket, DEFAULT_SERVER_ADDR},
+    player::PlayerConfig,
+    GameplayPlugins,
+};
+
+// 定义服务器每秒运行的帧数
+const TICKS_PER_SECOND: f64 = 60.0;
+
+// 无窗口的专用服务器，运行与单机游戏相同的移动和碰撞系统，并把世界状态同步给客户端
+// 用法：`cargo run --bin server -- [监听地址]`，默认监听 `127.0.0.1:5000`
+fn main() {
+    let addr = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| DEFAULT_SERVER_ADDR.to_string());
+    let socket = ServerSocket::bind(&addr).expect("无法绑定服务器地址");
+
+    App::new()
+        .add_plugins(
+            MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(Duration::from_secs_f64(
+                1.0 / TICKS_PER_SECOND,
+            ))),
+        )
+        .add_plugins((
+            bevy::log::LogPlugin::default(),
+            TransformPlugin,
+            HierarchyPlugin,
+        ))
+        // 服务器不加载模型，使用空的场景句柄
+        .init_resource::<SceneAssets>()
+        // 服务器没有本地玩家，飞船在客户端加入时生成
+        .insert_resource(PlayerConfig {
+            player_count: 0,
+            friendly_fire: true,
+        })
+        .insert_resource(socket)
+        .add_plugins(GameplayPlugins)
+        .add_plugins(NetworkServerPlugin)
+        .run();
+}
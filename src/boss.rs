@@ -0,0 +1,484 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    asset_loader::SceneAssets,
+    collision_detection::{Collider, DestroyedKind, EntityDestroyed},
+    difficulty::DifficultyTuning,
+    enemy::{arrive, EnemyProjectile},
+    health::{Health, MISSILE_DAMAGE},
+    movement::{Acceleration, MovingObjectBundle, Velocity},
+    player::PlayerId,
+    schedule::InGameSet,
+    spaceship::{MissileOwner, Spaceship, SpaceshipMissile},
+    wave::{Wave, WaveStarted},
+};
+
+// 定义首领生成的位置，首领从场地上方边缘进入
+const SPAWN_TRANSLATION: Vec3 = Vec3::new(0.0, 0.0, 70.0);
+
+// 定义首领最终停留的位置
+const HOLD_TRANSLATION: Vec3 = Vec3::new(0.0, 0.0, 20.0);
+
+// 定义首领的最大速度
+const BOSS_MAX_SPEED: f32 = 6.0;
+
+// 定义首领的最大加速度
+const BOSS_MAX_FORCE: f32 = 4.0;
+
+// 定义首领抵达停留位置时开始减速的距离
+const BOSS_SLOWING_RADIUS: f32 = 20.0;
+
+// 定义首领的旋转速度，狂暴阶段会加倍
+const BOSS_ROTATE_SPEED: f32 = 0.4;
+
+// 定义首领发射子弹时子弹生成位置到首领中心的距离，需要位于所有部件之外
+const PROJECTILE_SPAWN_DISTANCE: f32 = 16.0;
+
+// 定义首领子弹的速度
+const PROJECTILE_SPEED: f32 = 20.0;
+
+// 定义首领子弹的半径
+const PROJECTILE_RADIUS: f32 = 1.0;
+
+// 定义环形弹幕的子弹数量
+const RING_PROJECTILE_COUNT: usize = 12;
+
+// 定义瞄准射击时散射子弹之间的夹角，单位为弧度
+const SPREAD_ANGLE: f32 = 0.2;
+
+// `BossPartKind` 表示首领部件的种类
+#[derive(Reflect, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BossPartKind {
+    // 核心，核心被摧毁时首领死亡，在所有装甲被摧毁之前不会受到伤害
+    Core,
+    // 装甲，保护核心
+    Armor,
+    // 弱点，受到的伤害加倍
+    WeakPoint,
+}
+
+// `BossPartSpec` 结构体描述首领的一个部件
+struct BossPartSpec {
+    kind: BossPartKind,
+    offset: Vec3,
+    radius: f32,
+    health: f32,
+    scale: f32,
+}
+
+// 定义首领由哪些部件组成
+const BOSS_PARTS: [BossPartSpec; 5] = [
+    BossPartSpec {
+        kind: BossPartKind::Core,
+        offset: Vec3::ZERO,
+        radius: 6.0,
+        health: 40.0,
+        scale: 2.4,
+    },
+    BossPartSpec {
+        kind: BossPartKind::Armor,
+        offset: Vec3::new(10.0, 0.0, 0.0),
+        radius: 4.0,
+        health: 25.0,
+        scale: 1.6,
+    },
+    BossPartSpec {
+        kind: BossPartKind::Armor,
+        offset: Vec3::new(-10.0, 0.0, 0.0),
+        radius: 4.0,
+        health: 25.0,
+        scale: 1.6,
+    },
+    BossPartSpec {
+        kind: BossPartKind::WeakPoint,
+        offset: Vec3::new(5.0, 0.0, -8.0),
+        radius: 2.0,
+        health: 10.0,
+        scale: 0.8,
+    },
+    BossPartSpec {
+        kind: BossPartKind::WeakPoint,
+        offset: Vec3::new(-5.0, 0.0, -8.0),
+        radius: 2.0,
+        health: 10.0,
+        scale: 0.8,
+    },
+];
+
+// 弱点受到的伤害倍数
+const WEAK_POINT_DAMAGE_MULTIPLIER: f32 = 3.0;
+
+// `BossPhase` 表示首领的攻击阶段，随着首领的总生命值降低而推进
+#[derive(Reflect, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BossPhase {
+    // 定期发射环形弹幕
+    Ring,
+    // 定期向玩家发射散射子弹
+    Aimed,
+    // 同时使用两种攻击，并且旋转得更快
+    Enraged,
+}
+
+impl BossPhase {
+    // 根据剩余生命值的比例返回对应的阶段
+    fn from_health_fraction(fraction: f32) -> Self {
+        if fraction > 2.0 / 3.0 {
+            Self::Ring
+        } else if fraction > 1.0 / 3.0 {
+            Self::Aimed
+        } else {
+            Self::Enraged
+        }
+    }
+
+    // 返回该阶段的攻击间隔，单位为秒
+    fn attack_interval(self) -> f32 {
+        match self {
+            Self::Ring => 3.0,
+            Self::Aimed => 1.5,
+            Self::Enraged => 1.0,
+        }
+    }
+}
+
+// `Boss` 组件位于首领的根实体上，根实体本身没有碰撞器，碰撞器位于各个部件上
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+pub struct Boss {
+    pub phase: BossPhase,
+    // 所有部件的最大生命值之和
+    pub max_health: f32,
+    attack_timer: Timer,
+}
+
+// `BossPart` 组件位于首领的子实体上
+#[derive(Component, Reflect, Debug, Clone, Copy)]
+#[reflect(Component)]
+pub struct BossPart {
+    pub kind: BossPartKind,
+}
+
+// `BossHealthBar` 组件标记首领血条的填充部分
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+pub struct BossHealthBar;
+
+// `BossHealthBarRoot` 组件标记首领血条的根节点，首领死亡时一起销毁
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+pub struct BossHealthBarRoot;
+
+pub struct BossPlugin;
+
+impl Plugin for BossPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Boss>()
+            .register_type::<BossPart>()
+            .register_type::<BossPartKind>()
+            .register_type::<BossPhase>()
+            .register_type::<BossHealthBar>()
+            .register_type::<BossHealthBarRoot>()
+            .register_type::<Health>()
+            .register_type::<TimerMode>()
+            .init_resource::<DifficultyTuning>()
+            .add_systems(
+                Update,
+                (
+                    spawn_boss,
+                    move_boss,
+                    update_boss_phase,
+                    boss_attacks,
+                    update_boss_health_bar,
+                )
+                    .chain()
+                    .in_set(InGameSet::EntityUpdates),
+            )
+            .add_systems(
+                Update,
+                handle_boss_part_collisions.in_set(InGameSet::DespawnEntities),
+            );
+    }
+}
+
+// 这个函数在首领波开始时生成首领
+fn spawn_boss(
+    mut commands: Commands,
+    mut wave_started: EventReader<WaveStarted>,
+    wave: Res<Wave>,
+    query: Query<(), With<Boss>>,
+    scene_assets: Res<SceneAssets>,
+) {
+    if wave_started.read().last().is_none() || !wave.is_boss_wave() || !query.is_empty() {
+        return;
+    }
+    info!("首领出现在第 {} 波", wave.number);
+
+    let max_health = BOSS_PARTS.iter().map(|part| part.health).sum();
+    let phase = BossPhase::Ring;
+    commands
+        .spawn((
+            SpatialBundle::from_transform(Transform::from_translation(SPAWN_TRANSLATION)),
+            Velocity::new(Vec3::ZERO),
+            Acceleration::new(Vec3::ZERO),
+            Boss {
+                phase,
+                max_health,
+                attack_timer: Timer::from_seconds(phase.attack_interval(), TimerMode::Repeating),
+            },
+        ))
+        .with_children(|parent| {
+            // 每个部件都是一个带有碰撞器和生命值的子实体，碰撞检测使用它们的全局变换
+            for part in BOSS_PARTS.iter() {
+                parent.spawn((
+                    SceneBundle {
+                        scene: scene_assets.asteroid.clone(),
+                        transform: Transform::from_translation(part.offset)
+                            .with_scale(Vec3::splat(part.scale)),
+                        ..default()
+                    },
+                    Collider::new(part.radius),
+                    Health::new(part.health),
+                    BossPart { kind: part.kind },
+                ));
+            }
+        });
+
+    spawn_boss_health_bar(&mut commands);
+}
+
+// 这个函数在屏幕顶部生成首领的血条，网络客户端也用它显示同步而来的首领的血条
+pub fn spawn_boss_health_bar(commands: &mut Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(16.0),
+                    left: Val::Percent(25.0),
+                    width: Val::Percent(50.0),
+                    height: Val::Px(12.0),
+                    ..default()
+                },
+                background_color: Color::rgba(0.2, 0.2, 0.2, 0.8).into(),
+                ..default()
+            },
+            BossHealthBarRoot,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Percent(100.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    background_color: Color::rgb(0.9, 0.1, 0.2).into(),
+                    ..default()
+                },
+                BossHealthBar,
+            ));
+        });
+}
+
+// 这个函数让首领移动到停留位置，并缓慢旋转
+fn move_boss(
+    mut query: Query<(&mut Transform, &Velocity, &mut Acceleration, &Boss)>,
+    time: Res<Time>,
+) {
+    for (mut transform, velocity, mut acceleration, boss) in query.iter_mut() {
+        acceleration.value = arrive(
+            transform.translation,
+            velocity.value,
+            HOLD_TRANSLATION,
+            BOSS_MAX_SPEED,
+            BOSS_SLOWING_RADIUS,
+        )
+        .clamp_length_max(BOSS_MAX_FORCE);
+
+        let rotate_speed = match boss.phase {
+            BossPhase::Enraged => BOSS_ROTATE_SPEED * 2.0,
+            _ => BOSS_ROTATE_SPEED,
+        };
+        transform.rotate_y(rotate_speed * time.delta_seconds());
+    }
+}
+
+// 这个函数计算首领剩余的总生命值，推进攻击阶段，并在核心被摧毁时销毁首领
+fn update_boss_phase(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Boss, &Children)>,
+    part_query: Query<(&BossPart, &Health)>,
+    health_bar_query: Query<Entity, With<BossHealthBarRoot>>,
+) {
+    for (entity, mut boss, children) in query.iter_mut() {
+        let parts: Vec<_> = part_query.iter_many(children).collect();
+        let core_alive = parts
+            .iter()
+            .any(|(part, health)| part.kind == BossPartKind::Core && !health.is_depleted());
+        if !core_alive {
+            info!("首领被击败");
+            commands.entity(entity).despawn_recursive();
+            for health_bar in health_bar_query.iter() {
+                commands.entity(health_bar).despawn_recursive();
+            }
+            continue;
+        }
+
+        let health: f32 = parts.iter().map(|(_, health)| health.current).sum();
+        let phase = BossPhase::from_health_fraction(health / boss.max_health);
+        if phase != boss.phase {
+            info!("首领进入 {:?} 阶段", phase);
+            boss.phase = phase;
+            boss.attack_timer = Timer::from_seconds(phase.attack_interval(), TimerMode::Repeating);
+        }
+    }
+}
+
+// 这个函数根据首领当前的阶段发射子弹
+fn boss_attacks(
+    mut commands: Commands,
+    mut query: Query<(&Transform, &mut Boss)>,
+    spaceship_query: Query<&GlobalTransform, With<Spaceship>>,
+    time: Res<Time>,
+    scene_assets: Res<SceneAssets>,
+) {
+    for (transform, mut boss) in query.iter_mut() {
+        boss.attack_timer.tick(time.delta());
+        if !boss.attack_timer.just_finished() {
+            continue;
+        }
+        let position = transform.translation;
+
+        let mut directions = vec![];
+        // 环形弹幕：向四周均匀地发射子弹
+        if matches!(boss.phase, BossPhase::Ring | BossPhase::Enraged) {
+            let (_, yaw, _) = transform.rotation.to_euler(EulerRot::YXZ);
+            directions.extend((0..RING_PROJECTILE_COUNT).map(|i| {
+                let angle = yaw + TAU * i as f32 / RING_PROJECTILE_COUNT as f32;
+                Vec3::new(angle.cos(), 0.0, angle.sin())
+            }));
+        }
+        // 瞄准射击：向最近的玩家飞船发射三发散射子弹
+        if matches!(boss.phase, BossPhase::Aimed | BossPhase::Enraged) {
+            if let Some(target) = spaceship_query
+                .iter()
+                .map(|spaceship| spaceship.translation())
+                .min_by(|a, b| a.distance(position).total_cmp(&b.distance(position)))
+            {
+                let aim = (target - position).normalize_or_zero();
+                directions.extend(
+                    [-SPREAD_ANGLE, 0.0, SPREAD_ANGLE]
+                        .into_iter()
+                        .map(|angle| Quat::from_rotation_y(angle) * aim),
+                );
+            }
+        }
+
+        for direction in directions {
+            commands.spawn((
+                MovingObjectBundle {
+                    velocity: Velocity::new(direction * PROJECTILE_SPEED),
+                    acceleration: Acceleration::new(Vec3::ZERO),
+                    collider: Collider::new(PROJECTILE_RADIUS),
+                    model: SceneBundle {
+                        scene: scene_assets.missiles.clone(),
+                        transform: Transform::from_translation(
+                            position + direction * PROJECTILE_SPAWN_DISTANCE,
+                        ),
+                        ..default()
+                    },
+                },
+                EnemyProjectile,
+            ));
+        }
+    }
+}
+
+// 这个函数根据首领剩余的总生命值更新血条的长度
+fn update_boss_health_bar(
+    boss_query: Query<(&Boss, &Children)>,
+    part_query: Query<&Health, With<BossPart>>,
+    mut health_bar_query: Query<&mut Style, With<BossHealthBar>>,
+) {
+    let Ok((boss, children)) = boss_query.get_single() else {
+        return;
+    };
+    let health: f32 = part_query
+        .iter_many(children)
+        .map(|health| health.current)
+        .sum();
+    for mut style in health_bar_query.iter_mut() {
+        style.width = Val::Percent(100.0 * health / boss.max_health);
+    }
+}
+
+// 这个函数用于处理首领部件的碰撞事件
+// 伤害只作用于被击中的部件，而不是整个首领；击中首领的导弹会被销毁
+fn handle_boss_part_collisions(
+    mut commands: Commands,
+    mut query: Query<(
+        Entity,
+        &GlobalTransform,
+        &Collider,
+        &BossPart,
+        &mut Health,
+        &Parent,
+    )>,
+    missile_query: Query<Option<&MissileOwner>, With<SpaceshipMissile>>,
+    mut destroyed: EventWriter<EntityDestroyed>,
+    tuning: Res<DifficultyTuning>,
+) {
+    // 只要还有装甲部件存在，核心就不会受到伤害
+    let armored_bosses: Vec<Entity> = query
+        .iter()
+        .filter(|(_, _, _, part, health, _)| {
+            part.kind == BossPartKind::Armor && !health.is_depleted()
+        })
+        .map(|(.., parent)| parent.get())
+        .collect();
+
+    for (entity, transform, collider, part, mut health, parent) in query.iter_mut() {
+        let hits: Vec<(Entity, Option<PlayerId>)> = collider
+            .colliding_entities
+            .iter()
+            .filter_map(|&collided_entity| {
+                missile_query
+                    .get(collided_entity)
+                    .ok()
+                    .map(|owner| (collided_entity, owner.map(|&MissileOwner(player)| player)))
+            })
+            .collect();
+        let Some(&(_, by_player)) = hits.last() else {
+            continue;
+        };
+        for &(missile, _) in hits.iter() {
+            commands.entity(missile).despawn_recursive();
+        }
+
+        let multiplier = match part.kind {
+            BossPartKind::Core if armored_bosses.contains(&parent.get()) => 0.0,
+            BossPartKind::WeakPoint => WEAK_POINT_DAMAGE_MULTIPLIER,
+            _ => 1.0,
+        } * tuning.damage_scale;
+        if health.is_depleted() || !health.damage(hits.len() as f32 * MISSILE_DAMAGE * multiplier) {
+            continue;
+        }
+        // 核心被摧毁时由 `update_boss_phase` 销毁整个首领，其他部件被摧毁时只销毁该部件
+        let (destroyed_entity, kind) = if part.kind == BossPartKind::Core {
+            (parent.get(), DestroyedKind::Boss)
+        } else {
+            commands.entity(entity).despawn_recursive();
+            (entity, DestroyedKind::BossPart)
+        };
+        destroyed.send(EntityDestroyed {
+            entity: destroyed_entity,
+            kind,
+            translation: transform.translation(),
+            radius: collider.radius,
+            by_player,
+        });
+    }
+}
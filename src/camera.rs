@@ -1,30 +1,162 @@
-use bevy::prelude::*;
+use bevy::{
+    prelude::*,
+    render::camera::{ClearColorConfig, Viewport},
+    window::{PrimaryWindow, WindowResized},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    player::{PlayerConfig, PlayerId},
+    schedule::InGameSet,
+    spaceship::Spaceship,
+};
 
 const CAMERA_DISTANCE: f32 = 80.0;
 
-#[derive(Component, Debug)]
+// 定义分屏模式下摄像机的高度，比单人模式更近，以便看清自己的飞船
+const SPLIT_SCREEN_CAMERA_DISTANCE: f32 = 60.0;
+
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
 pub struct MainCamera;
 
+// `PlayerCamera` 组件标记分屏模式下跟随某一名玩家的摄像机
+#[derive(Component, Reflect, Debug, Clone, Copy)]
+#[reflect(Component)]
+pub struct PlayerCamera(pub PlayerId);
+
+// `CameraMode` 资源决定单人模式下的摄像机是固定俯视整个场地，还是跟随玩家的飞船
+// 分屏模式下每台摄像机总是跟随对应玩家的飞船
+#[derive(Resource, Reflect, Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[reflect(Resource)]
+pub enum CameraMode {
+    #[default]
+    Fixed,
+    Follow,
+}
+
 pub struct CameraPlugin;
 
 impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, spawn_camera);
+        app.register_type::<MainCamera>()
+            .register_type::<PlayerCamera>()
+            .register_type::<CameraMode>()
+            .init_resource::<CameraMode>()
+            .add_systems(Startup, spawn_camera)
+            .add_systems(
+                Update,
+                (
+                    set_camera_viewports,
+                    follow_player.after(InGameSet::EntityUpdates),
+                ),
+            );
+    }
+}
+
+// 定义一个名为 `spawn_camera` 的函数，它接受一个可变的 `Commands` 类型参数和一个 `PlayerConfig` 资源引用参数
+// 这个函数用于生成摄像机实体，单人模式下生成一台，分屏模式下为每名玩家生成一台
+fn spawn_camera(mut commands: Commands, player_config: Res<PlayerConfig>) {
+    // 单人模式下，摄像机的位置被设置为 (0.0, CAMERA_DISTANCE, 0.0)，并且朝向原点，上方向为 Z 轴
+    if !player_config.is_split_screen() {
+        commands.spawn((
+            Camera3dBundle {
+                transform: Transform::from_xyz(0.0, CAMERA_DISTANCE, 0.0)
+                    .looking_at(Vec3::ZERO, Vec3::Z),
+                ..default()
+            },
+            MainCamera,
+        ));
+        return;
+    }
+
+    // 分屏模式下，每台摄像机渲染到窗口的一半，视口在 `set_camera_viewports` 中设置
+    for player in player_config.players() {
+        commands.spawn((
+            Camera3dBundle {
+                transform: Transform::from_xyz(0.0, SPLIT_SCREEN_CAMERA_DISTANCE, 0.0)
+                    .looking_at(Vec3::ZERO, Vec3::Z),
+                camera: Camera {
+                    // 后面的摄像机在前面的摄像机之后渲染，并且不清除已经渲染的画面
+                    order: player.0 as isize,
+                    clear_color: if player.0 == 0 {
+                        ClearColorConfig::Default
+                    } else {
+                        ClearColorConfig::None
+                    },
+                    ..default()
+                },
+                ..default()
+            },
+            MainCamera,
+            PlayerCamera(player),
+        ));
     }
 }
 
-// 定义一个名为 `spawn_camera` 的函数，它接受一个可变的 `Commands` 类型参数
-// 这个函数用于生成一个新的摄像机实体
-fn spawn_camera(mut commands: Commands) {
-    // 使用 `commands` 的 `spawn` 方法来创建一个新的实体
-    // 这个新实体拥有 `Camera3dBundle` 组件和 `MainCamera` 组件
-    // `Camera3dBundle` 组件包含一个 `transform`，它的位置被设置为 (0.0, CAMERA_DISTANCE, 0.0)，并且朝向原点，上方向为 Z 轴
-    commands.spawn((
-        Camera3dBundle {
-            transform: Transform::from_xyz(0.0, CAMERA_DISTANCE, 0.0)
-                .looking_at(Vec3::ZERO, Vec3::Z),
+// 这个函数在窗口大小改变时，把窗口横向平均分给每一台玩家摄像机
+fn set_camera_viewports(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut resize_events: EventReader<WindowResized>,
+    mut query: Query<(&mut Camera, &PlayerCamera)>,
+    player_config: Res<PlayerConfig>,
+) {
+    // 只关心最近一次的窗口大小改变事件
+    if resize_events.read().last().is_none() {
+        return;
+    }
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+
+    let player_count = player_config.players().count().max(1) as u32;
+    let width = window.physical_width() / player_count;
+    let height = window.physical_height();
+    for (mut camera, &PlayerCamera(player)) in query.iter_mut() {
+        camera.viewport = Some(Viewport {
+            physical_position: UVec2::new(player.0 as u32 * width, 0),
+            physical_size: UVec2::new(width, height),
             ..default()
-        },
-        MainCamera,
-    ));
+        });
+    }
+}
+
+// `FollowCameraQuery` 查询可以跟随飞船的摄像机
+type FollowCameraQuery<'w, 's> = Query<
+    'w,
+    's,
+    (&'static mut Transform, Option<&'static PlayerCamera>),
+    (With<MainCamera>, Without<Spaceship>),
+>;
+
+// 这个函数让每台玩家摄像机跟随对应玩家的飞船
+// 单人模式的摄像机只在 `CameraMode::Follow` 下跟随玩家一的飞船，切换回固定模式时回到场地中央
+fn follow_player(
+    mut camera_query: FollowCameraQuery,
+    spaceship_query: Query<(&Transform, &PlayerId), With<Spaceship>>,
+    camera_mode: Res<CameraMode>,
+) {
+    for (mut camera_transform, player_camera) in camera_query.iter_mut() {
+        let player = match player_camera {
+            Some(&PlayerCamera(player)) => player,
+            None if *camera_mode == CameraMode::Follow => PlayerId(0),
+            None => {
+                if camera_mode.is_changed() {
+                    camera_transform.translation.x = 0.0;
+                    camera_transform.translation.z = 0.0;
+                }
+                continue;
+            }
+        };
+        // 如果玩家的飞船已经被销毁，那么摄像机停留在原地
+        let Some((spaceship_transform, _)) = spaceship_query
+            .iter()
+            .find(|(_, &spaceship_player)| spaceship_player == player)
+        else {
+            continue;
+        };
+        camera_transform.translation.x = spaceship_transform.translation.x;
+        camera_transform.translation.z = spaceship_transform.translation.z;
+    }
 }
@@ -0,0 +1,198 @@
+use bevy::prelude::*;
+
+// 定义 GJK 算法的最大迭代次数
+const GJK_MAX_ITERATIONS: usize = 64;
+
+// `ColliderShape` 表示碰撞器的形状，所有形状都以实体的全局位置为中心，并随实体的全局旋转一起旋转
+// 形状的尺寸以世界单位表示，不受实体缩放的影响
+#[derive(Reflect, Debug, Clone, PartialEq)]
+pub enum ColliderShape {
+    // 球体，半径保存在 `Collider::radius` 中
+    Sphere,
+    // 胶囊体，中心线沿本地 Z 轴，长度为 `2 * half_length`，两端是半径为 `radius` 的半球
+    Capsule { radius: f32, half_length: f32 },
+    // 有向包围盒，`half_extents` 是沿本地 X、Y、Z 轴的半边长
+    Cuboid { half_extents: Vec3 },
+    // 凸包，由本地坐标系中的一组顶点定义，顶点不能全部位于同一个平面上
+    ConvexHull { points: Vec<Vec3> },
+}
+
+impl ColliderShape {
+    // 返回能够包住这个形状的最小球体的半径，用于粗略检测
+    // 球体没有额外的尺寸，因此返回 `None`
+    pub fn bounding_radius(&self) -> Option<f32> {
+        match self {
+            Self::Sphere => None,
+            Self::Capsule {
+                radius,
+                half_length,
+            } => Some(radius + half_length),
+            Self::Cuboid { half_extents } => Some(half_extents.length()),
+            Self::ConvexHull { points } => {
+                points.iter().map(|point| point.length()).reduce(f32::max)
+            }
+        }
+    }
+
+    // 支撑函数：返回形状在 `direction` 方向上最远的点，`radius` 只用于球体
+    fn support(&self, radius: f32, translation: Vec3, rotation: Quat, direction: Vec3) -> Vec3 {
+        let direction = direction.normalize_or_zero();
+        match self {
+            Self::Sphere => translation + direction * radius,
+            Self::Capsule {
+                radius,
+                half_length,
+            } => {
+                let axis = rotation * Vec3::Z * *half_length;
+                let end = if axis.dot(direction) >= 0.0 {
+                    axis
+                } else {
+                    -axis
+                };
+                translation + end + direction * *radius
+            }
+            Self::Cuboid { half_extents } => {
+                let local = rotation.inverse() * direction;
+                let corner = Vec3::new(
+                    half_extents.x.copysign(local.x),
+                    half_extents.y.copysign(local.y),
+                    half_extents.z.copysign(local.z),
+                );
+                translation + rotation * corner
+            }
+            Self::ConvexHull { points } => {
+                let farthest = points
+                    .iter()
+                    .map(|&point| rotation * point)
+                    .max_by(|a, b| a.dot(direction).total_cmp(&b.dot(direction)))
+                    .unwrap_or(Vec3::ZERO);
+                translation + farthest
+            }
+        }
+    }
+}
+
+// `ShapeInstance` 表示一个位于世界空间中的形状
+#[derive(Debug, Clone, Copy)]
+pub struct ShapeInstance<'a> {
+    pub shape: &'a ColliderShape,
+    // 球体的半径
+    pub radius: f32,
+    pub translation: Vec3,
+    pub rotation: Quat,
+}
+
+impl ShapeInstance<'_> {
+    fn support(&self, direction: Vec3) -> Vec3 {
+        self.shape
+            .support(self.radius, self.translation, self.rotation, direction)
+    }
+}
+
+// 这个函数判断两个形状是否相交
+// 两个球体直接比较距离，其他组合使用 GJK 算法在两个形状的闵可夫斯基差上判断是否包含原点
+pub fn shapes_intersect(a: ShapeInstance, b: ShapeInstance) -> bool {
+    if let (ColliderShape::Sphere, ColliderShape::Sphere) = (a.shape, b.shape) {
+        return a.translation.distance(b.translation) < a.radius + b.radius;
+    }
+    gjk_intersects(|direction| a.support(direction) - b.support(-direction))
+}
+
+// GJK 算法：`support` 是闵可夫斯基差的支撑函数，如果闵可夫斯基差包含原点，那么两个形状相交
+fn gjk_intersects(support: impl Fn(Vec3) -> Vec3) -> bool {
+    let first = support(Vec3::X);
+    let mut simplex = vec![first];
+    let mut direction = -first;
+
+    for _ in 0..GJK_MAX_ITERATIONS {
+        // 原点位于单纯形上
+        if direction.length_squared() <= f32::EPSILON {
+            return true;
+        }
+        let point = support(direction);
+        // 新的点没有越过原点，说明原点在闵可夫斯基差之外
+        if point.dot(direction) < 0.0 {
+            return false;
+        }
+        simplex.push(point);
+        if update_simplex(&mut simplex, &mut direction) {
+            return true;
+        }
+    }
+    false
+}
+
+// 这个函数把单纯形缩减为最靠近原点的部分，并更新下一次搜索的方向
+// 单纯形中最后一个点是最新加入的点；如果单纯形包含原点，那么返回 `true`
+fn update_simplex(simplex: &mut Vec<Vec3>, direction: &mut Vec3) -> bool {
+    match simplex.len() {
+        2 => {
+            let (b, a) = (simplex[0], simplex[1]);
+            line_case(simplex, direction, a, b);
+            false
+        }
+        3 => {
+            let (c, b, a) = (simplex[0], simplex[1], simplex[2]);
+            triangle_case(simplex, direction, a, b, c);
+            false
+        }
+        4 => {
+            let (d, c, b, a) = (simplex[0], simplex[1], simplex[2], simplex[3]);
+            let ao = -a;
+            // 依次检查包含最新点的三个面，法线朝向四面体外侧
+            for (b, c, opposite) in [(b, c, d), (c, d, b), (d, b, c)] {
+                let mut normal = (b - a).cross(c - a);
+                if normal.dot(opposite - a) > 0.0 {
+                    normal = -normal;
+                }
+                if normal.dot(ao) > 0.0 {
+                    triangle_case(simplex, direction, a, b, c);
+                    return false;
+                }
+            }
+            true
+        }
+        _ => false,
+    }
+}
+
+// 单纯形是线段 ab 时的情况，a 是最新加入的点
+fn line_case(simplex: &mut Vec<Vec3>, direction: &mut Vec3, a: Vec3, b: Vec3) {
+    let ab = b - a;
+    let ao = -a;
+    if ab.dot(ao) > 0.0 {
+        *simplex = vec![b, a];
+        *direction = ab.cross(ao).cross(ab);
+    } else {
+        *simplex = vec![a];
+        *direction = ao;
+    }
+}
+
+// 单纯形是三角形 abc 时的情况，a 是最新加入的点
+fn triangle_case(simplex: &mut Vec<Vec3>, direction: &mut Vec3, a: Vec3, b: Vec3, c: Vec3) {
+    let ab = b - a;
+    let ac = c - a;
+    let ao = -a;
+    let abc = ab.cross(ac);
+    if abc.cross(ac).dot(ao) > 0.0 {
+        // 原点位于 ac 边的外侧
+        if ac.dot(ao) > 0.0 {
+            *simplex = vec![c, a];
+            *direction = ac.cross(ao).cross(ac);
+        } else {
+            line_case(simplex, direction, a, b);
+        }
+    } else if ab.cross(abc).dot(ao) > 0.0 {
+        // 原点位于 ab 边的外侧
+        line_case(simplex, direction, a, b);
+    } else if abc.dot(ao) > 0.0 {
+        // 原点位于三角形的上方
+        *simplex = vec![c, b, a];
+        *direction = abc;
+    } else {
+        // 原点位于三角形的下方
+        *simplex = vec![b, c, a];
+        *direction = -abc;
+    }
+}
@@ -1,14 +1,31 @@
 use bevy::{prelude::*, utils::HashMap};
 
-use crate::{asteroids::Asteroid, schedule::InGameSet, spaceship::Spaceship};
+use crate::{
+    asteroids::Asteroid,
+    collider_shape::{shapes_intersect, ColliderShape, ShapeInstance},
+    difficulty::DifficultyTuning,
+    enemy::EnemyProjectile,
+    health::{Health, IMPACT_DAMAGE, MISSILE_DAMAGE},
+    movement::Velocity,
+    player::{PlayerConfig, PlayerId},
+    schedule::InGameSet,
+    spaceship::{GodMode, MissileOwner, Spaceship, SpaceshipShield},
+};
 
 // `Collider` 结构体用于处理碰撞检测
-#[derive(Component, Debug)]
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
 pub struct Collider {
-    // `radius` 字段表示碰撞器的半径
+    // `radius` 字段表示碰撞器的半径，对于不是球体的形状，它是包围球的半径
     pub radius: f32,
+    // `shape` 字段表示碰撞器的形状
+    pub shape: ColliderShape,
     // `colliding_entities` 字段是一个 `Entity` 类型的向量，用于存储与当前实体发生碰撞的其他实体
+    // 它在每一帧都会重新计算，因此不需要保存
+    #[reflect(ignore)]
     pub colliding_entities: Vec<Entity>,
+    // `previous_translation` 字段记录上一次碰撞检测时实体的全局位置，用于连续碰撞检测
+    pub previous_translation: Option<Vec3>,
 }
 
 // 为 `Collider` 结构体实现方法
@@ -19,61 +36,173 @@ impl Collider {
         // 返回一个新的 `Collider` 实例，其中 `radius` 字段的值为传入的 `radius` 参数，`colliding_entities` 字段的值为一个空向量
         Self {
             radius,
+            shape: ColliderShape::Sphere,
             colliding_entities: vec![],
+            previous_translation: None,
         }
     }
+
+    // 创建一个胶囊体碰撞器，胶囊体沿实体的本地 Z 轴延伸
+    pub fn capsule(radius: f32, half_length: f32) -> Self {
+        Self::with_shape(ColliderShape::Capsule {
+            radius,
+            half_length,
+        })
+    }
+
+    // 创建一个有向包围盒碰撞器
+    pub fn cuboid(half_extents: Vec3) -> Self {
+        Self::with_shape(ColliderShape::Cuboid { half_extents })
+    }
+
+    // 创建一个凸包碰撞器，`points` 是本地坐标系中的顶点
+    pub fn convex_hull(points: Vec<Vec3>) -> Self {
+        Self::with_shape(ColliderShape::ConvexHull { points })
+    }
+
+    // 根据形状创建碰撞器，`radius` 字段被设置为形状的包围球半径
+    fn with_shape(shape: ColliderShape) -> Self {
+        let mut collider = Self::new(shape.bounding_radius().unwrap_or_default());
+        collider.shape = shape;
+        collider
+    }
+
+    // 返回碰撞器对应物体的质量，质量与球体的体积成正比，即与半径的立方成正比
+    pub fn mass(&self) -> f32 {
+        self.radius.powi(3)
+    }
+}
+
+// `Sensor` 组件标记只用于检测接触的碰撞器（例如道具），与它接触的实体不会因此被销毁
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+pub struct Sensor;
+
+// `SpawnGrace` 组件使刚生成的实体在计时器结束之前不会因为碰撞被摧毁
+// 例如冰质小行星分裂出的碎片，否则摧毁母体的导弹会继续飞行并立即击碎它们
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+pub struct SpawnGrace(pub Timer);
+
+impl SpawnGrace {
+    pub fn from_seconds(seconds: f32) -> Self {
+        Self(Timer::from_seconds(seconds, TimerMode::Once))
+    }
+}
+
+// `DestroyedKind` 表示被摧毁的实体的种类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DestroyedKind {
+    Asteroid,
+    Spaceship,
+    Enemy,
+    BossPart,
+    Boss,
+}
+
+// `EntityDestroyed` 事件在实体因为碰撞被摧毁时发送（飞出边界而被销毁的实体不会发送这个事件）
+// 其他系统可以根据它计算分数、掉落道具或者播放特效
+#[derive(Event, Debug, Clone, Copy)]
+pub struct EntityDestroyed {
+    pub entity: Entity,
+    pub kind: DestroyedKind,
+    pub translation: Vec3,
+    pub radius: f32,
+    // 如果实体是被玩家的导弹摧毁的，记录这名玩家
+    pub by_player: Option<PlayerId>,
+}
+
+// `Destructible` trait 把组件类型和被摧毁时的种类对应起来，供 `handle_collisions` 使用
+pub trait Destructible: Component {
+    const KIND: DestroyedKind;
+}
+
+impl Destructible for Asteroid {
+    const KIND: DestroyedKind = DestroyedKind::Asteroid;
 }
 
 pub struct CollisionDetectionPlugin;
 
 impl Plugin for CollisionDetectionPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            Update,
-            collision_detection.in_set(InGameSet::CollisionDetection),
-        )
-        .add_systems(
-            Update,
-            (
-                handle_collisions::<Asteroid>,
-                handle_collisions::<Spaceship>,
+        app.register_type::<Collider>()
+            .register_type::<ColliderShape>()
+            .register_type::<Sensor>()
+            .register_type::<SpawnGrace>()
+            .register_type::<Option<Vec3>>()
+            .register_type::<Vec<Vec3>>()
+            .init_resource::<DifficultyTuning>()
+            .add_event::<EntityDestroyed>()
+            .add_systems(
+                Update,
+                collision_detection.in_set(InGameSet::CollisionDetection),
             )
-                .in_set(InGameSet::DespawnEntities),
-        );
+            .add_systems(Update, tick_spawn_grace.in_set(InGameSet::EntityUpdates))
+            .add_systems(
+                Update,
+                (
+                    bounce_asteroids,
+                    handle_collisions::<Asteroid>,
+                    handle_spaceship_collisions,
+                )
+                    .in_set(InGameSet::DespawnEntities),
+            );
     }
 }
 
 // 这个函数用于检测碰撞
-fn collision_detection(mut query: Query<(Entity, &GlobalTransform, &mut Collider)>) {
+// 碰撞器可以位于子实体上（例如首领的各个部件），因此这里使用全局变换计算位置
+// 同一个父实体下的碰撞器属于同一个物体，它们之间不会发生碰撞
+// 为了让高速运动的物体（例如导弹）在帧率较低时也不会穿过其他物体，这里对两个物体从上一次检测到这一次检测之间扫过的路径进行检测
+fn collision_detection(
+    mut query: Query<(Entity, Ref<GlobalTransform>, &mut Collider, Option<&Parent>)>,
+) {
     // 创建一个空的 HashMap，用于存储发生碰撞的实体
     let mut colliding_entities: HashMap<Entity, Vec<Entity>> = HashMap::new();
 
     // 第一阶段：检测碰撞
-    // 对查询结果进行迭代，每次迭代得到一个实体、全局变换和碰撞器的元组
-    for (entity_a, transform_a, collider_a) in query.iter() {
-        // 再次对查询结果进行迭代，每次迭代得到另一个实体、全局变换和碰撞器的元组
-        for (entity_b, transform_b, collider_b) in query.iter() {
-            // 如果两个实体不是同一个实体
-            if entity_a != entity_b {
-                // 计算两个实体的位置之间的距离
-                let distance = transform_a
-                    .translation()
-                    .distance(transform_b.translation());
-                // 如果距离小于两个碰撞器的半径之和，那么这两个实体发生了碰撞
-                if distance < collider_a.radius + collider_b.radius {
-                    // 在 HashMap 中为 entity_a 添加一个与其发生碰撞的实体 entity_b
-                    colliding_entities
-                        .entry(entity_a)
-                        .or_insert_with(Vec::new)
-                        .push(entity_b);
+    // 对查询结果进行迭代，每次迭代得到一个实体、全局变换、碰撞器和父实体的元组
+    for (entity_a, transform_a, collider_a, parent_a) in query.iter() {
+        // 刚生成的实体的全局变换还没有被计算，它位于原点，因此在第一帧跳过它
+        if transform_a.is_added() {
+            continue;
+        }
+        // 再次对查询结果进行迭代，每次迭代得到另一个实体、全局变换、碰撞器和父实体的元组
+        for (entity_b, transform_b, collider_b, parent_b) in query.iter() {
+            // 如果两个实体是同一个实体，或者属于同一个父实体，那么跳过
+            if entity_a == entity_b || transform_b.is_added() {
+                continue;
+            }
+            if let (Some(parent_a), Some(parent_b)) = (parent_a, parent_b) {
+                if parent_a.get() == parent_b.get() {
+                    continue;
                 }
             }
+            // 计算两个实体在这段时间内的最近距离，以及到达最近距离的时刻
+            let path_a = (collider_a.previous_translation, transform_a.translation());
+            let path_b = (collider_b.previous_translation, transform_b.translation());
+            let (distance, time) = closest_approach(path_a, path_b);
+            // 如果距离小于两个碰撞器的半径之和，那么这两个实体的包围球发生了碰撞
+            if distance >= collider_a.radius + collider_b.radius {
+                continue;
+            }
+            // 如果其中一个碰撞器不是球体，那么在最近距离的时刻用精确的形状再检测一次
+            if shapes_intersect(
+                shape_instance(collider_a, &transform_a, path_a, time),
+                shape_instance(collider_b, &transform_b, path_b, time),
+            ) {
+                // 在 HashMap 中为 entity_a 添加一个与其发生碰撞的实体 entity_b
+                colliding_entities
+                    .entry(entity_a)
+                    .or_insert_with(Vec::new)
+                    .push(entity_b);
+            }
         }
     }
 
     // 第二阶段：更新碰撞器
     // 对查询结果进行迭代，每次迭代得到一个实体、全局变换和碰撞器的元组
-    for (entity, _, mut collider) in query.iter_mut() {
+    for (entity, transform, mut collider, _) in query.iter_mut() {
         // 清空碰撞器的 `colliding_entities` 字段
         collider.colliding_entities.clear();
         // 如果在 HashMap 中找到了与当前实体发生碰撞的实体
@@ -83,25 +212,260 @@ fn collision_detection(mut query: Query<(Entity, &GlobalTransform, &mut Collider
                 .colliding_entities
                 .extend(collisions.iter().copied());
         }
+        // 记录这一次检测时的位置，作为下一次检测时路径的起点
+        if !transform.is_added() {
+            collider.previous_translation = Some(transform.translation());
+        }
+    }
+}
+
+// 这个函数计算两个运动的球体在一段时间内的最近距离
+// 每个参数是物体的起点和终点，没有起点时物体视为静止在终点；两个物体在这段时间内都做匀速直线运动
+pub fn swept_distance(a: (Option<Vec3>, Vec3), b: (Option<Vec3>, Vec3)) -> f32 {
+    closest_approach(a, b).0
+}
+
+// 这个函数返回两个运动物体的最近距离，以及到达最近距离的时刻，时刻的范围是 0.0 到 1.0
+fn closest_approach(a: (Option<Vec3>, Vec3), b: (Option<Vec3>, Vec3)) -> (f32, f32) {
+    let (start_a, end_a) = (a.0.unwrap_or(a.1), a.1);
+    let (start_b, end_b) = (b.0.unwrap_or(b.1), b.1);
+    // 在 a 的参考系中，b 的相对位置从 `start` 线性变化到 `end`，求这条线段到原点的最近距离
+    let start = start_b - start_a;
+    let end = end_b - end_a;
+    let movement = end - start;
+    let length_squared = movement.length_squared();
+    if length_squared <= f32::EPSILON {
+        return (end.length(), 1.0);
     }
+    let time = (-start.dot(movement) / length_squared).clamp(0.0, 1.0);
+    ((start + movement * time).length(), time)
 }
 
-// 定义一个名为 `handle_collisions` 的泛型函数，它接受两个参数：一个可变的 `Commands` 类型参数和一个 `Query` 类型参数
+// 这个函数返回碰撞器在 `time` 时刻的形状，形状的旋转使用实体当前的全局旋转
+fn shape_instance<'a>(
+    collider: &'a Collider,
+    transform: &GlobalTransform,
+    path: (Option<Vec3>, Vec3),
+    time: f32,
+) -> ShapeInstance<'a> {
+    ShapeInstance {
+        shape: &collider.shape,
+        radius: collider.radius,
+        translation: lerp_path(path, time),
+        rotation: transform.to_scale_rotation_translation().1,
+    }
+}
+
+// 这个函数返回物体在 `time` 时刻沿路径所在的位置
+fn lerp_path((start, end): (Option<Vec3>, Vec3), time: f32) -> Vec3 {
+    start.map_or(end, |start| start.lerp(end, time))
+}
+
+// 这个函数处理小行星之间的弹性碰撞
+// 重叠的两颗小行星会按质量比例被推开，如果它们正在相互靠近，那么沿碰撞法线施加满足动量守恒和动能守恒的冲量
+fn bounce_asteroids(
+    mut query: Query<(Entity, &mut Transform, &mut Velocity, &Collider), With<Asteroid>>,
+) {
+    // 收集所有发生碰撞的小行星对，每一对只处理一次
+    let pairs: Vec<(Entity, Entity)> = query
+        .iter()
+        .flat_map(|(entity_a, _, _, collider)| {
+            collider
+                .colliding_entities
+                .iter()
+                .filter(move |&&entity_b| entity_a < entity_b)
+                .map(move |&entity_b| (entity_a, entity_b))
+        })
+        .collect();
+
+    for (entity_a, entity_b) in pairs {
+        let Ok(
+            [(_, mut transform_a, mut velocity_a, collider_a), (_, mut transform_b, mut velocity_b, collider_b)],
+        ) = query.get_many_mut([entity_a, entity_b])
+        else {
+            continue;
+        };
+        // 使用本帧的位置重新计算重叠，碰撞检测的结果可能已经因为上一对的分离而过时
+        let offset = transform_b.translation - transform_a.translation;
+        let overlap = collider_a.radius + collider_b.radius - offset.length();
+        if overlap <= 0.0 {
+            continue;
+        }
+        let normal = offset.try_normalize().unwrap_or(Vec3::X);
+        let mass_a = collider_a.mass();
+        let mass_b = collider_b.mass();
+        let total_mass = mass_a + mass_b;
+
+        // 按质量的反比把两颗小行星沿法线推开，较轻的一颗移动得更多
+        transform_a.translation -= normal * overlap * mass_b / total_mass;
+        transform_b.translation += normal * overlap * mass_a / total_mass;
+
+        // 如果两颗小行星已经在相互远离，那么不再施加冲量
+        let approach_speed = (velocity_b.value - velocity_a.value).dot(normal);
+        if approach_speed >= 0.0 {
+            continue;
+        }
+        let impulse = -2.0 * approach_speed / (1.0 / mass_a + 1.0 / mass_b);
+        velocity_a.value -= normal * impulse / mass_a;
+        velocity_b.value += normal * impulse / mass_b;
+    }
+}
+
+// `DestructibleQuery` 查询所有可以被摧毁的 `T` 类型实体，处在保护时间内的实体除外
+type DestructibleQuery<'w, 's, T> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static GlobalTransform,
+        &'static Collider,
+        Option<&'static mut Health>,
+    ),
+    (With<T>, Without<SpawnGrace>),
+>;
+
+// 定义一个名为 `handle_collisions` 的泛型函数，它接受三个参数：一个可变的 `Commands` 类型参数、一个 `Query` 类型参数和一个 `EventWriter`
 // 这个函数用于处理实体的碰撞事件，其中 `T` 是实体的组件类型
-fn handle_collisions<T: Component>(
+// 拥有 `Health` 的实体被导弹击中时只受到按难度缩放的伤害，生命值没有耗尽时导弹被吸收，生命值耗尽时才被摧毁
+// 实体在这里只是被标记为销毁，需要读取被摧毁实体的组件的系统可以在它之后用 `after_ignore_deferred` 运行
+pub fn handle_collisions<T: Destructible>(
     mut commands: Commands,
-    query: Query<(Entity, &Collider), With<T>>,
+    mut query: DestructibleQuery<T>,
+    same_kind_query: Query<(), With<T>>,
+    sensor_query: Query<(), With<Sensor>>,
+    missile_query: Query<&MissileOwner>,
+    tuning: Res<DifficultyTuning>,
+    mut destroyed: EventWriter<EntityDestroyed>,
 ) {
-    // 对查询结果进行迭代，每次迭代得到一个实体和它的碰撞器
-    for (entity, collider) in query.iter() {
+    // 对查询结果进行迭代，每次迭代得到一个实体、它的全局变换、碰撞器和生命值
+    for (entity, transform, collider, mut health) in query.iter_mut() {
+        let mut destroyed_by = None;
         // 对碰撞器的 `colliding_entities` 字段进行迭代，每次迭代得到一个与实体发生碰撞的实体
         for &collided_entity in collider.colliding_entities.iter() {
-            // 如果发生碰撞的实体也是同类型的实体，那么跳过这次迭代，不处理这次碰撞
-            if query.get(collided_entity).is_ok() {
+            // 如果发生碰撞的实体也是同类型的实体，或者是传感器，那么跳过这次迭代，不处理这次碰撞
+            if same_kind_query.contains(collided_entity) || sensor_query.contains(collided_entity) {
+                continue;
+            }
+            let by_player = missile_query
+                .get(collided_entity)
+                .ok()
+                .map(|&MissileOwner(player)| player);
+            if let (Some(health), Some(_)) = (health.as_deref_mut(), by_player) {
+                if !health.damage(MISSILE_DAMAGE * tuning.damage_scale) {
+                    commands.entity(collided_entity).despawn_recursive();
+                    continue;
+                }
+            }
+            destroyed_by = Some(by_player);
+            break;
+        }
+        // 如果发生碰撞的实体不是同类型的实体，那么销毁这个实体
+        let Some(by_player) = destroyed_by else {
+            continue;
+        };
+        commands.entity(entity).despawn_recursive();
+        destroyed.send(EntityDestroyed {
+            entity,
+            kind: T::KIND,
+            translation: transform.translation(),
+            radius: collider.radius,
+            by_player,
+        });
+    }
+}
+
+// 这个函数在保护时间结束后删除 `SpawnGrace` 组件
+fn tick_spawn_grace(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut SpawnGrace)>,
+    time: Res<Time>,
+) {
+    for (entity, mut grace) in query.iter_mut() {
+        if grace.0.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<SpawnGrace>();
+        }
+    }
+}
+
+// `SpaceshipColliderQuery` 查询所有飞船的碰撞器、所属玩家、是否开启了护盾以及生命值
+type SpaceshipColliderQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static GlobalTransform,
+        &'static Collider,
+        &'static PlayerId,
+        Has<SpaceshipShield>,
+        Option<&'static mut Health>,
+    ),
+    With<Spaceship>,
+>;
+
+// `ProjectileFilter` 匹配玩家的导弹和敌方的子弹
+type ProjectileFilter = Or<(With<MissileOwner>, With<EnemyProjectile>)>;
+
+// 这个函数用于处理飞船的碰撞事件
+// 与 `handle_collisions` 不同，它会根据友军伤害规则决定导弹是否能击毁飞船，并且开启护盾的飞船不会被摧毁
+// 拥有 `Health` 的飞船每次被击中受到的伤害除以难度的伤害倍数，没有被摧毁时击中它的子弹和导弹被吸收
+#[allow(clippy::too_many_arguments)]
+fn handle_spaceship_collisions(
+    mut commands: Commands,
+    mut query: SpaceshipColliderQuery,
+    spaceship_query: Query<(), With<Spaceship>>,
+    sensor_query: Query<(), With<Sensor>>,
+    missile_query: Query<&MissileOwner>,
+    projectile_query: Query<(), ProjectileFilter>,
+    player_config: Res<PlayerConfig>,
+    tuning: Res<DifficultyTuning>,
+    god_mode: Option<Res<GodMode>>,
+    mut destroyed: EventWriter<EntityDestroyed>,
+) {
+    // 无敌模式下飞船不会被摧毁
+    if god_mode.is_some_and(|god_mode| god_mode.enabled) {
+        return;
+    }
+    for (entity, transform, collider, &player, shielded, mut health) in query.iter_mut() {
+        if shielded {
+            continue;
+        }
+        let mut destroyed_by = None;
+        for &collided_entity in collider.colliding_entities.iter() {
+            // 飞船之间的碰撞以及与传感器的接触不会造成伤害
+            if spaceship_query.contains(collided_entity) || sensor_query.contains(collided_entity) {
                 continue;
             }
-            // 如果发生碰撞的实体不是同类型的实体，那么销毁这个实体
-            commands.entity(entity).despawn_recursive();
+            // 如果碰撞的实体是导弹，自己的导弹永远不会造成伤害，其他玩家的导弹只有在启用友军伤害时才会造成伤害
+            let by_player = missile_query
+                .get(collided_entity)
+                .ok()
+                .map(|&MissileOwner(owner)| owner);
+            if let Some(owner) = by_player {
+                if owner == player || !player_config.friendly_fire {
+                    continue;
+                }
+            }
+            if let Some(health) = health.as_deref_mut() {
+                if !health.damage(IMPACT_DAMAGE / tuning.damage_scale) {
+                    if projectile_query.contains(collided_entity) {
+                        commands.entity(collided_entity).despawn_recursive();
+                    }
+                    continue;
+                }
+            }
+            destroyed_by = Some(by_player);
+            break;
         }
+        let Some(by_player) = destroyed_by else {
+            continue;
+        };
+        commands.entity(entity).despawn_recursive();
+        destroyed.send(EntityDestroyed {
+            entity,
+            kind: DestroyedKind::Spaceship,
+            translation: transform.translation(),
+            radius: collider.radius,
+            by_player,
+        });
     }
 }
@@ -0,0 +1,410 @@
+use std::{collections::BTreeMap, str::FromStr};
+
+use bevy::{input::InputSystem, prelude::*};
+
+use crate::{
+    asteroids::Asteroid,
+    boss::{Boss, BossHealthBarRoot},
+    enemy::{Enemy, EnemyProjectile},
+    schedule::InGameSet,
+};
+
+// 定义打开和关闭控制台的按键
+const TOGGLE_KEY: KeyCode = KeyCode::Backquote;
+
+// 定义控制台保留的输出行数
+const MAX_OUTPUT_LINES: usize = 200;
+
+// 定义控制台显示的输出行数
+const VISIBLE_OUTPUT_LINES: usize = 14;
+
+// 定义控制台保留的历史命令数量
+const MAX_HISTORY: usize = 100;
+
+// `ConsoleHandler` 是控制台命令的处理函数
+// 它接受整个 `World` 和命令名之后的参数，返回要显示在控制台中的结果或错误
+pub type ConsoleHandler = fn(&mut World, &[&str]) -> Result<String, String>;
+
+// `ConsoleCommand` 结构体表示一条注册的控制台命令
+#[derive(Debug, Clone)]
+pub struct ConsoleCommand {
+    // 命令的用法说明，显示在 `help` 的输出中
+    pub help: String,
+    pub handler: ConsoleHandler,
+}
+
+// `ConsoleCommands` 资源是控制台命令的注册表，各个插件在 `build` 中向它注册自己的命令
+// 命令名可以由多个单词组成，例如 `spawn asteroid`，执行时使用与输入匹配的最长命令名
+#[derive(Resource, Debug, Default)]
+pub struct ConsoleCommands {
+    commands: BTreeMap<String, ConsoleCommand>,
+}
+
+impl ConsoleCommands {
+    // 注册一条命令，同名的命令会被覆盖
+    pub fn register(&mut self, name: &str, help: &str, handler: ConsoleHandler) {
+        self.commands.insert(
+            name.to_string(),
+            ConsoleCommand {
+                help: help.to_string(),
+                handler,
+            },
+        );
+    }
+
+    // 查找与输入的单词匹配的最长命令名，返回命令以及命令名占用的单词数量
+    pub fn find(&self, words: &[&str]) -> Option<(&ConsoleCommand, usize)> {
+        (1..=words.len()).rev().find_map(|count| {
+            self.commands
+                .get(&words[..count].join(" "))
+                .map(|command| (command, count))
+        })
+    }
+
+    // 返回所有以 `prefix` 开头的命令名，按字母顺序排列
+    pub fn complete(&self, prefix: &str) -> Vec<&str> {
+        self.commands
+            .keys()
+            .filter(|name| name.starts_with(prefix))
+            .map(String::as_str)
+            .collect()
+    }
+
+    // 按字母顺序遍历所有命令
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &ConsoleCommand)> {
+        self.commands
+            .iter()
+            .map(|(name, command)| (name.as_str(), command))
+    }
+}
+
+// `ConsoleCommandsExt` trait 让插件可以在 `build` 中直接向 `App` 注册控制台命令
+pub trait ConsoleCommandsExt {
+    fn add_console_command(&mut self, name: &str, help: &str, handler: ConsoleHandler)
+        -> &mut Self;
+}
+
+impl ConsoleCommandsExt for App {
+    fn add_console_command(
+        &mut self,
+        name: &str,
+        help: &str,
+        handler: ConsoleHandler,
+    ) -> &mut Self {
+        self.world
+            .get_resource_or_insert_with(ConsoleCommands::default)
+            .register(name, help, handler);
+        self
+    }
+}
+
+// 这个函数解析并执行一行控制台命令
+pub fn execute_command(world: &mut World, line: &str) -> Result<String, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    if words.is_empty() {
+        return Ok(String::new());
+    }
+    let (handler, count) = world
+        .get_resource::<ConsoleCommands>()
+        .and_then(|commands| commands.find(&words))
+        .map(|(command, count)| (command.handler, count))
+        .ok_or_else(|| format!("unknown command `{}`, try `help`", words[0]))?;
+    handler(world, &words[count..])
+}
+
+// 这个函数解析第 `index` 个参数，参数不存在时返回错误
+pub fn parse_arg<T: FromStr>(args: &[&str], index: usize, name: &str) -> Result<T, String> {
+    let arg = args
+        .get(index)
+        .ok_or_else(|| format!("missing argument <{name}>"))?;
+    arg.parse()
+        .map_err(|_| format!("invalid value `{arg}` for <{name}>"))
+}
+
+// 这个函数解析第 `index` 个参数，参数不存在时返回默认值
+pub fn parse_optional_arg<T: FromStr>(
+    args: &[&str],
+    index: usize,
+    name: &str,
+    default: T,
+) -> Result<T, String> {
+    if args.len() <= index {
+        return Ok(default);
+    }
+    parse_arg(args, index, name)
+}
+
+// `ConsoleCommandsPlugin` 插件注册内置的控制台命令，它不依赖窗口，因此也可以在服务器和测试中使用
+pub struct ConsoleCommandsPlugin;
+
+impl Plugin for ConsoleCommandsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ConsoleCommands>()
+            .add_console_command("help", "help - list all commands", help_command)
+            .add_console_command(
+                "kill all",
+                "kill all - destroy all asteroids, enemies and bosses",
+                kill_all_command,
+            );
+    }
+}
+
+// `help` 命令列出所有命令的用法
+fn help_command(world: &mut World, _args: &[&str]) -> Result<String, String> {
+    let commands = world.resource::<ConsoleCommands>();
+    Ok(commands
+        .iter()
+        .map(|(_, command)| command.help.as_str())
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+// `kill all` 命令销毁所有的小行星、敌方飞船、敌方子弹和首领
+fn kill_all_command(world: &mut World, _args: &[&str]) -> Result<String, String> {
+    let mut query = world.query_filtered::<Entity, Or<(
+        With<Asteroid>,
+        With<Enemy>,
+        With<EnemyProjectile>,
+        With<Boss>,
+        With<BossHealthBarRoot>,
+    )>>();
+    let entities: Vec<Entity> = query.iter(world).collect();
+    for &entity in entities.iter() {
+        despawn_with_children_recursive(world, entity);
+    }
+    Ok(format!("destroyed {} entities", entities.len()))
+}
+
+// `ConsoleState` 资源保存下拉控制台的状态
+#[derive(Resource, Debug, Default)]
+pub struct ConsoleState {
+    pub open: bool,
+    // 正在输入的命令
+    pub input: String,
+    // 已经执行过的命令，最新的在最后
+    pub history: Vec<String>,
+    // 使用上下方向键浏览历史命令时，当前显示的历史命令的位置
+    history_index: Option<usize>,
+    // 控制台的输出，最新的在最后
+    pub output: Vec<String>,
+    // 已经提交但还没有执行的命令
+    pending: Vec<String>,
+}
+
+impl ConsoleState {
+    // 在控制台中输出一行或多行文本
+    pub fn print(&mut self, text: &str) {
+        self.output.extend(text.lines().map(str::to_string));
+        let overflow = self.output.len().saturating_sub(MAX_OUTPUT_LINES);
+        self.output.drain(..overflow);
+    }
+
+    // 提交当前输入的命令
+    fn submit(&mut self) {
+        let line = std::mem::take(&mut self.input);
+        self.history_index = None;
+        if line.trim().is_empty() {
+            return;
+        }
+        if self.history.last() != Some(&line) {
+            self.history.push(line.clone());
+            let overflow = self.history.len().saturating_sub(MAX_HISTORY);
+            self.history.drain(..overflow);
+        }
+        self.pending.push(line);
+    }
+
+    // 浏览历史命令，`older` 为真时显示更早的命令
+    fn browse_history(&mut self, older: bool) {
+        if self.history.is_empty() {
+            return;
+        }
+        let last = self.history.len() - 1;
+        self.history_index = match (self.history_index, older) {
+            (None, true) => Some(last),
+            (None, false) => None,
+            (Some(index), true) => Some(index.saturating_sub(1)),
+            (Some(index), false) if index < last => Some(index + 1),
+            (Some(_), false) => None,
+        };
+        self.input = self
+            .history_index
+            .map(|index| self.history[index].clone())
+            .unwrap_or_default();
+    }
+
+    // 使用注册的命令名补全当前的输入
+    // 只有一个匹配时补全整个命令名，有多个匹配时补全它们的公共前缀并列出所有匹配
+    fn complete(&mut self, commands: &ConsoleCommands) {
+        let prefix = self.input.trim_start().to_string();
+        let matches = commands.complete(&prefix);
+        match matches.as_slice() {
+            [] => {}
+            [name] => self.input = format!("{name} "),
+            [first, rest @ ..] => {
+                let common = rest.iter().fold(first.to_string(), |common, name| {
+                    common
+                        .chars()
+                        .zip(name.chars())
+                        .take_while(|(a, b)| a == b)
+                        .map(|(a, _)| a)
+                        .collect()
+                });
+                self.input = common;
+                let listing = matches.join("  ");
+                self.print(&listing);
+            }
+        }
+    }
+}
+
+// `ConsoleRoot` 组件标记控制台的根节点
+#[derive(Component, Debug)]
+struct ConsoleRoot;
+
+// `ConsoleText` 组件标记控制台的文本
+#[derive(Component, Debug)]
+struct ConsoleText;
+
+// `ConsolePlugin` 插件提供按反引号键打开的下拉控制台，它依赖窗口和键盘输入
+// 控制台打开时会清空键盘状态，使游戏逻辑读取不到按键
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ConsoleState>()
+            .init_resource::<ConsoleCommands>()
+            .add_systems(Startup, spawn_console)
+            .add_systems(PreUpdate, console_input.after(InputSystem))
+            .add_systems(
+                Update,
+                (run_pending_commands, update_console_ui)
+                    .chain()
+                    .before(InGameSet::UserInput),
+            );
+    }
+}
+
+// 这个函数生成控制台的界面，控制台一开始是隐藏的
+fn spawn_console(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(0.0),
+                    left: Val::Px(0.0),
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(40.0),
+                    padding: UiRect::all(Val::Px(8.0)),
+                    align_items: AlignItems::FlexEnd,
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.85).into(),
+                visibility: Visibility::Hidden,
+                z_index: ZIndex::Global(100),
+                ..default()
+            },
+            ConsoleRoot,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 16.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ),
+                ConsoleText,
+            ));
+        });
+}
+
+// 这个函数处理控制台的键盘输入
+fn console_input(
+    mut state: ResMut<ConsoleState>,
+    mut keyboard_input: ResMut<ButtonInput<KeyCode>>,
+    mut characters: EventReader<ReceivedCharacter>,
+    commands: Res<ConsoleCommands>,
+) {
+    if keyboard_input.just_pressed(TOGGLE_KEY) {
+        state.open = !state.open;
+        characters.clear();
+        keyboard_input.reset_all();
+        return;
+    }
+    if !state.open {
+        characters.clear();
+        return;
+    }
+
+    for event in characters.read() {
+        for character in event.char.chars() {
+            if !character.is_control() && character != '`' {
+                state.input.push(character);
+            }
+        }
+    }
+    if keyboard_input.just_pressed(KeyCode::Backspace) {
+        state.input.pop();
+    }
+    if keyboard_input.just_pressed(KeyCode::Enter) {
+        state.submit();
+    }
+    if keyboard_input.just_pressed(KeyCode::Tab) {
+        state.complete(&commands);
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowUp) {
+        state.browse_history(true);
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowDown) {
+        state.browse_history(false);
+    }
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        state.open = false;
+    }
+    // 清空键盘状态，避免游戏逻辑在控制台打开时响应按键
+    keyboard_input.reset_all();
+}
+
+// 这个函数执行已经提交的命令，并把结果输出到控制台
+fn run_pending_commands(world: &mut World) {
+    let pending = std::mem::take(&mut world.resource_mut::<ConsoleState>().pending);
+    for line in pending {
+        let result = execute_command(world, &line);
+        let mut state = world.resource_mut::<ConsoleState>();
+        state.print(&format!("> {line}"));
+        match result {
+            Ok(message) => state.print(&message),
+            Err(error) => state.print(&format!("error: {error}")),
+        }
+    }
+}
+
+// 这个函数更新控制台的界面
+// Bevy 的默认字体不包含中文字符，因此控制台的输出使用英文
+fn update_console_ui(
+    state: Res<ConsoleState>,
+    mut root_query: Query<&mut Visibility, With<ConsoleRoot>>,
+    mut text_query: Query<&mut Text, With<ConsoleText>>,
+) {
+    if !state.is_changed() {
+        return;
+    }
+    for mut visibility in root_query.iter_mut() {
+        *visibility = if state.open {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+    }
+    for mut text in text_query.iter_mut() {
+        let start = state.output.len().saturating_sub(VISIBLE_OUTPUT_LINES);
+        let mut lines: Vec<&str> = state.output[start..].iter().map(String::as_str).collect();
+        let prompt = format!("> {}_", state.input);
+        lines.push(&prompt);
+        text.sections[0].value = lines.join("\n");
+    }
+}
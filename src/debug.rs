@@ -1,25 +1,257 @@
 // 引入 bevy 库的所有预定义类型
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashSet};
 
-use crate::schedule::InGameSet;
+use crate::{
+    asteroids::Asteroid,
+    collider_shape::ColliderShape,
+    collision_detection::Collider,
+    despawn::DESPAWN_DISTANCE,
+    enemy::Enemy,
+    movement::{Acceleration, Velocity},
+    schedule::InGameSet,
+    spaceship::SpaceshipMissile,
+};
+
+// 定义切换调试覆盖层的按键
+const TOGGLE_KEY: KeyCode = KeyCode::F3;
+
+// 定义碰撞器调试线框的颜色
+const COLLIDER_COLOR: Color = Color::GREEN;
+
+// 定义速度箭头的颜色
+const VELOCITY_COLOR: Color = Color::CYAN;
+
+// 定义加速度箭头的颜色
+const ACCELERATION_COLOR: Color = Color::ORANGE;
+
+// 定义销毁边界的颜色
+const BOUNDARY_COLOR: Color = Color::RED;
+
+// 定义空间网格的颜色
+const GRID_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.15);
+
+// 定义有碰撞器的网格单元的颜色
+const OCCUPIED_CELL_COLOR: Color = Color::YELLOW;
+
+// 定义空间网格单元的边长
+const GRID_CELL_SIZE: f32 = 10.0;
+
+// 定义加速度箭头的缩放比例，加速度通常比速度小得多
+const ACCELERATION_ARROW_SCALE: f32 = 2.0;
+
+// `DebugOverlay` 资源记录调试覆盖层是否开启
+#[derive(Resource, Debug, Default)]
+pub struct DebugOverlay {
+    pub enabled: bool,
+}
+
+// `DebugOverlayText` 组件标记显示实体数量的文本
+#[derive(Component, Debug)]
+struct DebugOverlayText;
 
 // 定义一个名为 `DebugPlugin` 的结构体
+// 它使用 gizmos 绘制碰撞器、速度、加速度、销毁边界和空间网格，并在左上角显示实体数量，按 F3 开启或关闭
 pub struct DebugPlugin;
 
 // 为 `DebugPlugin` 结构体实现 `Plugin` trait
 impl Plugin for DebugPlugin {
-    // 在 `build` 方法中，我们将 `print_position` 系统添加到更新阶段
+    // 在 `build` 方法中，我们添加切换覆盖层的系统，以及只在覆盖层开启时运行的绘制系统
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, print_position.after(InGameSet::EntityUpdates));
+        app.init_resource::<DebugOverlay>()
+            .add_systems(Startup, spawn_overlay_text)
+            .add_systems(
+                Update,
+                (toggle_overlay, update_overlay_text)
+                    .chain()
+                    .after(InGameSet::EntityUpdates),
+            )
+            .add_systems(
+                Update,
+                (draw_colliders, draw_motion, draw_boundary_and_grid)
+                    .after(InGameSet::EntityUpdates)
+                    .run_if(overlay_enabled),
+            );
     }
 }
 
-// 定义一个名为 `print_position` 的函数，它接受一个 `Query` 类型参数
-// 这个查询获取所有拥有 `Entity` 和 `Transform` 组件的实体。
-fn print_position(query: Query<(Entity, &Transform)>) {
-    // 对查询结果进行迭代。每次迭代都会返回一个元组，其中包含一个实体的 `Entity` 和 `Transform` 组件。
-    // 打印实体的 ID 和位置信息。
-    for (entity, transform) in query.iter() {
-        info!("实体 {:?} 的位置是 {:?},", entity, transform.translation);
+// 这个函数判断调试覆盖层是否开启
+fn overlay_enabled(overlay: Res<DebugOverlay>) -> bool {
+    overlay.enabled
+}
+
+// 这个函数在按下 `TOGGLE_KEY` 时切换调试覆盖层
+fn toggle_overlay(mut overlay: ResMut<DebugOverlay>, keyboard_input: Res<ButtonInput<KeyCode>>) {
+    if keyboard_input.just_pressed(TOGGLE_KEY) {
+        overlay.enabled = !overlay.enabled;
+    }
+}
+
+// 这个函数生成显示实体数量的文本，文本一开始是隐藏的
+fn spawn_overlay_text(mut commands: Commands) {
+    commands.spawn((
+        TextBundle {
+            visibility: Visibility::Hidden,
+            ..TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 16.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(8.0),
+                left: Val::Px(8.0),
+                ..default()
+            })
+        },
+        DebugOverlayText,
+    ));
+}
+
+// 这个函数更新实体数量文本的内容，并根据覆盖层是否开启显示或隐藏文本
+fn update_overlay_text(
+    overlay: Res<DebugOverlay>,
+    mut text_query: Query<(&mut Text, &mut Visibility), With<DebugOverlayText>>,
+    entity_query: Query<Entity>,
+    collider_query: Query<(), With<Collider>>,
+    asteroid_query: Query<(), With<Asteroid>>,
+    missile_query: Query<(), With<SpaceshipMissile>>,
+    enemy_query: Query<(), With<Enemy>>,
+) {
+    for (mut text, mut visibility) in text_query.iter_mut() {
+        *visibility = if overlay.enabled {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+        if !overlay.enabled {
+            continue;
+        }
+        // Bevy 的默认字体不包含中文字符，因此这里使用英文
+        text.sections[0].value = format!(
+            "entities: {}\ncolliders: {}\nasteroids: {}\nmissiles: {}\nenemies: {}",
+            entity_query.iter().count(),
+            collider_query.iter().count(),
+            asteroid_query.iter().count(),
+            missile_query.iter().count(),
+            enemy_query.iter().count(),
+        );
+    }
+}
+
+// 这个函数使用 gizmos 绘制每个碰撞器的形状
+fn draw_colliders(query: Query<(&GlobalTransform, &Collider)>, mut gizmos: Gizmos) {
+    for (transform, collider) in query.iter() {
+        let (_, rotation, translation) = transform.to_scale_rotation_translation();
+        match &collider.shape {
+            ColliderShape::Sphere => {
+                gizmos.circle(translation, Direction3d::Y, collider.radius, COLLIDER_COLOR);
+            }
+            ColliderShape::Capsule {
+                radius,
+                half_length,
+            } => {
+                // 胶囊体由两端的球体和连接它们的四条侧线组成
+                let axis = rotation * Vec3::Z * *half_length;
+                gizmos.sphere(translation + axis, rotation, *radius, COLLIDER_COLOR);
+                gizmos.sphere(translation - axis, rotation, *radius, COLLIDER_COLOR);
+                for side in [Vec3::X, Vec3::NEG_X, Vec3::Y, Vec3::NEG_Y] {
+                    let side = rotation * side * *radius;
+                    gizmos.line(
+                        translation + axis + side,
+                        translation - axis + side,
+                        COLLIDER_COLOR,
+                    );
+                }
+            }
+            ColliderShape::Cuboid { half_extents } => {
+                gizmos.cuboid(
+                    Transform::from_translation(translation)
+                        .with_rotation(rotation)
+                        .with_scale(*half_extents * 2.0),
+                    COLLIDER_COLOR,
+                );
+            }
+            ColliderShape::ConvexHull { points } => {
+                // 凸包的顶点数量很少，这里直接连接每一对顶点
+                for (index, &a) in points.iter().enumerate() {
+                    for &b in points.iter().skip(index + 1) {
+                        gizmos.line(
+                            translation + rotation * a,
+                            translation + rotation * b,
+                            COLLIDER_COLOR,
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+// 这个函数为每个运动物体绘制速度和加速度箭头
+fn draw_motion(
+    query: Query<(&GlobalTransform, &Velocity, Option<&Acceleration>)>,
+    mut gizmos: Gizmos,
+) {
+    for (transform, velocity, acceleration) in query.iter() {
+        let start = transform.translation();
+        if velocity.value.length_squared() > f32::EPSILON {
+            gizmos.arrow(start, start + velocity.value, VELOCITY_COLOR);
+        }
+        if let Some(acceleration) = acceleration {
+            if acceleration.value.length_squared() > f32::EPSILON {
+                gizmos.arrow(
+                    start,
+                    start + acceleration.value * ACCELERATION_ARROW_SCALE,
+                    ACCELERATION_COLOR,
+                );
+            }
+        }
+    }
+}
+
+// 这个函数绘制销毁边界和空间网格，包含碰撞器的网格单元会被高亮
+fn draw_boundary_and_grid(query: Query<&GlobalTransform, With<Collider>>, mut gizmos: Gizmos) {
+    gizmos
+        .circle(Vec3::ZERO, Direction3d::Y, DESPAWN_DISTANCE, BOUNDARY_COLOR)
+        .segments(128);
+
+    // 在 XZ 平面上绘制覆盖销毁边界的网格线
+    let cells = (DESPAWN_DISTANCE / GRID_CELL_SIZE).ceil() as i32;
+    let extent = cells as f32 * GRID_CELL_SIZE;
+    for index in -cells..=cells {
+        let offset = index as f32 * GRID_CELL_SIZE;
+        gizmos.line(
+            Vec3::new(offset, 0.0, -extent),
+            Vec3::new(offset, 0.0, extent),
+            GRID_COLOR,
+        );
+        gizmos.line(
+            Vec3::new(-extent, 0.0, offset),
+            Vec3::new(extent, 0.0, offset),
+            GRID_COLOR,
+        );
+    }
+
+    // 高亮包含碰撞器的网格单元
+    let occupied: HashSet<IVec2> = query
+        .iter()
+        .map(|transform| {
+            let translation = transform.translation();
+            (Vec2::new(translation.x, translation.z) / GRID_CELL_SIZE)
+                .floor()
+                .as_ivec2()
+        })
+        .collect();
+    for cell in occupied {
+        let center = (cell.as_vec2() + 0.5) * GRID_CELL_SIZE;
+        gizmos.rect(
+            Vec3::new(center.x, 0.0, center.y),
+            Quat::from_rotation_x(std::f32::consts::FRAC_PI_2),
+            Vec2::splat(GRID_CELL_SIZE),
+            OCCUPIED_CELL_COLOR,
+        );
     }
 }
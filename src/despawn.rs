@@ -1,9 +1,9 @@
 use bevy::prelude::*;
 
-use crate::schedule::InGameSet;
+use crate::{movement::Velocity, schedule::InGameSet};
 
 // 定义一个常量 `DESPAWN_DISTANCE`，表示实体从原点距离超过这个值时将被销毁，初始值为 100.0
-const DESPAWN_DISTANCE: f32 = 100.0;
+pub const DESPAWN_DISTANCE: f32 = 100.0;
 
 // 定义一个公共结构体 `DespawnPlugin`
 pub struct DespawnPlugin;
@@ -18,15 +18,19 @@ impl Plugin for DespawnPlugin {
 }
 
 // 定义一个名为 `despawn_far_away_entities` 的函数，它接受一个可变的 `Commands` 类型参数和一个 `Query` 类型参数
-fn despawn_far_away_entities(mut commands: Commands, query: Query<(Entity, &GlobalTransform)>) {
+// 只查询带有速度的游戏实体，界面节点的全局变换在像素空间中，摄像机也会跟随飞船离开原点，它们都不应该被销毁
+fn despawn_far_away_entities(
+    mut commands: Commands,
+    query: Query<(Entity, &GlobalTransform), With<Velocity>>,
+) {
     // 对查询结果进行迭代，每次迭代得到一个实体和全局变换的元组
     for (entity, transform) in query.iter() {
         // 计算实体的位置与原点的距离
         let distance = transform.translation().distance(Vec3::ZERO);
 
-        // 如果实体的位置距离原点的距离大于 `DESPAWN_DISTANCE`，则销毁该实体
+        // 如果实体的位置距离原点的距离大于 `DESPAWN_DISTANCE`，则连同子实体一起销毁该实体
         if distance > DESPAWN_DISTANCE {
-            commands.entity(entity).despawn();
+            commands.entity(entity).despawn_recursive();
         }
     }
 }
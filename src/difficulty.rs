@@ -0,0 +1,279 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    collision_detection::{DestroyedKind, EntityDestroyed},
+    console::ConsoleCommandsExt,
+    schedule::InGameSet,
+    settings::Settings,
+    spaceship::{Spaceship, SpaceshipMissile},
+};
+
+// 定义动态难度中，存活多长时间（单位为秒）被认为玩得很好
+const TARGET_TIME_ALIVE_SECONDS: f32 = 60.0;
+
+// 定义动态难度统计死亡次数的时间窗口，单位为秒
+const DEATH_WINDOW_SECONDS: f32 = 60.0;
+
+// 定义动态难度统计命中率的时间窗口，单位为秒，更早的射击和命中会逐渐被遗忘
+const ACCURACY_WINDOW_SECONDS: f32 = 30.0;
+
+// 定义计算命中率所需的最少射击次数，射击次数不足时认为命中率为 0.5
+const MIN_SHOTS: f32 = 5.0;
+
+// 定义每次死亡降低的表现分数
+const DEATH_PENALTY: f32 = 0.5;
+
+// 定义表现分数换算为难度等级的比例
+const SKILL_TO_LEVEL: f32 = 1.5;
+
+// 定义动态难度等级每秒最多变化的数值，避免难度突然跳变
+const LEVEL_CHANGE_PER_SECOND: f32 = 0.05;
+
+// `Difficulty` 资源表示玩家选择的难度，它保存在设置文件中
+// `Dynamic` 根据玩家最近的表现在简单和疯狂之间连续调整
+#[derive(Resource, Reflect, Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[reflect(Resource)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    Insane,
+    Dynamic,
+}
+
+impl Difficulty {
+    // 所有的难度，预设从简单到困难排列，动态难度在最后
+    pub const ALL: [Difficulty; 5] = [
+        Difficulty::Easy,
+        Difficulty::Normal,
+        Difficulty::Hard,
+        Difficulty::Insane,
+        Difficulty::Dynamic,
+    ];
+
+    // 返回预设对应的难度等级，简单为 0.0，疯狂为 3.0，动态难度没有固定的等级
+    pub fn level(&self) -> Option<f32> {
+        match self {
+            Difficulty::Easy => Some(0.0),
+            Difficulty::Normal => Some(1.0),
+            Difficulty::Hard => Some(2.0),
+            Difficulty::Insane => Some(3.0),
+            Difficulty::Dynamic => None,
+        }
+    }
+}
+
+// `DifficultyTuning` 资源保存当前难度下的游戏参数，其他插件读取它调整生成和伤害
+#[derive(Resource, Reflect, Debug, Clone, Copy, PartialEq)]
+#[reflect(Resource)]
+pub struct DifficultyTuning {
+    // 小行星生成间隔的倍数，越小生成越快
+    pub spawn_interval_scale: f32,
+    // 小行星速度和加速度的倍数
+    pub asteroid_speed_scale: f32,
+    // 玩家导弹伤害的倍数，玩家飞船受到的伤害除以这个倍数
+    pub damage_scale: f32,
+}
+
+impl Default for DifficultyTuning {
+    fn default() -> Self {
+        Self::for_level(1.0)
+    }
+}
+
+impl DifficultyTuning {
+    // 各个预设的参数，按难度等级排列
+    const PRESETS: [DifficultyTuning; 4] = [
+        DifficultyTuning {
+            spawn_interval_scale: 1.5,
+            asteroid_speed_scale: 0.7,
+            damage_scale: 1.5,
+        },
+        DifficultyTuning {
+            spawn_interval_scale: 1.0,
+            asteroid_speed_scale: 1.0,
+            damage_scale: 1.0,
+        },
+        DifficultyTuning {
+            spawn_interval_scale: 0.7,
+            asteroid_speed_scale: 1.3,
+            damage_scale: 0.8,
+        },
+        DifficultyTuning {
+            spawn_interval_scale: 0.45,
+            asteroid_speed_scale: 1.7,
+            damage_scale: 0.6,
+        },
+    ];
+
+    // 返回难度等级对应的参数，等级在两个预设之间时对参数进行线性插值
+    pub fn for_level(level: f32) -> Self {
+        let max_level = (Self::PRESETS.len() - 1) as f32;
+        let level = level.clamp(0.0, max_level);
+        let lower = (level.floor() as usize).min(Self::PRESETS.len() - 2);
+        let t = level - lower as f32;
+        let (a, b) = (Self::PRESETS[lower], Self::PRESETS[lower + 1]);
+        Self {
+            spawn_interval_scale: a.spawn_interval_scale.lerp(b.spawn_interval_scale, t),
+            asteroid_speed_scale: a.asteroid_speed_scale.lerp(b.asteroid_speed_scale, t),
+            damage_scale: a.damage_scale.lerp(b.damage_scale, t),
+        }
+    }
+}
+
+// `PlayerPerformance` 资源记录玩家最近的表现，用于动态难度
+#[derive(Resource, Reflect, Debug, Clone, PartialEq)]
+#[reflect(Resource)]
+pub struct PlayerPerformance {
+    // 距离上一次死亡的时间，单位为秒
+    pub time_alive: f32,
+    // 最近的射击次数和击毁次数，随时间逐渐衰减
+    pub shots: f32,
+    pub hits: f32,
+    // 最近的死亡时间，单位为游戏开始后的秒数
+    pub recent_deaths: Vec<f32>,
+    // 动态难度当前的等级
+    pub level: f32,
+}
+
+impl Default for PlayerPerformance {
+    fn default() -> Self {
+        Self {
+            time_alive: 0.0,
+            shots: 0.0,
+            hits: 0.0,
+            recent_deaths: Vec::new(),
+            level: 1.0,
+        }
+    }
+}
+
+impl PlayerPerformance {
+    // 返回最近的命中率，射击次数不足时返回 0.5
+    pub fn accuracy(&self) -> f32 {
+        if self.shots < MIN_SHOTS {
+            0.5
+        } else {
+            (self.hits / self.shots).min(1.0)
+        }
+    }
+
+    // 根据存活时间、命中率和死亡次数返回动态难度的目标等级
+    pub fn target_level(&self) -> f32 {
+        let alive = (self.time_alive / TARGET_TIME_ALIVE_SECONDS).min(1.0);
+        let skill = alive + self.accuracy() - DEATH_PENALTY * self.recent_deaths.len() as f32;
+        (skill * SKILL_TO_LEVEL).clamp(0.0, 3.0)
+    }
+}
+
+// 定义一个名为 `DifficultyPlugin` 的结构体
+// 它根据 `Difficulty` 计算 `DifficultyTuning`，动态难度下统计玩家的存活时间、命中率和死亡次数并逐渐调整难度
+pub struct DifficultyPlugin;
+
+impl Plugin for DifficultyPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Difficulty>()
+            .register_type::<DifficultyTuning>()
+            .register_type::<PlayerPerformance>()
+            .register_type::<Vec<f32>>()
+            .init_resource::<Difficulty>()
+            .init_resource::<DifficultyTuning>()
+            .init_resource::<PlayerPerformance>()
+            .add_event::<EntityDestroyed>()
+            .add_systems(
+                Update,
+                (track_performance, update_tuning)
+                    .chain()
+                    .after(InGameSet::CollisionDetection),
+            )
+            .add_console_command(
+                "difficulty",
+                "difficulty [easy|normal|hard|insane|dynamic] - show or change the difficulty",
+                difficulty_command,
+            );
+    }
+}
+
+// 这个函数统计玩家的射击次数、击毁次数、死亡次数和存活时间
+fn track_performance(
+    mut performance: ResMut<PlayerPerformance>,
+    mut destroyed_events: EventReader<EntityDestroyed>,
+    missile_query: Query<(), Added<SpaceshipMissile>>,
+    spaceship_query: Query<(), With<Spaceship>>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed_seconds();
+    let delta_seconds = time.delta_seconds();
+
+    // 旧的射击和命中逐渐被遗忘
+    let decay = (1.0 - delta_seconds / ACCURACY_WINDOW_SECONDS).max(0.0);
+    performance.shots = performance.shots * decay + missile_query.iter().count() as f32;
+    performance.hits *= decay;
+
+    for event in destroyed_events.read() {
+        match event.kind {
+            DestroyedKind::Spaceship => {
+                performance.recent_deaths.push(now);
+                performance.time_alive = 0.0;
+            }
+            _ if event.by_player.is_some() => performance.hits += 1.0,
+            _ => {}
+        }
+    }
+    performance
+        .recent_deaths
+        .retain(|&death| now - death < DEATH_WINDOW_SECONDS);
+    if !spaceship_query.is_empty() {
+        performance.time_alive += delta_seconds;
+    }
+}
+
+// 这个函数根据难度更新游戏参数，动态难度下难度等级逐渐向目标等级移动
+fn update_tuning(
+    difficulty: Res<Difficulty>,
+    mut performance: ResMut<PlayerPerformance>,
+    mut tuning: ResMut<DifficultyTuning>,
+    time: Res<Time>,
+) {
+    let level = match difficulty.level() {
+        Some(level) => level,
+        None => {
+            let max_change = LEVEL_CHANGE_PER_SECOND * time.delta_seconds();
+            let change =
+                (performance.target_level() - performance.level).clamp(-max_change, max_change);
+            performance.level += change;
+            performance.level
+        }
+    };
+    let wanted = DifficultyTuning::for_level(level);
+    if *tuning != wanted {
+        *tuning = wanted;
+    }
+}
+
+// `difficulty` 控制台命令显示或修改难度
+// 存在设置时修改的难度也写入设置，否则设置改变时会把难度改回去
+fn difficulty_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    if let Some(name) = args.first() {
+        let difficulty = Difficulty::ALL
+            .into_iter()
+            .find(|difficulty| format!("{difficulty:?}").eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("invalid value `{name}` for <difficulty>"))?;
+        if let Some(mut settings) = world.get_resource_mut::<Settings>() {
+            settings.difficulty = difficulty;
+        }
+        world.insert_resource(difficulty);
+    }
+    let difficulty = *world.resource::<Difficulty>();
+    // 预设的参数立即生效，动态难度显示当前的参数
+    let tuning = difficulty
+        .level()
+        .map(DifficultyTuning::for_level)
+        .unwrap_or(*world.resource::<DifficultyTuning>());
+    Ok(format!(
+        "difficulty {difficulty:?}: spawn interval x{:.2}, asteroid speed x{:.2}, damage x{:.2}",
+        tuning.spawn_interval_scale, tuning.asteroid_speed_scale, tuning.damage_scale
+    ))
+}
@@ -0,0 +1,340 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use rand::prelude::*;
+
+use crate::{
+    asset_loader::SceneAssets,
+    collision_detection::{Collider, DestroyedKind, EntityDestroyed},
+    difficulty::DifficultyTuning,
+    health::{Health, MISSILE_DAMAGE},
+    movement::{Acceleration, MovingObjectBundle, Velocity},
+    rng::GameRng,
+    schedule::InGameSet,
+    spaceship::{MissileOwner, Spaceship, SpaceshipMissile},
+};
+
+// 定义生成敌方飞船的时间间隔，单位为秒，初始值为 8.0
+const SPAWN_TIME_SECONDS: f32 = 8.0;
+
+// 定义同时存在的敌方飞船的最大数量
+const MAX_ENEMIES: usize = 3;
+
+// 定义敌方飞船生成位置到原点的距离，敌方飞船从场地边缘进入
+const SPAWN_DISTANCE: f32 = 60.0;
+
+// 定义敌方飞船的半径，初始值为 4.0
+const ENEMY_RADIUS: f32 = 4.0;
+
+// 定义敌方飞船模型的缩放比例
+const ENEMY_SCALE: f32 = 0.8;
+
+// 定义敌方飞船的生命值，普通难度下一发导弹就能击毁敌方飞船
+const ENEMY_HEALTH: f32 = 1.0;
+
+// 定义敌方飞船的最大速度
+const ENEMY_MAX_SPEED: f32 = 15.0;
+
+// 定义敌方飞船的最大转向力，即加速度的最大值
+const ENEMY_MAX_FORCE: f32 = 20.0;
+
+// 定义环绕型敌方飞船环绕玩家的半径
+const ORBIT_RADIUS: f32 = 25.0;
+
+// 定义抵达行为开始减速的距离
+const ARRIVE_SLOWING_RADIUS: f32 = 15.0;
+
+// 定义敌方飞船逃离玩家的距离，玩家飞船进入这个范围时环绕型敌方飞船会逃离
+const FLEE_RADIUS: f32 = 12.0;
+
+// 定义敌方飞船的开火间隔，单位为秒
+const FIRE_INTERVAL_SECONDS: f32 = 2.0;
+
+// 定义敌方飞船开火的最大距离
+const FIRE_RANGE: f32 = 45.0;
+
+// 定义敌方子弹的速度
+const PROJECTILE_SPEED: f32 = 30.0;
+
+// 定义敌方子弹的半径
+const PROJECTILE_RADIUS: f32 = 1.0;
+
+// 定义敌方子弹在敌方飞船前方生成的距离，需要大于两者半径之和，避免击中自己
+const PROJECTILE_FORWARD_SPAWN_SCALAR: f32 = 6.0;
+
+// `Enemy` 组件标记敌方飞船
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+pub struct Enemy;
+
+// `EnemyBehavior` 组件决定敌方飞船如何接近玩家
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq, Eq)]
+#[reflect(Component)]
+pub enum EnemyBehavior {
+    // 直接追击玩家的飞船
+    Seek,
+    // 抵达玩家周围后环绕玩家飞行，玩家靠得太近时逃离
+    Orbit,
+}
+
+// `EnemyWeapon` 组件保存敌方飞船的开火计时器
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+pub struct EnemyWeapon {
+    timer: Timer,
+}
+
+impl Default for EnemyWeapon {
+    fn default() -> Self {
+        Self {
+            timer: Timer::from_seconds(FIRE_INTERVAL_SECONDS, TimerMode::Repeating),
+        }
+    }
+}
+
+// `EnemyProjectile` 组件标记敌方飞船发射的子弹
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+pub struct EnemyProjectile;
+
+#[derive(Resource, Reflect, Debug)]
+#[reflect(Resource)]
+pub struct EnemySpawnTimer {
+    timer: Timer,
+}
+
+impl Default for EnemySpawnTimer {
+    fn default() -> Self {
+        Self {
+            timer: Timer::from_seconds(SPAWN_TIME_SECONDS, TimerMode::Repeating),
+        }
+    }
+}
+
+pub struct EnemyPlugin;
+
+impl Plugin for EnemyPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Enemy>()
+            .register_type::<EnemyBehavior>()
+            .register_type::<EnemyWeapon>()
+            .register_type::<EnemyProjectile>()
+            .register_type::<EnemySpawnTimer>()
+            .register_type::<Health>()
+            .register_type::<GameRng>()
+            .register_type::<TimerMode>()
+            .init_resource::<GameRng>()
+            .init_resource::<EnemySpawnTimer>()
+            .init_resource::<DifficultyTuning>()
+            .add_systems(
+                Update,
+                (spawn_enemy, steer_enemies, enemy_weapon_controls)
+                    .in_set(InGameSet::EntityUpdates),
+            )
+            .add_systems(
+                Update,
+                handle_enemy_collisions.in_set(InGameSet::DespawnEntities),
+            );
+    }
+}
+
+// 寻找行为：返回以最大速度朝目标前进所需的转向力
+pub fn seek(position: Vec3, velocity: Vec3, target: Vec3, max_speed: f32) -> Vec3 {
+    let desired = (target - position).normalize_or_zero() * max_speed;
+    desired - velocity
+}
+
+// 逃离行为：返回以最大速度远离目标所需的转向力
+pub fn flee(position: Vec3, velocity: Vec3, target: Vec3, max_speed: f32) -> Vec3 {
+    let desired = -(target - position).normalize_or_zero() * max_speed;
+    desired - velocity
+}
+
+// 抵达行为：与寻找行为类似，但在进入 `slowing_radius` 之后逐渐减速，最终停在目标处
+pub fn arrive(
+    position: Vec3,
+    velocity: Vec3,
+    target: Vec3,
+    max_speed: f32,
+    slowing_radius: f32,
+) -> Vec3 {
+    let offset = target - position;
+    let distance = offset.length();
+    let speed = max_speed * (distance / slowing_radius).min(1.0);
+    let desired = offset.normalize_or_zero() * speed;
+    desired - velocity
+}
+
+// 这个函数定期在场地边缘生成敌方飞船
+fn spawn_enemy(
+    mut commands: Commands,
+    mut spawn_timer: ResMut<EnemySpawnTimer>,
+    query: Query<(), With<Enemy>>,
+    time: Res<Time>,
+    scene_assets: Res<SceneAssets>,
+    mut rng: ResMut<GameRng>,
+) {
+    spawn_timer.timer.tick(time.delta());
+    if !spawn_timer.timer.just_finished() || query.iter().count() >= MAX_ENEMIES {
+        return;
+    }
+
+    // 在以原点为圆心、`SPAWN_DISTANCE` 为半径的圆上随机选择一个位置
+    let angle = rng.gen_range(0.0..TAU);
+    let translation = Vec3::new(angle.cos(), 0.0, angle.sin()) * SPAWN_DISTANCE;
+    // 随机选择敌方飞船的行为
+    let behavior = if rng.gen_bool(0.5) {
+        EnemyBehavior::Seek
+    } else {
+        EnemyBehavior::Orbit
+    };
+
+    commands.spawn((
+        MovingObjectBundle {
+            velocity: Velocity::new(Vec3::ZERO),
+            acceleration: Acceleration::new(Vec3::ZERO),
+            collider: Collider::new(ENEMY_RADIUS),
+            model: SceneBundle {
+                scene: scene_assets.enemy.clone(),
+                transform: Transform::from_translation(translation)
+                    .with_scale(Vec3::splat(ENEMY_SCALE)),
+                ..default()
+            },
+        },
+        Enemy,
+        behavior,
+        Health::new(ENEMY_HEALTH),
+        EnemyWeapon::default(),
+    ));
+}
+
+// 这个函数根据每艘敌方飞船的行为计算转向力，并把它写入加速度
+fn steer_enemies(
+    mut query: Query<(&mut Transform, &Velocity, &mut Acceleration, &EnemyBehavior), With<Enemy>>,
+    spaceship_query: Query<&GlobalTransform, With<Spaceship>>,
+) {
+    for (mut transform, velocity, mut acceleration, behavior) in query.iter_mut() {
+        let position = transform.translation;
+
+        // 寻找距离最近的玩家飞船
+        let target = spaceship_query
+            .iter()
+            .map(|spaceship| spaceship.translation())
+            .min_by(|a, b| a.distance(position).total_cmp(&b.distance(position)));
+
+        let steering = match (target, behavior) {
+            // 没有玩家飞船时，敌方飞船减速并停在原地
+            (None, _) => -velocity.value,
+            (Some(target), EnemyBehavior::Seek) => {
+                seek(position, velocity.value, target, ENEMY_MAX_SPEED)
+            }
+            (Some(target), EnemyBehavior::Orbit) => {
+                let offset = position - target;
+                let distance = offset.length();
+                if distance < FLEE_RADIUS {
+                    flee(position, velocity.value, target, ENEMY_MAX_SPEED)
+                } else {
+                    // 抵达环绕半径上最近的点，再沿切线方向提前一段距离，从而绕着玩家飞行
+                    let radial = offset.normalize_or_zero();
+                    let tangent = Vec3::Y.cross(radial);
+                    let orbit_point =
+                        target + radial * ORBIT_RADIUS + tangent * ARRIVE_SLOWING_RADIUS;
+                    arrive(
+                        position,
+                        velocity.value,
+                        orbit_point,
+                        ENEMY_MAX_SPEED,
+                        ARRIVE_SLOWING_RADIUS,
+                    )
+                }
+            }
+        };
+        acceleration.value = steering.clamp_length_max(ENEMY_MAX_FORCE);
+
+        // 让敌方飞船的机头朝向它的运动方向
+        if velocity.value.length_squared() > f32::EPSILON {
+            transform.look_to(-velocity.value, Vec3::Y);
+        }
+    }
+}
+
+// 这个函数让敌方飞船定期向射程内最近的玩家飞船开火
+fn enemy_weapon_controls(
+    mut commands: Commands,
+    mut query: Query<(&Transform, &mut EnemyWeapon), With<Enemy>>,
+    spaceship_query: Query<&GlobalTransform, With<Spaceship>>,
+    time: Res<Time>,
+    scene_assets: Res<SceneAssets>,
+) {
+    for (transform, mut weapon) in query.iter_mut() {
+        weapon.timer.tick(time.delta());
+        if !weapon.timer.just_finished() {
+            continue;
+        }
+
+        let position = transform.translation;
+        let Some(target) = spaceship_query
+            .iter()
+            .map(|spaceship| spaceship.translation())
+            .filter(|target| target.distance(position) <= FIRE_RANGE)
+            .min_by(|a, b| a.distance(position).total_cmp(&b.distance(position)))
+        else {
+            continue;
+        };
+
+        let direction = (target - position).normalize_or_zero();
+        commands.spawn((
+            MovingObjectBundle {
+                velocity: Velocity::new(direction * PROJECTILE_SPEED),
+                acceleration: Acceleration::new(Vec3::ZERO),
+                collider: Collider::new(PROJECTILE_RADIUS),
+                model: SceneBundle {
+                    scene: scene_assets.missiles.clone(),
+                    transform: Transform::from_translation(
+                        position + direction * PROJECTILE_FORWARD_SPAWN_SCALAR,
+                    ),
+                    ..default()
+                },
+            },
+            EnemyProjectile,
+        ));
+    }
+}
+
+// 这个函数用于处理敌方飞船的碰撞事件，敌方飞船只会被玩家的导弹击毁
+// 每发导弹造成的伤害乘以难度的伤害倍数，敌方飞船没有被击毁时导弹被吸收
+fn handle_enemy_collisions(
+    mut commands: Commands,
+    mut query: Query<(Entity, &GlobalTransform, &Collider, Option<&mut Health>), With<Enemy>>,
+    missile_query: Query<Option<&MissileOwner>, With<SpaceshipMissile>>,
+    tuning: Res<DifficultyTuning>,
+    mut destroyed: EventWriter<EntityDestroyed>,
+) {
+    for (entity, transform, collider, mut health) in query.iter_mut() {
+        let mut destroyed_by = None;
+        for &collided_entity in collider.colliding_entities.iter() {
+            let Ok(owner) = missile_query.get(collided_entity) else {
+                continue;
+            };
+            if let Some(health) = health.as_deref_mut() {
+                if !health.damage(MISSILE_DAMAGE * tuning.damage_scale) {
+                    commands.entity(collided_entity).despawn_recursive();
+                    continue;
+                }
+            }
+            destroyed_by = Some(owner);
+            break;
+        }
+        let Some(owner) = destroyed_by else {
+            continue;
+        };
+        commands.entity(entity).despawn_recursive();
+        destroyed.send(EntityDestroyed {
+            entity,
+            kind: DestroyedKind::Enemy,
+            translation: transform.translation(),
+            radius: collider.radius,
+            by_player: owner.map(|&MissileOwner(player)| player),
+        });
+    }
+}
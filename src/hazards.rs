@@ -0,0 +1,446 @@
+use std::ops::Range;
+
+use bevy::{ecs::system::CommandQueue, prelude::*};
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    collision_detection::Collider,
+    console::{parse_arg, parse_optional_arg, ConsoleCommandsExt},
+    movement::{ExternalVelocity, MovementSet, SpeedScale, Velocity},
+    particles::Particle,
+    rng::GameRng,
+    spaceship::Spaceship,
+    wave::WaveStarted,
+};
+
+// 定义引力井的影响半径和强度，引力产生的加速度等于强度除以距离的平方
+const GRAVITY_WELL_RADIUS: f32 = 30.0;
+const GRAVITY_WELL_STRENGTH: f32 = 400.0;
+
+// 定义黑洞的影响半径、强度和视界半径，碰到视界的物体会像撞上小行星一样被摧毁
+const BLACK_HOLE_RADIUS: f32 = 40.0;
+const BLACK_HOLE_STRENGTH: f32 = 900.0;
+const BLACK_HOLE_HORIZON: f32 = 3.0;
+
+// 定义计算引力时使用的最小距离，避免物体离中心很近时引力趋于无穷大
+const GRAVITY_MIN_DISTANCE: f32 = 5.0;
+
+// 定义星云的半径，以及星云中的物体移动速度的倍数
+const NEBULA_RADIUS: f32 = 20.0;
+const NEBULA_SPEED_SCALE: f32 = 0.5;
+
+// 定义太阳耀斑的影响半径和在中心处把物体向外推开的加速度，加速度在边缘处减小为零
+const SOLAR_FLARE_RADIUS: f32 = 35.0;
+const SOLAR_FLARE_STRENGTH: f32 = 25.0;
+
+// 定义太阳耀斑爆发的周期和每次爆发持续的时间，单位为秒
+const SOLAR_FLARE_PERIOD_SECONDS: f32 = 6.0;
+const SOLAR_FLARE_ACTIVE_SECONDS: f32 = 1.5;
+
+// 定义从第几波开始出现环境危害，每一波开始时旧的危害消失，并随机生成一个新的危害
+const FIRST_HAZARD_WAVE: u32 = 2;
+
+// 定义随机生成的危害的位置范围，它位于可见范围之内
+const HAZARD_RANGE_X: Range<f32> = -40.0..40.0;
+const HAZARD_RANGE_Z: Range<f32> = -20.0..20.0;
+
+// 定义随机生成的危害与任何飞船之间的最小距离
+const HAZARD_SAFE_RADIUS: f32 = 25.0;
+
+// 定义为危害寻找安全位置的最多尝试次数，全部失败时这一波不生成危害
+const MAX_PLACEMENT_ATTEMPTS: usize = 8;
+
+// 定义危害的显示圆盘在游戏平面下方的深度，使它不会挡住其他物体
+const VISUAL_DEPTH: f32 = -2.0;
+
+// `Hazard` 组件标记环境危害实体，并表示危害的种类
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[reflect(Component)]
+pub enum Hazard {
+    // 把范围内的物体拉向中心
+    GravityWell,
+    // 更强的引力井，中心的视界会摧毁碰到它的物体
+    BlackHole,
+    // 使范围内的物体移动变慢，并且使飞船无法开启护盾
+    Nebula,
+    // 周期性地爆发，把范围内的物体向外推开
+    SolarFlare,
+}
+
+impl Hazard {
+    pub const ALL: [Hazard; 4] = [
+        Hazard::GravityWell,
+        Hazard::BlackHole,
+        Hazard::Nebula,
+        Hazard::SolarFlare,
+    ];
+
+    // 返回危害在控制台命令中使用的名字
+    pub fn name(&self) -> &'static str {
+        match self {
+            Hazard::GravityWell => "well",
+            Hazard::BlackHole => "blackhole",
+            Hazard::Nebula => "nebula",
+            Hazard::SolarFlare => "flare",
+        }
+    }
+
+    // 返回危害的影响半径
+    pub fn radius(&self) -> f32 {
+        match self {
+            Hazard::GravityWell => GRAVITY_WELL_RADIUS,
+            Hazard::BlackHole => BLACK_HOLE_RADIUS,
+            Hazard::Nebula => NEBULA_RADIUS,
+            Hazard::SolarFlare => SOLAR_FLARE_RADIUS,
+        }
+    }
+
+    // 返回危害的显示颜色
+    fn color(&self) -> Color {
+        match self {
+            Hazard::GravityWell => Color::rgba(0.4, 0.2, 0.8, 0.25),
+            Hazard::BlackHole => Color::rgba(0.05, 0.0, 0.1, 0.6),
+            Hazard::Nebula => Color::rgba(0.2, 0.7, 0.7, 0.3),
+            Hazard::SolarFlare => Color::rgba(1.0, 0.6, 0.1, 0.35),
+        }
+    }
+}
+
+// `GravityWell` 组件把 `radius` 范围内的物体拉向中心，引力产生的加速度为 `strength` 除以距离的平方
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub struct GravityWell {
+    pub radius: f32,
+    pub strength: f32,
+}
+
+// `Nebula` 组件使 `radius` 范围内的物体的位移乘以 `speed_scale`，并使飞船无法开启护盾
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub struct Nebula {
+    pub radius: f32,
+    pub speed_scale: f32,
+}
+
+// `SolarFlare` 组件在每个周期的开始爆发 `active_seconds` 秒，爆发时把 `radius` 范围内的物体向外推开
+#[derive(Component, Reflect, Debug, Clone, PartialEq)]
+#[reflect(Component)]
+pub struct SolarFlare {
+    pub radius: f32,
+    pub strength: f32,
+    pub active_seconds: f32,
+    pub timer: Timer,
+}
+
+impl SolarFlare {
+    // 耀斑是否正在爆发
+    pub fn is_active(&self) -> bool {
+        self.timer.elapsed_secs() < self.active_seconds
+    }
+}
+
+// `InNebula` 组件标记位于星云中的实体
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+pub struct InNebula;
+
+// `HazardRadius` 组件记录网络客户端上同步而来的危害的影响半径，客户端按它显示危害的范围
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub struct HazardRadius(pub f32);
+
+// `HazardVisual` 组件标记危害的显示圆盘
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+pub struct HazardVisual;
+
+// `FieldQuery` 查询受力场影响的实体，拥有 `ExternalVelocity` 的实体由它保存外力的效果
+type FieldQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static Transform,
+        &'static mut Velocity,
+        Option<&'static mut ExternalVelocity>,
+    ),
+>;
+
+// `NebulaTargetQuery` 查询可能位于星云中的实体，以及它们当前的速度倍数，粒子不受星云影响
+type NebulaTargetQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static Transform,
+        Option<&'static SpeedScale>,
+        Has<InNebula>,
+    ),
+    (With<Velocity>, Without<Particle>),
+>;
+
+// 定义一个名为 `HazardPlugin` 的结构体
+// 它从第 `FIRST_HAZARD_WAVE` 波开始，在每一波开始时随机生成一个环境危害：引力井、黑洞、星云或者太阳耀斑
+// 危害作用于范围内所有拥有 `Velocity` 的实体，力场在 `MovementSet::ApplyForces` 中改变速度，也就是在 `update_velocity` 之前
+// 没有网格和材质资源（例如无窗口模拟）时危害不会显示，但仍然会生效
+pub struct HazardPlugin;
+
+impl Plugin for HazardPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Hazard>()
+            .register_type::<GravityWell>()
+            .register_type::<Nebula>()
+            .register_type::<SolarFlare>()
+            .register_type::<InNebula>()
+            .register_type::<HazardRadius>()
+            .register_type::<HazardVisual>()
+            .register_type::<GameRng>()
+            .init_resource::<GameRng>()
+            .add_event::<WaveStarted>()
+            .add_systems(
+                Update,
+                (
+                    spawn_wave_hazards,
+                    tick_solar_flares,
+                    apply_field_forces,
+                    update_nebula_effects,
+                )
+                    .chain()
+                    .in_set(MovementSet::ApplyForces),
+            )
+            .add_systems(Update, (add_hazard_visuals, update_flare_visuals))
+            .add_console_command(
+                "spawn hazard",
+                "spawn hazard <well|blackhole|nebula|flare> [x] [z] - spawn an environmental hazard",
+                spawn_hazard_command,
+            );
+    }
+}
+
+// 这个函数在 `translation` 处生成一个危害，返回危害实体
+pub fn spawn_hazard(commands: &mut Commands, hazard: Hazard, translation: Vec3) -> Entity {
+    let mut entity_commands = commands.spawn((
+        hazard,
+        SpatialBundle::from_transform(Transform::from_translation(translation)),
+    ));
+    match hazard {
+        Hazard::GravityWell => {
+            entity_commands.insert(GravityWell {
+                radius: GRAVITY_WELL_RADIUS,
+                strength: GRAVITY_WELL_STRENGTH,
+            });
+        }
+        Hazard::BlackHole => {
+            entity_commands.insert((
+                GravityWell {
+                    radius: BLACK_HOLE_RADIUS,
+                    strength: BLACK_HOLE_STRENGTH,
+                },
+                Collider::new(BLACK_HOLE_HORIZON),
+            ));
+        }
+        Hazard::Nebula => {
+            entity_commands.insert(Nebula {
+                radius: NEBULA_RADIUS,
+                speed_scale: NEBULA_SPEED_SCALE,
+            });
+        }
+        Hazard::SolarFlare => {
+            entity_commands.insert(SolarFlare {
+                radius: SOLAR_FLARE_RADIUS,
+                strength: SOLAR_FLARE_STRENGTH,
+                active_seconds: SOLAR_FLARE_ACTIVE_SECONDS,
+                timer: Timer::from_seconds(SOLAR_FLARE_PERIOD_SECONDS, TimerMode::Repeating),
+            });
+        }
+    }
+    entity_commands.id()
+}
+
+// 这个函数在每一波开始时删除旧的危害，并在离所有飞船足够远的地方随机生成一个新的危害
+fn spawn_wave_hazards(
+    mut commands: Commands,
+    mut wave_started: EventReader<WaveStarted>,
+    hazard_query: Query<Entity, With<Hazard>>,
+    spaceship_query: Query<&Transform, With<Spaceship>>,
+    mut rng: ResMut<GameRng>,
+) {
+    let Some(event) = wave_started.read().last() else {
+        return;
+    };
+    for entity in hazard_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    if event.number < FIRST_HAZARD_WAVE {
+        return;
+    }
+
+    let rng = rng.as_mut();
+    let hazard = *Hazard::ALL.choose(rng).unwrap();
+    let translation = (0..MAX_PLACEMENT_ATTEMPTS)
+        .map(|_| {
+            Vec3::new(
+                rng.gen_range(HAZARD_RANGE_X),
+                0.0,
+                rng.gen_range(HAZARD_RANGE_Z),
+            )
+        })
+        .find(|&translation| {
+            spaceship_query
+                .iter()
+                .all(|ship| ship.translation.distance(translation) >= HAZARD_SAFE_RADIUS)
+        });
+    if let Some(translation) = translation {
+        info!("第 {} 波出现了危害 {:?}", event.number, hazard);
+        spawn_hazard(&mut commands, hazard, translation);
+    }
+}
+
+// 这个函数更新太阳耀斑的爆发周期
+fn tick_solar_flares(mut query: Query<&mut SolarFlare>, time: Res<Time>) {
+    for mut flare in query.iter_mut() {
+        flare.timer.tick(time.delta());
+    }
+}
+
+// 这个函数计算引力井和正在爆发的太阳耀斑对范围内每个实体产生的加速度，并据此改变实体的速度
+fn apply_field_forces(
+    mut query: FieldQuery,
+    well_query: Query<(&Transform, &GravityWell), Without<Velocity>>,
+    flare_query: Query<(&Transform, &SolarFlare), Without<Velocity>>,
+    time: Res<Time>,
+) {
+    if well_query.is_empty() && flare_query.is_empty() {
+        return;
+    }
+    for (transform, mut velocity, external) in query.iter_mut() {
+        let translation = transform.translation;
+        let mut acceleration = Vec3::ZERO;
+        for (well_transform, well) in well_query.iter() {
+            let offset = well_transform.translation - translation;
+            let distance = offset.length();
+            if distance < well.radius {
+                let distance = distance.max(GRAVITY_MIN_DISTANCE);
+                acceleration += offset.normalize_or_zero() * well.strength / (distance * distance);
+            }
+        }
+        for (flare_transform, flare) in flare_query.iter() {
+            let offset = translation - flare_transform.translation;
+            let distance = offset.length();
+            if flare.is_active() && distance < flare.radius {
+                acceleration +=
+                    offset.normalize_or_zero() * flare.strength * (1.0 - distance / flare.radius);
+            }
+        }
+
+        let change = acceleration * time.delta_seconds();
+        match external {
+            Some(mut external) => external.value += change,
+            None => velocity.value += change,
+        }
+    }
+}
+
+// 这个函数为位于星云中的实体添加 `InNebula` 和 `SpeedScale` 组件，离开星云时删除它们
+// 实体同时位于多个星云中时，使用最慢的速度倍数
+fn update_nebula_effects(
+    mut commands: Commands,
+    query: NebulaTargetQuery,
+    nebula_query: Query<(&Transform, &Nebula)>,
+) {
+    for (entity, transform, speed_scale, in_nebula) in query.iter() {
+        let scale = nebula_query
+            .iter()
+            .filter(|(nebula_transform, nebula)| {
+                nebula_transform.translation.distance(transform.translation) < nebula.radius
+            })
+            .map(|(_, nebula)| nebula.speed_scale)
+            .reduce(f32::min);
+        match scale {
+            Some(scale)
+                if !in_nebula
+                    || speed_scale.map(|speed_scale| speed_scale.value) != Some(scale) =>
+            {
+                // 粒子和道具可能在同一帧内被销毁，因此实体不存在时跳过插入
+                commands
+                    .entity(entity)
+                    .try_insert((InNebula, SpeedScale { value: scale }));
+            }
+            None if in_nebula => {
+                commands.entity(entity).remove::<(InNebula, SpeedScale)>();
+            }
+            _ => {}
+        }
+    }
+}
+
+// 这个函数为新生成的危害添加一个表示影响范围的半透明圆盘，网络客户端也用它显示同步来的危害
+pub fn add_hazard_visuals(
+    mut commands: Commands,
+    query: Query<(Entity, &Hazard, Option<&HazardRadius>), Added<Hazard>>,
+    meshes: Option<ResMut<Assets<Mesh>>>,
+    materials: Option<ResMut<Assets<StandardMaterial>>>,
+) {
+    let (Some(mut meshes), Some(mut materials)) = (meshes, materials) else {
+        return;
+    };
+    for (entity, hazard, radius) in query.iter() {
+        let radius = radius.map_or(hazard.radius(), |radius| radius.0);
+        let visual = commands
+            .spawn((
+                PbrBundle {
+                    mesh: meshes.add(Cylinder::new(radius, 0.1)),
+                    material: materials.add(StandardMaterial {
+                        base_color: hazard.color(),
+                        alpha_mode: AlphaMode::Blend,
+                        unlit: true,
+                        ..default()
+                    }),
+                    transform: Transform::from_xyz(0.0, VISUAL_DEPTH, 0.0),
+                    ..default()
+                },
+                HazardVisual,
+            ))
+            .id();
+        commands.entity(entity).add_child(visual);
+    }
+}
+
+// 这个函数只在太阳耀斑爆发时显示它的圆盘
+fn update_flare_visuals(
+    flare_query: Query<(&SolarFlare, &Children)>,
+    mut visual_query: Query<&mut Visibility, With<HazardVisual>>,
+) {
+    for (flare, children) in flare_query.iter() {
+        let visibility = if flare.is_active() {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        for &child in children.iter() {
+            if let Ok(mut child_visibility) = visual_query.get_mut(child) {
+                child_visibility.set_if_neq(visibility);
+            }
+        }
+    }
+}
+
+// `spawn hazard` 控制台命令在指定位置生成一个危害，默认位置是场地中央
+fn spawn_hazard_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let name: String = parse_arg(args, 0, "kind")?;
+    let hazard = Hazard::ALL
+        .into_iter()
+        .find(|hazard| hazard.name().eq_ignore_ascii_case(&name))
+        .ok_or_else(|| format!("invalid value `{name}` for <kind>"))?;
+    let x: f32 = parse_optional_arg(args, 1, "x", 0.0)?;
+    let z: f32 = parse_optional_arg(args, 2, "z", 0.0)?;
+
+    let mut queue = CommandQueue::default();
+    spawn_hazard(
+        &mut Commands::new(&mut queue, world),
+        hazard,
+        Vec3::new(x, 0.0, z),
+    );
+    queue.apply(world);
+    Ok(format!("spawned {hazard:?} at ({x}, {z})"))
+}
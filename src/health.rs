@@ -0,0 +1,41 @@
+use bevy::prelude::*;
+
+// 定义每发导弹造成的基础伤害，实际伤害还要乘以难度的伤害倍数
+pub const MISSILE_DAMAGE: f32 = 1.0;
+
+// 定义撞击和子弹对玩家飞船造成的基础伤害，实际伤害还要除以难度的伤害倍数
+pub const IMPACT_DAMAGE: f32 = 1.0;
+
+// `Health` 组件表示实体的生命值，拥有生命值的实体在受到伤害后不会立即被销毁
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub struct Health {
+    pub current: f32,
+    pub max: f32,
+}
+
+impl Health {
+    pub fn new(max: f32) -> Self {
+        Self { current: max, max }
+    }
+
+    // 造成伤害，如果生命值因此耗尽则返回 `true`
+    pub fn damage(&mut self, amount: f32) -> bool {
+        self.current = (self.current - amount).max(0.0);
+        self.is_depleted()
+    }
+
+    // 生命值是否已经耗尽
+    pub fn is_depleted(&self) -> bool {
+        self.current <= 0.0
+    }
+
+    // 返回剩余生命值占最大生命值的比例
+    pub fn fraction(&self) -> f32 {
+        if self.max > 0.0 {
+            self.current / self.max
+        } else {
+            0.0
+        }
+    }
+}
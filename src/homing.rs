@@ -0,0 +1,109 @@
+use bevy::{
+    ecs::{
+        entity::{EntityMapper, MapEntities},
+        reflect::ReflectMapEntities,
+    },
+    prelude::*,
+};
+
+use crate::{asteroids::Asteroid, enemy::Enemy, movement::Velocity, schedule::InGameSet};
+
+// 定义追踪导弹搜索目标的最大距离
+const HOMING_RANGE: f32 = 60.0;
+
+// 定义追踪导弹搜索目标的锥形范围的半角，单位为弧度，只有位于导弹前方这个角度以内的目标才会被锁定
+const HOMING_CONE_HALF_ANGLE: f32 = 0.6;
+
+// 定义追踪导弹的最大转向速度，单位为弧度每秒
+const HOMING_TURN_RATE: f32 = 3.0;
+
+// `HomingMissile` 组件让导弹自动追踪前方的小行星或敌方飞船
+#[derive(Component, Reflect, Debug, Default)]
+#[reflect(Component, MapEntities)]
+pub struct HomingMissile {
+    // 当前锁定的目标，目标被销毁后会重新搜索
+    pub target: Option<Entity>,
+}
+
+// 从存档中读取追踪导弹时，把目标映射为读取后的实体
+impl MapEntities for HomingMissile {
+    fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
+        if let Some(target) = &mut self.target {
+            *target = entity_mapper.map_entity(*target);
+        }
+    }
+}
+
+// `HomingTargetFilter` 筛选追踪导弹可以锁定的目标
+type HomingTargetFilter = Or<(With<Asteroid>, With<Enemy>)>;
+
+pub struct HomingPlugin;
+
+impl Plugin for HomingPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<HomingMissile>()
+            .register_type::<Option<Entity>>()
+            .add_systems(
+                Update,
+                (acquire_homing_targets, steer_homing_missiles)
+                    .chain()
+                    .in_set(InGameSet::EntityUpdates),
+            );
+    }
+}
+
+// 这个函数为没有目标或者目标已经被销毁的追踪导弹锁定新的目标
+// 目标是导弹前方锥形范围内距离最近的小行星或敌方飞船
+fn acquire_homing_targets(
+    mut query: Query<(&Transform, &Velocity, &mut HomingMissile)>,
+    target_query: Query<(Entity, &GlobalTransform), HomingTargetFilter>,
+) {
+    for (transform, velocity, mut homing) in query.iter_mut() {
+        if homing
+            .target
+            .is_some_and(|target| target_query.contains(target))
+        {
+            continue;
+        }
+        let position = transform.translation;
+        let heading = velocity.value.normalize_or_zero();
+        homing.target = target_query
+            .iter()
+            .map(|(entity, target)| (entity, target.translation() - position))
+            .filter(|(_, offset)| {
+                offset.length() <= HOMING_RANGE
+                    && heading.angle_between(*offset) <= HOMING_CONE_HALF_ANGLE
+            })
+            .min_by(|(_, a), (_, b)| a.length().total_cmp(&b.length()))
+            .map(|(entity, _)| entity);
+    }
+}
+
+// 这个函数让追踪导弹以有限的转向速度转向它的目标，导弹的速率保持不变
+// 导弹模型始终朝向它的运动方向
+fn steer_homing_missiles(
+    mut query: Query<(&mut Transform, &mut Velocity, &HomingMissile)>,
+    target_query: Query<&GlobalTransform>,
+    time: Res<Time>,
+) {
+    for (mut transform, mut velocity, homing) in query.iter_mut() {
+        if let Some(target) = homing
+            .target
+            .and_then(|target| target_query.get(target).ok())
+        {
+            let heading = velocity.value.normalize_or_zero();
+            let desired = (target.translation() - transform.translation).normalize_or_zero();
+            let angle = heading.angle_between(desired);
+            if angle > f32::EPSILON {
+                // 绕垂直于当前方向和期望方向的轴旋转速度，每帧最多旋转 `HOMING_TURN_RATE * delta` 弧度
+                let axis = heading.cross(desired).try_normalize().unwrap_or(Vec3::Y);
+                let turn = angle.min(HOMING_TURN_RATE * time.delta_seconds());
+                velocity.value = Quat::from_axis_angle(axis, turn) * velocity.value;
+            }
+        }
+
+        if velocity.value.length_squared() > f32::EPSILON {
+            transform.look_to(-velocity.value, Vec3::Y);
+        }
+    }
+}
@@ -0,0 +1,333 @@
+use std::any::TypeId;
+
+use bevy::{
+    prelude::*,
+    reflect::{ReflectMut, ReflectRef, TypeRegistration},
+    window::PrimaryWindow,
+};
+use bevy_egui::{egui, EguiContext, EguiPlugin};
+
+// 定义切换检查器窗口的按键
+const TOGGLE_KEY: KeyCode = KeyCode::F2;
+
+// 定义实体列表的最大高度，单位为像素
+const ENTITY_LIST_HEIGHT: f32 = 200.0;
+
+// 定义拖动数值控件时每个像素改变的数值
+const DRAG_SPEED: f64 = 0.1;
+
+// `Inspector` 资源记录检查器窗口是否打开，以及当前选中的实体
+#[derive(Resource, Debug, Default)]
+pub struct Inspector {
+    pub open: bool,
+    pub selected: Option<Entity>,
+}
+
+// 定义一个名为 `InspectorPlugin` 的结构体
+// 它打开一个检查器窗口，列出所有拥有游戏组件的实体，并通过反射显示和编辑选中实体的组件以及游戏资源，按 F2 打开或关闭
+// 只有注册了反射类型的组件和资源才会显示
+pub struct InspectorPlugin;
+
+impl Plugin for InspectorPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<EguiPlugin>() {
+            app.add_plugins(EguiPlugin);
+        }
+        app.init_resource::<Inspector>().add_systems(
+            Update,
+            (toggle_inspector, inspector_ui.run_if(inspector_open)).chain(),
+        );
+    }
+}
+
+// 这个函数判断检查器窗口是否打开
+fn inspector_open(inspector: Res<Inspector>) -> bool {
+    inspector.open
+}
+
+// 这个函数在按下 `TOGGLE_KEY` 时打开或关闭检查器窗口
+fn toggle_inspector(mut inspector: ResMut<Inspector>, keyboard_input: Res<ButtonInput<KeyCode>>) {
+    if keyboard_input.just_pressed(TOGGLE_KEY) {
+        inspector.open = !inspector.open;
+    }
+}
+
+// 这个函数判断类型是否是本游戏定义的类型
+fn is_gameplay_type(registration: &TypeRegistration) -> bool {
+    registration
+        .type_info()
+        .type_path()
+        .starts_with(concat!(env!("CARGO_CRATE_NAME"), "::"))
+}
+
+// 这个函数返回类型的短名称，例如 `Velocity`
+fn short_name(registration: &TypeRegistration) -> String {
+    registration
+        .type_info()
+        .type_path_table()
+        .short_path()
+        .to_string()
+}
+
+// 这个函数绘制检查器窗口，它需要独占地访问 `World`，以便通过反射修改任意的组件和资源
+fn inspector_ui(world: &mut World) {
+    let Ok(mut context) = world
+        .query_filtered::<&mut EguiContext, With<PrimaryWindow>>()
+        .get_single_mut(world)
+    else {
+        return;
+    };
+    let context = context.get_mut().clone();
+    let type_registry = world.resource::<AppTypeRegistry>().clone();
+    let type_registry = type_registry.read();
+
+    // 收集可以通过反射访问的组件和资源
+    let components: Vec<(TypeId, String, ReflectComponent, bool)> = type_registry
+        .iter()
+        .filter_map(|registration| {
+            let reflect_component = registration.data::<ReflectComponent>()?;
+            Some((
+                registration.type_id(),
+                short_name(registration),
+                reflect_component.clone(),
+                is_gameplay_type(registration),
+            ))
+        })
+        .collect();
+    let resources: Vec<(String, ReflectResource)> = type_registry
+        .iter()
+        .filter(|registration| is_gameplay_type(registration))
+        .filter_map(|registration| {
+            let reflect_resource = registration.data::<ReflectResource>()?;
+            Some((short_name(registration), reflect_resource.clone()))
+        })
+        .collect();
+
+    // 拥有游戏组件的实体以它的第一个游戏组件命名
+    let mut entities: Vec<(Entity, String)> = world
+        .iter_entities()
+        .filter_map(|entity_ref| {
+            components
+                .iter()
+                .find(|(type_id, _, _, gameplay)| {
+                    *gameplay && entity_ref.contains_type_id(*type_id)
+                })
+                .map(|(_, name, _, _)| (entity_ref.id(), name.clone()))
+        })
+        .collect();
+    entities.sort_by_key(|(entity, _)| *entity);
+
+    let inspector = world.resource::<Inspector>();
+    let mut open = inspector.open;
+    let mut selected = inspector.selected;
+    if selected.is_some_and(|entity| entities.iter().all(|(other, _)| *other != entity)) {
+        selected = None;
+    }
+
+    // egui 的默认字体不包含中文字符，因此这里使用英文
+    egui::Window::new("Inspector")
+        .open(&mut open)
+        .default_width(320.0)
+        .show(&context, |ui| {
+            ui.heading("Entities");
+            egui::ScrollArea::vertical()
+                .id_source("entities")
+                .max_height(ENTITY_LIST_HEIGHT)
+                .show(ui, |ui| {
+                    for (entity, name) in &entities {
+                        let label = format!("{name} {entity:?}");
+                        if ui
+                            .selectable_label(selected == Some(*entity), label)
+                            .clicked()
+                        {
+                            selected = Some(*entity);
+                        }
+                    }
+                });
+
+            egui::ScrollArea::vertical()
+                .id_source("details")
+                .show(ui, |ui| {
+                    if let Some(entity) = selected {
+                        ui.separator();
+                        ui.heading(format!("{entity:?}"));
+                        for (type_id, name, reflect_component, _) in &components {
+                            let mut entity_mut = world.entity_mut(entity);
+                            if !entity_mut.contains_type_id(*type_id) {
+                                continue;
+                            }
+                            if let Some(mut value) = reflect_component.reflect_mut(&mut entity_mut)
+                            {
+                                ui.collapsing(name, |ui| {
+                                    // 只有数值真的被修改时才触发变更检测
+                                    if edit_reflect(ui, value.bypass_change_detection()) {
+                                        value.set_changed();
+                                    }
+                                });
+                            }
+                        }
+                    }
+
+                    ui.separator();
+                    ui.heading("Resources");
+                    for (name, reflect_resource) in &resources {
+                        if let Some(mut value) = reflect_resource.reflect_mut(world) {
+                            ui.collapsing(name, |ui| {
+                                if edit_reflect(ui, value.bypass_change_detection()) {
+                                    value.set_changed();
+                                }
+                            });
+                        }
+                    }
+                });
+        });
+
+    let mut inspector = world.resource_mut::<Inspector>();
+    inspector.open = open;
+    inspector.selected = selected;
+}
+
+// 这个函数根据值的反射信息绘制编辑控件，如果值被修改则返回 `true`
+fn edit_reflect(ui: &mut egui::Ui, value: &mut dyn Reflect) -> bool {
+    // 常见的类型直接使用对应的控件编辑
+    if let Some(value) = value.downcast_mut::<f32>() {
+        return ui
+            .add(egui::DragValue::new(value).speed(DRAG_SPEED))
+            .changed();
+    }
+    if let Some(value) = value.downcast_mut::<f64>() {
+        return ui
+            .add(egui::DragValue::new(value).speed(DRAG_SPEED))
+            .changed();
+    }
+    if let Some(value) = value.downcast_mut::<u32>() {
+        return ui.add(egui::DragValue::new(value)).changed();
+    }
+    if let Some(value) = value.downcast_mut::<u64>() {
+        return ui.add(egui::DragValue::new(value)).changed();
+    }
+    if let Some(value) = value.downcast_mut::<usize>() {
+        return ui.add(egui::DragValue::new(value)).changed();
+    }
+    if let Some(value) = value.downcast_mut::<bool>() {
+        return ui.checkbox(value, "").changed();
+    }
+    if let Some(value) = value.downcast_mut::<String>() {
+        return ui.text_edit_singleline(value).changed();
+    }
+    if let Some(value) = value.downcast_mut::<Vec3>() {
+        return edit_vec3(ui, value);
+    }
+    if let Some(value) = value.downcast_mut::<Quat>() {
+        // 旋转以欧拉角（单位为度）的形式编辑
+        let (y, x, z) = value.to_euler(EulerRot::YXZ);
+        let mut angles = Vec3::new(x, y, z) * 180.0 / std::f32::consts::PI;
+        if !edit_vec3(ui, &mut angles) {
+            return false;
+        }
+        let angles = angles * std::f32::consts::PI / 180.0;
+        *value = Quat::from_euler(EulerRot::YXZ, angles.y, angles.x, angles.z);
+        return true;
+    }
+
+    let mut changed = false;
+    match value.reflect_mut() {
+        ReflectMut::Struct(value) => {
+            for index in 0..value.field_len() {
+                let name = value.name_at(index).unwrap_or_default().to_string();
+                if let Some(field) = value.field_at_mut(index) {
+                    changed |= edit_field(ui, &name, field);
+                }
+            }
+        }
+        ReflectMut::TupleStruct(value) => {
+            for index in 0..value.field_len() {
+                if let Some(field) = value.field_mut(index) {
+                    changed |= edit_field(ui, &index.to_string(), field);
+                }
+            }
+        }
+        ReflectMut::Tuple(value) => {
+            for index in 0..value.field_len() {
+                if let Some(field) = value.field_mut(index) {
+                    changed |= edit_field(ui, &index.to_string(), field);
+                }
+            }
+        }
+        ReflectMut::List(value) => {
+            for index in 0..value.len() {
+                if let Some(item) = value.get_mut(index) {
+                    changed |= edit_field(ui, &index.to_string(), item);
+                }
+            }
+        }
+        ReflectMut::Array(value) => {
+            for index in 0..value.len() {
+                if let Some(item) = value.get_mut(index) {
+                    changed |= edit_field(ui, &index.to_string(), item);
+                }
+            }
+        }
+        ReflectMut::Map(value) => {
+            for index in 0..value.len() {
+                if let Some((key, item)) = value.get_at_mut(index) {
+                    let name = format!("{key:?}");
+                    changed |= edit_field(ui, &name, item);
+                }
+            }
+        }
+        ReflectMut::Enum(value) => {
+            ui.label(value.variant_name());
+            for index in 0..value.field_len() {
+                let name = value
+                    .name_at(index)
+                    .map_or_else(|| index.to_string(), str::to_string);
+                if let Some(field) = value.field_at_mut(index) {
+                    changed |= edit_field(ui, &name, field);
+                }
+            }
+        }
+        // 其他无法拆分的类型（例如 `Duration`、`Entity`）只显示它的值
+        ReflectMut::Value(value) => {
+            ui.label(format!("{value:?}"));
+        }
+    }
+    changed
+}
+
+// 这个函数绘制一个带名称的字段，复合类型的字段可以折叠
+fn edit_field(ui: &mut egui::Ui, name: &str, value: &mut dyn Reflect) -> bool {
+    let is_compound = !value.is::<Vec3>()
+        && !value.is::<Quat>()
+        && !matches!(value.reflect_ref(), ReflectRef::Value(_));
+    if is_compound {
+        ui.collapsing(name, |ui| edit_reflect(ui, value))
+            .body_returned
+            .unwrap_or(false)
+    } else {
+        ui.horizontal(|ui| {
+            ui.label(name);
+            edit_reflect(ui, value)
+        })
+        .inner
+    }
+}
+
+// 这个函数使用三个拖动控件编辑三维向量
+fn edit_vec3(ui: &mut egui::Ui, value: &mut Vec3) -> bool {
+    ui.horizontal(|ui| {
+        let mut changed = false;
+        for (label, component) in [
+            ("x", &mut value.x),
+            ("y", &mut value.y),
+            ("z", &mut value.z),
+        ] {
+            ui.label(label);
+            changed |= ui
+                .add(egui::DragValue::new(component).speed(DRAG_SPEED))
+                .changed();
+        }
+        changed
+    })
+    .inner
+}
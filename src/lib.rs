@@ -0,0 +1,62 @@
+// 游戏逻辑以库的形式导出，供游戏客户端、服务器和无窗口模拟等多个二进制程序共用
+use bevy::{app::PluginGroupBuilder, prelude::*};
+
+pub mod asset_loader;
+pub mod asteroids;
+pub mod audio;
+pub mod boss;
+pub mod camera;
+pub mod collider_shape;
+pub mod collision_detection;
+pub mod console;
+pub mod debug;
+pub mod despawn;
+pub mod difficulty;
+pub mod enemy;
+pub mod hazards;
+pub mod health;
+pub mod homing;
+#[cfg(feature = "inspector")]
+pub mod inspector;
+pub mod movement;
+pub mod network;
+pub mod particles;
+pub mod pickup;
+pub mod player;
+pub mod rng;
+pub mod schedule;
+pub mod score;
+pub mod settings;
+pub mod simulation;
+pub mod snapshot;
+pub mod spaceship;
+pub mod telemetry;
+pub mod time_control;
+pub mod wave;
+
+// `GameplayPlugins` 插件组包含不依赖窗口和渲染的全部游戏逻辑插件
+// 使用它的应用需要自行提供 `SceneAssets` 和 `PlayerConfig` 资源
+pub struct GameplayPlugins;
+
+impl PluginGroup for GameplayPlugins {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(movement::MovementPlugin)
+            .add(difficulty::DifficultyPlugin)
+            .add(spaceship::SpaceshipPlugin)
+            .add(homing::HomingPlugin)
+            .add(asteroids::AsteroidPlugin)
+            .add(hazards::HazardPlugin)
+            .add(enemy::EnemyPlugin)
+            .add(wave::WavePlugin)
+            .add(boss::BossPlugin)
+            .add(score::ScorePlugin)
+            .add(pickup::PickupPlugin)
+            .add(collision_detection::CollisionDetectionPlugin)
+            .add(despawn::DespawnPlugin)
+            .add(schedule::SchedulePlugin)
+            .add(console::ConsoleCommandsPlugin)
+            .add(time_control::TimeControlPlugin)
+            .add(snapshot::SnapshotPlugin)
+    }
+}
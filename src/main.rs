@@ -1,43 +1,87 @@
-mod asset_loader;
-mod asteroids;
-mod camera;
-mod collision_detection;
-mod debug;
-mod despawn;
-mod movement;
-mod schedule;
-mod spaceship;
-
 use bevy::prelude::*;
 
-use asset_loader::AssetLoaderPlugin;
-use asteroids::AsteroidPlugin;
-use camera::CameraPlugin;
-use collision_detection::CollisionDetectionPlugin;
-// use debug::DebugPlugin;
-use despawn::DespawnPlugin;
-use movement::MovementPlugin;
-use schedule::SchedulePlugin;
-use spaceship::SpaceshipPlugin;
+use spaceship_game::{
+    asset_loader::AssetLoaderPlugin,
+    audio::GameAudioPlugin,
+    camera::CameraPlugin,
+    console::ConsolePlugin,
+    movement::MovementPlugin,
+    network::{ClientSocket, NetworkClientPlugin},
+    particles::ParticlePlugin,
+    player::{PlayerConfig, PlayerPlugin},
+    schedule::SchedulePlugin,
+    settings::{SettingsFile, SettingsPlugin},
+    telemetry::{TelemetryConfig, TelemetryPlugin},
+    GameplayPlugins,
+};
 
 fn main() {
-    App::new()
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    // 遥测参数在创建窗口之前检查，参数无效时与读取设置文件失败一样把错误输出到标准错误，然后以非零状态退出
+    let telemetry = TelemetryConfig::from_args(&args).unwrap_or_else(|error| {
+        eprintln!("无效的遥测参数: {}", error);
+        std::process::exit(1);
+    });
+
+    // 设置在创建窗口之前读取，使全屏、垂直同步和抗锯齿选项在第一帧就生效
+    let settings_file = SettingsFile::default();
+    let settings = settings_file.load();
+
+    let mut app = App::new();
+    app
         // Bevy built-ins.
         .insert_resource(ClearColor(Color::rgb(0.1, 0.0, 0.15)))
         .insert_resource(AmbientLight {
             color: Color::default(),
             brightness: 750.0,
         })
-        .add_plugins(DefaultPlugins)
+        .insert_resource(PlayerConfig::from_args(args.iter().cloned()))
+        .insert_resource(settings.graphics.msaa())
+        .add_plugins(DefaultPlugins.set(settings.window_plugin()))
         // User defined plugins.
         .add_plugins(AssetLoaderPlugin)
-        .add_plugins(MovementPlugin)
-        .add_plugins(SpaceshipPlugin)
-        .add_plugins(AsteroidPlugin)
+        .add_plugins(PlayerPlugin)
         .add_plugins(CameraPlugin)
-        .add_plugins(CollisionDetectionPlugin)
-        .add_plugins(DespawnPlugin)
-        .add_plugins(SchedulePlugin)
-        // .add_plugins(DebugPlugin)
-        .run();
+        .add_plugins(ParticlePlugin)
+        .add_plugins(GameAudioPlugin)
+        .insert_resource(settings)
+        .insert_resource(settings_file)
+        .add_plugins(SettingsPlugin);
+
+    // `--telemetry <种类>` 记录实体的运动数据，参数见 `TelemetryConfig::from_args`
+    if let Some(config) = telemetry {
+        app.insert_resource(config).add_plugins(TelemetryPlugin);
+    }
+
+    // 启用 `debug-overlay` 特性后，可以按 F3 开启调试覆盖层
+    #[cfg(feature = "debug-overlay")]
+    app.add_plugins(spaceship_game::debug::DebugPlugin);
+
+    // 启用 `inspector` 特性后，可以按 F2 打开检查器窗口，查看和修改实体的组件以及游戏资源
+    #[cfg(feature = "inspector")]
+    app.add_plugins(spaceship_game::inspector::InspectorPlugin);
+
+    // `--connect <地址>` 以网络客户端模式运行：游戏逻辑由服务器运行，本地只负责发送输入和显示同步的实体
+    match connect_addr(&args) {
+        Some(server_addr) => {
+            let socket = ClientSocket::connect(server_addr).expect("无法连接到服务器");
+            app.insert_resource(socket)
+                .add_plugins(MovementPlugin)
+                .add_plugins(SchedulePlugin)
+                .add_plugins(NetworkClientPlugin);
+        }
+        None => {
+            app.add_plugins(GameplayPlugins).add_plugins(ConsolePlugin);
+        }
+    }
+
+    app.run();
+}
+
+// 从命令行参数中读取 `--connect` 后面的服务器地址
+fn connect_addr(args: &[String]) -> Option<&str> {
+    args.iter()
+        .position(|arg| arg == "--connect")
+        .and_then(|index| args.get(index + 1))
+        .map(String::as_str)
 }
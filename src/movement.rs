@@ -2,7 +2,11 @@ use bevy::prelude::*;
 
 use crate::{collision_detection::Collider, schedule::InGameSet};
 
-#[derive(Component, Debug)]
+// 定义外力产生的速度每秒衰减的比例，飞船离开力场之后会逐渐停止漂移
+const EXTERNAL_VELOCITY_DAMPING: f32 = 1.5;
+
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
 pub struct Velocity {
     pub value: Vec3,
 }
@@ -13,7 +17,8 @@ impl Velocity {
     }
 }
 
-#[derive(Component, Debug)]
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
 pub struct Acceleration {
     pub value: Vec3,
 }
@@ -24,6 +29,29 @@ impl Acceleration {
     }
 }
 
+// `ExternalVelocity` 组件保存力场等外力产生的速度，实体的位移由 `Velocity` 和它相加得到
+// 速度每一帧都由输入重新设置的实体（例如飞船）用它保存外力的效果，没有这个组件的实体由外力直接改变 `Velocity`
+#[derive(Component, Reflect, Debug, Default)]
+#[reflect(Component)]
+pub struct ExternalVelocity {
+    pub value: Vec3,
+}
+
+// `SpeedScale` 组件缩放实体的位移，例如星云中的实体移动得更慢
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+pub struct SpeedScale {
+    pub value: f32,
+}
+
+// `MovementSet` 表示运动更新的两个阶段，它们都在 `InGameSet::EntityUpdates` 集合中运行
+// 力场等外力在 `ApplyForces` 中改变速度，之后 `Integrate` 根据加速度更新速度并移动实体
+#[derive(Debug, Hash, PartialEq, Eq, Clone, SystemSet)]
+pub enum MovementSet {
+    ApplyForces,
+    Integrate,
+}
+
 #[derive(Bundle)]
 // 定义一个名为 `MovingObjectBundle` 的公共结构体，它包含四个公共字段：
 // `velocity`：一个 `Velocity` 类型的字段，表示移动对象的速度。
@@ -41,29 +69,58 @@ pub struct MovementPlugin;
 
 impl Plugin for MovementPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            Update,
-            (update_velocity, update_position)
-                .chain()
-                .in_set(InGameSet::EntityUpdates),
-        );
+        app.register_type::<Velocity>()
+            .register_type::<Acceleration>()
+            .register_type::<ExternalVelocity>()
+            .register_type::<SpeedScale>()
+            .configure_sets(
+                Update,
+                (MovementSet::ApplyForces, MovementSet::Integrate)
+                    .chain()
+                    .in_set(InGameSet::EntityUpdates),
+            )
+            .add_systems(
+                Update,
+                (update_velocity, update_position)
+                    .chain()
+                    .in_set(MovementSet::Integrate),
+            );
     }
 }
 
-// 定义一个名为 `update_velocity` 的函数，它接受一个可变的 `Query` 类型参数和一个 `Time` 资源引用参数
-fn update_velocity(mut query: Query<(&Acceleration, &mut Velocity)>, time: Res<Time>) {
+// 定义一个名为 `update_velocity` 的函数，它接受两个可变的 `Query` 类型参数和一个 `Time` 资源引用参数
+// 外力产生的速度在这里逐渐衰减
+fn update_velocity(
+    mut query: Query<(&Acceleration, &mut Velocity)>,
+    mut external_query: Query<&mut ExternalVelocity>,
+    time: Res<Time>,
+) {
     // 对查询结果进行迭代，每次迭代得到一个加速度和速度的元组
     for (acceleration, mut velocity) in query.iter_mut() {
         // 更新速度值，新的速度值等于原速度值加上加速度值乘以时间的增量
         velocity.value += acceleration.value * time.delta_seconds();
     }
+    let damping = (1.0 - EXTERNAL_VELOCITY_DAMPING * time.delta_seconds()).max(0.0);
+    for mut external in external_query.iter_mut() {
+        external.value *= damping;
+    }
 }
 
 // 定义一个名为 `update_position` 的函数，它接受一个可变的 `Query` 类型参数和一个 `Time` 资源引用参数
-fn update_position(mut query: Query<(&Velocity, &mut Transform)>, time: Res<Time>) {
-    // 对查询结果进行迭代，每次迭代得到一个速度和变换的元组
-    for (velocity, mut transform) in query.iter_mut() {
+fn update_position(
+    mut query: Query<(
+        &Velocity,
+        Option<&ExternalVelocity>,
+        Option<&SpeedScale>,
+        &mut Transform,
+    )>,
+    time: Res<Time>,
+) {
+    // 对查询结果进行迭代，每次迭代得到一个速度、外力产生的速度、位移的缩放和变换的元组
+    for (velocity, external, speed_scale, mut transform) in query.iter_mut() {
+        let velocity = velocity.value + external.map_or(Vec3::ZERO, |external| external.value);
+        let scale = speed_scale.map_or(1.0, |speed_scale| speed_scale.value);
         // 更新变换的平移部分，新的平移值等于原平移值加上速度值乘以时间的增量
-        transform.translation += velocity.value * time.delta_seconds();
+        transform.translation += velocity * scale * time.delta_seconds();
     }
 }
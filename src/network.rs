@@ -0,0 +1,695 @@
+use std::{
+    io::{self, ErrorKind},
+    net::{SocketAddr, ToSocketAddrs, UdpSocket},
+};
+
+use bevy::{app::AppExit, prelude::*, transform::TransformSystem, utils::HashMap};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    asset_loader::SceneAssets,
+    asteroids::{tint_asteroids, Asteroid, AsteroidKind, AsteroidVariants},
+    boss::{spawn_boss_health_bar, Boss, BossHealthBar, BossHealthBarRoot, BossPart, BossPartKind},
+    enemy::{Enemy, EnemyProjectile},
+    hazards::{add_hazard_visuals, Hazard, HazardRadius},
+    health::Health,
+    movement::Velocity,
+    pickup::{add_pickup_visuals, Pickup, PickupKind},
+    player::{KeyBindings, PlayerId, PlayerInput},
+    schedule::InGameSet,
+    spaceship::{spaceship_bundle, Spaceship, SpaceshipMissile},
+};
+
+// 定义服务器的默认地址，默认只在本机回环地址上监听
+pub const DEFAULT_SERVER_ADDR: &str = "127.0.0.1:5000";
+
+// 定义 UDP 数据包的最大长度
+const MAX_PACKET_SIZE: usize = 65_507;
+
+// 定义客户端在收到欢迎消息之前重复发送握手消息的间隔，单位为秒
+const HELLO_INTERVAL_SECONDS: f32 = 1.0;
+
+// 定义服务器在多长时间没有收到客户端消息后断开该客户端，单位为秒
+const CLIENT_TIMEOUT_SECONDS: f32 = 5.0;
+
+// 定义网络玩家的飞船在 X 轴上的间隔，初始值为 15.0
+const SPAWN_SPACING: f32 = 15.0;
+
+// 定义网络玩家的飞船在 Z 轴上的初始位置
+const SPAWN_Z: f32 = -20.0;
+
+// 客户端发送给服务器的消息
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum ClientMessage {
+    // 请求加入游戏
+    Hello,
+    // 当前帧的输入动作
+    Input(PlayerInput),
+    // 离开游戏
+    Disconnect,
+}
+
+// 服务器发送给客户端的消息
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum ServerMessage {
+    // 确认客户端加入，并告知它的玩家编号
+    Welcome { player: usize },
+    // 所有需要同步的实体的当前状态
+    Snapshot(Snapshot),
+}
+
+// `Snapshot` 结构体表示服务器某一帧的世界状态
+// 客户端收到快照后，会生成快照中新出现的实体，并销毁快照中已经不存在的实体
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub tick: u64,
+    pub entities: Vec<NetEntity>,
+}
+
+// `NetEntity` 结构体表示一个需要同步的实体
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NetEntity {
+    // 实体在服务器上的编号
+    pub id: u64,
+    pub kind: NetKind,
+    pub translation: [f32; 3],
+    pub rotation: [f32; 4],
+    pub scale: [f32; 3],
+    pub velocity: [f32; 3],
+}
+
+// 需要同步的实体的种类，客户端根据种类选择模型
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum NetKind {
+    Spaceship { player: usize },
+    Asteroid { kind: AsteroidKind },
+    Missile,
+    Enemy,
+    EnemyProjectile,
+    // 首领的根实体本身没有模型，只携带首领剩余的生命值
+    Boss { health: f32, max_health: f32 },
+    BossPart { kind: BossPartKind },
+    Pickup { kind: PickupKind },
+    Hazard { hazard: Hazard, radius: f32 },
+}
+
+// 这个函数把消息编码为字节
+pub fn encode<T: Serialize>(message: &T) -> Vec<u8> {
+    bincode::serialize(message).expect("网络消息应当总是可以被编码")
+}
+
+// 这个函数把字节解码为消息，如果数据不合法则返回 `None`
+pub fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Option<T> {
+    bincode::deserialize(bytes).ok()
+}
+
+// 这个函数以非阻塞的方式读取套接字中所有已经到达的数据包
+fn receive_all(socket: &UdpSocket) -> Vec<(Vec<u8>, SocketAddr)> {
+    let mut packets = vec![];
+    let mut buffer = vec![0; MAX_PACKET_SIZE];
+    loop {
+        match socket.recv_from(&mut buffer) {
+            Ok((len, addr)) => packets.push((buffer[..len].to_vec(), addr)),
+            Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+            // 在某些平台上，对端关闭后会收到连接重置错误，忽略它并继续读取
+            Err(error) if error.kind() == ErrorKind::ConnectionReset => continue,
+            Err(error) => {
+                warn!("接收网络数据失败：{}", error);
+                break;
+            }
+        }
+    }
+    packets
+}
+
+// `ServerSocket` 资源保存服务器监听的 UDP 套接字
+#[derive(Resource, Debug)]
+pub struct ServerSocket(UdpSocket);
+
+impl ServerSocket {
+    // 在指定地址上绑定一个非阻塞的 UDP 套接字
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let socket = UdpSocket::bind(addr)?;
+        socket.set_nonblocking(true)?;
+        Ok(Self(socket))
+    }
+
+    // 返回服务器实际监听的地址
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.0.local_addr()
+    }
+}
+
+#[derive(Debug)]
+struct ConnectedClient {
+    player: PlayerId,
+    // 最后一次收到该客户端消息的时间
+    last_seen: f32,
+}
+
+// `ConnectedClients` 资源记录所有已经连接到服务器的客户端
+#[derive(Resource, Debug, Default)]
+pub struct ConnectedClients {
+    clients: HashMap<SocketAddr, ConnectedClient>,
+    next_player: usize,
+}
+
+impl ConnectedClients {
+    // 返回已经连接的客户端数量
+    pub fn len(&self) -> usize {
+        self.clients.len()
+    }
+
+    // 是否没有任何客户端连接
+    pub fn is_empty(&self) -> bool {
+        self.clients.is_empty()
+    }
+
+    // 返回所有已连接客户端的玩家编号
+    pub fn players(&self) -> impl Iterator<Item = PlayerId> + '_ {
+        self.clients.values().map(|client| client.player)
+    }
+}
+
+// 这个函数为一名网络玩家生成飞船，玩家编号决定飞船沿 X 轴的初始位置
+pub fn spawn_network_spaceship(
+    commands: &mut Commands,
+    scene_assets: &SceneAssets,
+    player: PlayerId,
+) {
+    let translation = Vec3::new(((player.0 % 4) as f32 - 1.5) * SPAWN_SPACING, 0.0, SPAWN_Z);
+    commands.spawn(spaceship_bundle(
+        scene_assets.spaceship.clone(),
+        player,
+        translation,
+    ));
+}
+
+#[derive(Resource, Debug, Default)]
+struct ServerTick(u64);
+
+pub struct NetworkServerPlugin;
+
+// 服务器在 `PreUpdate` 阶段读取客户端的输入，在 `PostUpdate` 阶段把世界状态发送给所有客户端
+// 中间的 `Update` 阶段运行的是与单机游戏完全相同的移动和碰撞系统
+impl Plugin for NetworkServerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ConnectedClients>()
+            .init_resource::<ServerTick>()
+            .add_systems(Startup, log_server_addr)
+            .add_systems(
+                PreUpdate,
+                (server_receive_messages, server_drop_timed_out_clients).chain(),
+            )
+            // 快照使用全局变换，因此在变换传播之后发送
+            .add_systems(
+                PostUpdate,
+                server_send_snapshots.after(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+fn log_server_addr(socket: Res<ServerSocket>) {
+    match socket.local_addr() {
+        Ok(addr) => info!("服务器正在监听 {}", addr),
+        Err(error) => warn!("无法获取服务器地址：{}", error),
+    }
+}
+
+// 这个函数处理客户端发送的所有消息
+fn server_receive_messages(
+    mut commands: Commands,
+    socket: Res<ServerSocket>,
+    mut clients: ResMut<ConnectedClients>,
+    mut spaceship_query: Query<(&PlayerId, &mut PlayerInput), With<Spaceship>>,
+    scene_assets: Res<SceneAssets>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed_seconds();
+    for (bytes, addr) in receive_all(&socket.0) {
+        let Some(message) = decode::<ClientMessage>(&bytes) else {
+            warn!("收到来自 {} 的无法解析的消息", addr);
+            continue;
+        };
+        match message {
+            ClientMessage::Hello => {
+                // 新的客户端加入时，为它分配玩家编号并生成一艘飞船
+                if !clients.clients.contains_key(&addr) {
+                    let player = PlayerId(clients.next_player);
+                    clients.next_player += 1;
+                    spawn_network_spaceship(&mut commands, &scene_assets, player);
+                    clients.clients.insert(
+                        addr,
+                        ConnectedClient {
+                            player,
+                            last_seen: now,
+                        },
+                    );
+                    info!("玩家 {} 从 {} 加入游戏", player.0, addr);
+                }
+                // 握手消息可能丢失，因此每次收到握手消息都重新发送欢迎消息
+                let player = clients.clients[&addr].player;
+                let welcome = ServerMessage::Welcome { player: player.0 };
+                if let Err(error) = socket.0.send_to(&encode(&welcome), addr) {
+                    warn!("发送欢迎消息失败：{}", error);
+                }
+            }
+            ClientMessage::Input(input) => {
+                let Some(client) = clients.clients.get_mut(&addr) else {
+                    continue;
+                };
+                client.last_seen = now;
+                // 把客户端的输入写入对应玩家的飞船，之后由飞船的控制系统处理
+                for (&player, mut player_input) in spaceship_query.iter_mut() {
+                    if player == client.player {
+                        *player_input = input;
+                    }
+                }
+            }
+            ClientMessage::Disconnect => {
+                if let Some(client) = clients.clients.remove(&addr) {
+                    info!("玩家 {} 离开游戏", client.player.0);
+                }
+            }
+        }
+    }
+}
+
+// 这个函数断开长时间没有发送消息的客户端，并销毁所有不属于已连接客户端的飞船
+fn server_drop_timed_out_clients(
+    mut commands: Commands,
+    mut clients: ResMut<ConnectedClients>,
+    spaceship_query: Query<(Entity, &PlayerId), With<Spaceship>>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed_seconds();
+    clients.clients.retain(|addr, client| {
+        let alive = now - client.last_seen < CLIENT_TIMEOUT_SECONDS;
+        if !alive {
+            info!("玩家 {} ({}) 连接超时", client.player.0, addr);
+        }
+        alive
+    });
+    for (entity, player) in spaceship_query.iter() {
+        if !clients
+            .clients
+            .values()
+            .any(|client| client.player == *player)
+        {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+// 服务器需要同步的实体的查询类型
+// 首领的部件是首领的子实体，因此同步的是全局变换，部件本身没有速度
+type ReplicatedQuery<'a> = (
+    Entity,
+    &'a GlobalTransform,
+    Option<&'a Velocity>,
+    Option<&'a PlayerId>,
+    Option<&'a AsteroidKind>,
+    Option<&'a Children>,
+    AnyOf<(
+        &'a Spaceship,
+        &'a Asteroid,
+        &'a SpaceshipMissile,
+        &'a Enemy,
+        &'a EnemyProjectile,
+        &'a Boss,
+        &'a BossPart,
+        &'a Pickup,
+        &'a Hazard,
+    )>,
+);
+
+// 这个函数把所有需要同步的实体的状态打包成快照，并发送给所有客户端
+fn server_send_snapshots(
+    socket: Res<ServerSocket>,
+    clients: Res<ConnectedClients>,
+    mut tick: ResMut<ServerTick>,
+    query: Query<ReplicatedQuery>,
+    part_query: Query<&Health, With<BossPart>>,
+) {
+    tick.0 += 1;
+    if clients.is_empty() {
+        return;
+    }
+
+    let entities = query
+        .iter()
+        .filter_map(
+            |(entity, transform, velocity, player, asteroid_kind, children, markers)| {
+                let (
+                    spaceship,
+                    asteroid,
+                    missile,
+                    enemy,
+                    enemy_projectile,
+                    boss,
+                    boss_part,
+                    pickup,
+                    hazard,
+                ) = markers;
+                let kind = if let (Some(_), Some(player)) = (spaceship, player) {
+                    NetKind::Spaceship { player: player.0 }
+                } else if asteroid.is_some() {
+                    NetKind::Asteroid {
+                        kind: asteroid_kind.copied().unwrap_or_default(),
+                    }
+                } else if missile.is_some() {
+                    NetKind::Missile
+                } else if enemy.is_some() {
+                    NetKind::Enemy
+                } else if enemy_projectile.is_some() {
+                    NetKind::EnemyProjectile
+                } else if let Some(boss) = boss {
+                    // 首领的生命值是所有部件剩余生命值之和
+                    let health = children
+                        .map(|children| {
+                            part_query
+                                .iter_many(children)
+                                .map(|health| health.current)
+                                .sum()
+                        })
+                        .unwrap_or_default();
+                    NetKind::Boss {
+                        health,
+                        max_health: boss.max_health,
+                    }
+                } else if let Some(part) = boss_part {
+                    NetKind::BossPart { kind: part.kind }
+                } else if let Some(pickup) = pickup {
+                    NetKind::Pickup { kind: pickup.kind }
+                } else if let Some(&hazard) = hazard {
+                    NetKind::Hazard {
+                        hazard,
+                        radius: hazard.radius(),
+                    }
+                } else {
+                    return None;
+                };
+                let (scale, rotation, translation) = transform.to_scale_rotation_translation();
+                Some(NetEntity {
+                    id: entity.to_bits(),
+                    kind,
+                    translation: translation.to_array(),
+                    rotation: rotation.to_array(),
+                    scale: scale.to_array(),
+                    velocity: velocity
+                        .map_or(Vec3::ZERO, |velocity| velocity.value)
+                        .to_array(),
+                })
+            },
+        )
+        .collect();
+
+    let bytes = encode(&ServerMessage::Snapshot(Snapshot {
+        tick: tick.0,
+        entities,
+    }));
+    if bytes.len() > MAX_PACKET_SIZE {
+        warn!("快照过大（{} 字节），本帧不发送", bytes.len());
+        return;
+    }
+    for addr in clients.clients.keys() {
+        if let Err(error) = socket.0.send_to(&bytes, addr) {
+            warn!("向 {} 发送快照失败：{}", addr, error);
+        }
+    }
+}
+
+// `ClientSocket` 资源保存客户端连接到服务器的 UDP 套接字
+#[derive(Resource, Debug)]
+pub struct ClientSocket(UdpSocket);
+
+impl ClientSocket {
+    // 绑定一个本地的非阻塞 UDP 套接字，并把它连接到服务器地址
+    pub fn connect(server_addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let socket = UdpSocket::bind("0.0.0.0:0")?;
+        socket.connect(server_addr)?;
+        socket.set_nonblocking(true)?;
+        Ok(Self(socket))
+    }
+
+    fn send(&self, message: &ClientMessage) {
+        if let Err(error) = self.0.send(&encode(message)) {
+            warn!("向服务器发送消息失败：{}", error);
+        }
+    }
+}
+
+// `LocalController` 组件标记客户端上保存本地玩家输入的实体
+// 客户端不运行飞船的控制系统，输入会被发送给服务器
+#[derive(Component, Debug)]
+pub struct LocalController;
+
+// `Replicated` 组件标记由服务器同步而来的实体，并记录它在服务器上的编号
+#[derive(Component, Debug)]
+pub struct Replicated(pub u64);
+
+// `ReplicatedBoss` 组件记录客户端上同步而来的首领剩余的生命值，用于显示首领的血条
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct ReplicatedBoss {
+    pub health: f32,
+    pub max_health: f32,
+}
+
+// `ClientState` 资源记录客户端的连接状态
+#[derive(Resource, Debug)]
+pub struct ClientState {
+    // 服务器分配给本地玩家的编号，在收到欢迎消息之前为 `None`
+    pub player: Option<PlayerId>,
+    last_tick: Option<u64>,
+    hello_timer: Timer,
+    // 服务器实体编号到本地实体的映射
+    entities: HashMap<u64, Entity>,
+}
+
+impl Default for ClientState {
+    fn default() -> Self {
+        Self {
+            player: None,
+            last_tick: None,
+            hello_timer: Timer::from_seconds(HELLO_INTERVAL_SECONDS, TimerMode::Repeating),
+            entities: HashMap::new(),
+        }
+    }
+}
+
+pub struct NetworkClientPlugin;
+
+impl Plugin for NetworkClientPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ClientState>()
+            .init_resource::<AsteroidVariants>()
+            .add_systems(Startup, spawn_local_controller)
+            .add_systems(PreUpdate, client_receive_messages)
+            .add_systems(
+                Update,
+                (client_send_hello, client_send_input).after(InGameSet::UserInput),
+            )
+            .add_systems(
+                Update,
+                (
+                    tint_asteroids,
+                    add_pickup_visuals,
+                    add_hazard_visuals,
+                    client_update_boss_health_bar,
+                ),
+            )
+            .add_systems(Last, client_send_disconnect);
+    }
+}
+
+// 这个函数生成保存本地玩家输入的实体，它使用玩家一的按键绑定
+fn spawn_local_controller(mut commands: Commands, socket: Res<ClientSocket>) {
+    commands.spawn((
+        LocalController,
+        PlayerInput::default(),
+        KeyBindings::for_player(PlayerId(0)),
+    ));
+    // 立即发送第一次握手消息，之后在收到欢迎消息之前定期重发
+    socket.send(&ClientMessage::Hello);
+}
+
+// 这个函数在收到欢迎消息之前定期重发握手消息
+fn client_send_hello(socket: Res<ClientSocket>, mut state: ResMut<ClientState>, time: Res<Time>) {
+    if state.player.is_some() {
+        return;
+    }
+    state.hello_timer.tick(time.delta());
+    if state.hello_timer.just_finished() {
+        socket.send(&ClientMessage::Hello);
+    }
+}
+
+// 这个函数把本地玩家的输入发送给服务器
+fn client_send_input(
+    socket: Res<ClientSocket>,
+    state: Res<ClientState>,
+    query: Query<&PlayerInput, With<LocalController>>,
+) {
+    if state.player.is_none() {
+        return;
+    }
+    let Ok(input) = query.get_single() else {
+        return;
+    };
+    socket.send(&ClientMessage::Input(*input));
+}
+
+// 这个函数在应用退出时通知服务器
+fn client_send_disconnect(socket: Res<ClientSocket>, mut exit_events: EventReader<AppExit>) {
+    if exit_events.read().next().is_some() {
+        socket.send(&ClientMessage::Disconnect);
+    }
+}
+
+// 这个函数处理服务器发送的所有消息，并把最新的快照应用到本地世界
+fn client_receive_messages(
+    mut commands: Commands,
+    socket: Res<ClientSocket>,
+    mut state: ResMut<ClientState>,
+    mut query: Query<
+        (&mut Transform, &mut Velocity, Option<&mut ReplicatedBoss>),
+        With<Replicated>,
+    >,
+    scene_assets: Res<SceneAssets>,
+) {
+    // 同一帧收到多个快照时只保留最新的一个，并丢弃比已经应用过的快照更旧的快照
+    let mut latest: Option<Snapshot> = None;
+    for (bytes, _) in receive_all(&socket.0) {
+        match decode::<ServerMessage>(&bytes) {
+            Some(ServerMessage::Welcome { player }) => {
+                if state.player.is_none() {
+                    info!("已加入游戏，玩家编号为 {}", player);
+                }
+                state.player = Some(PlayerId(player));
+            }
+            Some(ServerMessage::Snapshot(snapshot)) => {
+                let newer_than_latest = latest.as_ref().map_or(true, |s| snapshot.tick > s.tick);
+                let newer_than_applied = state.last_tick.map_or(true, |tick| snapshot.tick > tick);
+                if newer_than_latest && newer_than_applied {
+                    latest = Some(snapshot);
+                }
+            }
+            None => warn!("收到无法解析的服务器消息"),
+        }
+    }
+    let Some(snapshot) = latest else {
+        return;
+    };
+    state.last_tick = Some(snapshot.tick);
+
+    let mut seen = HashMap::new();
+    for net_entity in snapshot.entities {
+        let transform = Transform {
+            translation: Vec3::from_array(net_entity.translation),
+            rotation: Quat::from_array(net_entity.rotation),
+            scale: Vec3::from_array(net_entity.scale),
+        };
+        let velocity = Vec3::from_array(net_entity.velocity);
+
+        // 已经存在的实体只更新变换和速度
+        if let Some(&entity) = state.entities.get(&net_entity.id) {
+            if let Ok((mut local_transform, mut local_velocity, boss)) = query.get_mut(entity) {
+                *local_transform = transform;
+                local_velocity.value = velocity;
+                if let (Some(mut boss), NetKind::Boss { health, max_health }) =
+                    (boss, net_entity.kind)
+                {
+                    *boss = ReplicatedBoss { health, max_health };
+                }
+                seen.insert(net_entity.id, entity);
+                continue;
+            }
+        }
+
+        // 快照中新出现的实体，根据种类生成对应的模型
+        let scene = match net_entity.kind {
+            NetKind::Spaceship { .. } => scene_assets.spaceship.clone(),
+            NetKind::Asteroid { .. } => scene_assets.asteroid.clone(),
+            NetKind::Missile | NetKind::EnemyProjectile => scene_assets.missiles.clone(),
+            NetKind::Enemy => scene_assets.enemy.clone(),
+            NetKind::Boss { .. } => Handle::default(),
+            NetKind::BossPart { .. } => scene_assets.asteroid.clone(),
+            // 道具的模型由 `add_pickup_visuals` 根据种类添加
+            NetKind::Pickup { .. } => Handle::default(),
+            // 危害的显示圆盘由 `add_hazard_visuals` 根据同步的半径添加
+            NetKind::Hazard { .. } => Handle::default(),
+        };
+        let mut entity_commands = commands.spawn((
+            SceneBundle {
+                scene,
+                transform,
+                ..default()
+            },
+            Velocity::new(velocity),
+            Replicated(net_entity.id),
+        ));
+        match net_entity.kind {
+            NetKind::Spaceship { player } => {
+                entity_commands.insert((Spaceship, PlayerId(player)));
+            }
+            NetKind::Asteroid { kind } => {
+                // 小行星的种类决定客户端上的颜色
+                entity_commands.insert((Asteroid, kind));
+            }
+            NetKind::Missile => {
+                entity_commands.insert(SpaceshipMissile);
+            }
+            NetKind::Enemy => {
+                entity_commands.insert(Enemy);
+            }
+            NetKind::EnemyProjectile => {
+                entity_commands.insert(EnemyProjectile);
+            }
+            NetKind::Boss { health, max_health } => {
+                entity_commands.insert(ReplicatedBoss { health, max_health });
+            }
+            NetKind::BossPart { kind } => {
+                entity_commands.insert(BossPart { kind });
+            }
+            NetKind::Pickup { kind } => {
+                entity_commands.insert(Pickup::new(kind));
+            }
+            NetKind::Hazard { hazard, radius } => {
+                entity_commands.insert((hazard, HazardRadius(radius)));
+            }
+        }
+        seen.insert(net_entity.id, entity_commands.id());
+    }
+
+    // 销毁快照中已经不存在的实体，飞出边界的实体可能已经先在本地被销毁
+    for (id, &entity) in state.entities.iter() {
+        if seen.contains_key(id) {
+            continue;
+        }
+        if let Some(entity_commands) = commands.get_entity(entity) {
+            entity_commands.despawn_recursive();
+        }
+    }
+    state.entities = seen;
+}
+
+// 这个函数在同步而来的首领出现时生成血条，根据首领剩余的生命值更新血条的长度，并在首领消失时销毁血条
+fn client_update_boss_health_bar(
+    mut commands: Commands,
+    boss_query: Query<&ReplicatedBoss>,
+    root_query: Query<Entity, With<BossHealthBarRoot>>,
+    mut health_bar_query: Query<&mut Style, With<BossHealthBar>>,
+) {
+    let Ok(boss) = boss_query.get_single() else {
+        for root in root_query.iter() {
+            commands.entity(root).despawn_recursive();
+        }
+        return;
+    };
+    if root_query.is_empty() {
+        spawn_boss_health_bar(&mut commands);
+    }
+    for mut style in health_bar_query.iter_mut() {
+        style.width = Val::Percent(100.0 * boss.health / boss.max_health);
+    }
+}
@@ -0,0 +1,393 @@
+use std::ops::Range;
+
+use bevy::prelude::*;
+use rand::prelude::*;
+
+use crate::{
+    collision_detection::{DestroyedKind, EntityDestroyed},
+    movement::Velocity,
+    player::PlayerInput,
+    schedule::InGameSet,
+    spaceship::Spaceship,
+};
+
+// 定义碰撞器每单位半径产生的碎片数量
+const PARTICLES_PER_RADIUS: f32 = 4.0;
+
+// 定义一次爆炸最多产生的碎片数量
+const MAX_BURST_PARTICLES: usize = 60;
+
+// 定义同时存在的碎片的最大数量，超过后新的爆炸不再产生碎片
+const MAX_PARTICLES: usize = 600;
+
+// 定义碎片的速度相对于碰撞器半径的比例
+const SPEED_PER_RADIUS: f32 = 3.0;
+
+// 定义碎片速度的随机倍数范围
+const SPEED_JITTER: Range<f32> = 0.4..1.4;
+
+// 定义碎片大小相对于碰撞器半径的比例
+const SIZE_PER_RADIUS: f32 = 0.15;
+
+// 定义碎片大小的随机倍数范围
+const SIZE_JITTER: Range<f32> = 0.5..1.0;
+
+// 定义碎片的存在时间范围，单位为秒
+const LIFETIME_SECONDS: Range<f32> = 0.6..1.4;
+
+// 定义碎片每秒损失的速度比例
+const DRAG: f32 = 1.5;
+
+// 定义碎片旋转速度的最大值，单位为弧度每秒
+const MAX_SPIN: f32 = 8.0;
+
+// 定义岩石碎片的颜色
+const DEBRIS_COLOR: Color = Color::rgb(0.55, 0.5, 0.45);
+
+// 定义火焰碎片的颜色
+const FIRE_COLOR: Color = Color::rgb(1.0, 0.55, 0.15);
+
+// 定义主引擎推力最大时每秒喷出的尾焰粒子数量
+const EXHAUST_RATE: f32 = 60.0;
+
+// 定义尾焰粒子相对于飞船的喷出速度
+const EXHAUST_SPEED: f32 = 15.0;
+
+// 定义尾焰粒子的大小
+const EXHAUST_SIZE: f32 = 0.5;
+
+// 定义尾焰粒子的存在时间范围，单位为秒
+const EXHAUST_LIFETIME_SECONDS: Range<f32> = 0.2..0.45;
+
+// 定义尾焰的颜色
+const EXHAUST_COLOR: Color = Color::rgb(0.45, 0.75, 1.0);
+
+// 定义主引擎喷口到飞船中心的距离，飞船的碰撞胶囊体半长加半径为 5.0
+const ENGINE_OFFSET: f32 = 4.5;
+
+// 定义姿态控制喷口到飞船中心的距离
+const RCS_OFFSET: f32 = 2.5;
+
+// 定义转向或翻滚输入最大时每秒喷出的姿态控制气团数量
+const RCS_RATE: f32 = 20.0;
+
+// 定义姿态控制气团的喷出速度
+const RCS_SPEED: f32 = 6.0;
+
+// 定义姿态控制气团的大小
+const RCS_SIZE: f32 = 0.3;
+
+// 定义姿态控制气团的存在时间范围，单位为秒
+const RCS_LIFETIME_SECONDS: Range<f32> = 0.15..0.3;
+
+// 定义姿态控制气团的颜色
+const RCS_COLOR: Color = Color::rgb(0.9, 0.9, 0.95);
+
+// `Particle` 组件保存一个碎片的状态，碎片随时间减速、缩小并逐渐变得透明，存在时间结束后被销毁
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+pub struct Particle {
+    pub lifetime: Timer,
+    // 碎片生成时的大小
+    pub size: f32,
+    // 碎片的旋转速度
+    pub spin: Vec3,
+}
+
+// `ThrusterEmitter` 组件记录飞船的喷口还没有喷出的粒子数量，使喷出的粒子数量与帧率无关
+#[derive(Component, Reflect, Debug, Default)]
+#[reflect(Component)]
+pub struct ThrusterEmitter {
+    pub exhaust: f32,
+    pub rcs: f32,
+}
+
+// `ParticleAssets` 资源保存所有碎片共用的网格
+#[derive(Resource, Debug, Default)]
+struct ParticleAssets {
+    mesh: Handle<Mesh>,
+}
+
+// 定义一个名为 `ParticlePlugin` 的结构体
+// 它在实体被摧毁时生成向四周飞散的碎片，碎片的数量、速度和大小随被摧毁实体的大小变化
+// 它还根据玩家的输入从飞船尾部喷出尾焰，并在转向和翻滚时喷出姿态控制气团
+// 粒子只用于显示，不参与碰撞，使用它的应用需要提供网格和材质资源
+pub struct ParticlePlugin;
+
+impl Plugin for ParticlePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Particle>()
+            .register_type::<ThrusterEmitter>()
+            .init_resource::<ParticleAssets>()
+            .add_event::<EntityDestroyed>()
+            .add_systems(Startup, create_particle_assets)
+            .add_systems(
+                Update,
+                (
+                    add_thruster_emitters,
+                    spawn_explosions,
+                    emit_thruster_particles,
+                    update_particles,
+                )
+                    .chain()
+                    .in_set(InGameSet::EntityUpdates),
+            );
+    }
+}
+
+// 这个函数创建碎片共用的立方体网格
+fn create_particle_assets(
+    mut particle_assets: ResMut<ParticleAssets>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    particle_assets.mesh = meshes.add(Cuboid::new(1.0, 1.0, 1.0));
+}
+
+// 这个函数根据被摧毁的实体的种类返回碎片数量的倍数和火焰碎片所占的比例
+fn burst_style(kind: DestroyedKind) -> (f32, f64) {
+    match kind {
+        DestroyedKind::Asteroid => (1.0, 0.0),
+        DestroyedKind::BossPart => (1.0, 0.5),
+        DestroyedKind::Enemy => (1.5, 0.6),
+        DestroyedKind::Spaceship => (2.0, 0.6),
+        DestroyedKind::Boss => (3.0, 0.7),
+    }
+}
+
+// 这个函数在实体被摧毁时生成一团碎片
+fn spawn_explosions(
+    mut commands: Commands,
+    mut destroyed: EventReader<EntityDestroyed>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    particle_assets: Res<ParticleAssets>,
+    query: Query<(), With<Particle>>,
+) {
+    // 碎片只是视觉效果，因此使用线程本地的随机数生成器，不影响可以保存的 `GameRng` 的状态
+    let mut rng = rand::thread_rng();
+    let mut count = query.iter().count();
+
+    for event in destroyed.read() {
+        let (multiplier, fire_ratio) = burst_style(event.kind);
+        let burst = ((event.radius * PARTICLES_PER_RADIUS * multiplier) as usize)
+            .min(MAX_BURST_PARTICLES)
+            .min(MAX_PARTICLES.saturating_sub(count));
+        count += burst;
+
+        for _ in 0..burst {
+            let direction = Vec3::new(
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-0.3..0.3),
+                rng.gen_range(-1.0..1.0),
+            )
+            .normalize_or_zero();
+            let speed = event.radius * SPEED_PER_RADIUS * rng.gen_range(SPEED_JITTER);
+            let size = event.radius * SIZE_PER_RADIUS * rng.gen_range(SIZE_JITTER);
+            let spin = Vec3::new(
+                rng.gen_range(-MAX_SPIN..MAX_SPIN),
+                rng.gen_range(-MAX_SPIN..MAX_SPIN),
+                rng.gen_range(-MAX_SPIN..MAX_SPIN),
+            );
+
+            let color = if rng.gen_bool(fire_ratio) {
+                ParticleColor::Glowing(FIRE_COLOR)
+            } else {
+                ParticleColor::Lit(DEBRIS_COLOR)
+            };
+
+            // 碎片从实体内部靠近表面的位置出现
+            spawn_particle(
+                &mut commands,
+                &mut materials,
+                &particle_assets,
+                color,
+                Transform::from_translation(event.translation + direction * event.radius * 0.5),
+                direction * speed,
+                size,
+                Particle {
+                    lifetime: Timer::from_seconds(rng.gen_range(LIFETIME_SECONDS), TimerMode::Once),
+                    size,
+                    spin,
+                },
+            );
+        }
+    }
+}
+
+// 这个函数为新生成的飞船添加喷口
+fn add_thruster_emitters(
+    mut commands: Commands,
+    query: Query<Entity, (With<Spaceship>, Without<ThrusterEmitter>)>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).insert(ThrusterEmitter::default());
+    }
+}
+
+// 这个函数根据飞船的推力、转向和翻滚输入喷出尾焰和姿态控制气团
+// 粒子的数量与输入的大小成正比，粒子继承飞船的速度，使尾焰始终跟在飞船后面
+fn emit_thruster_particles(
+    mut commands: Commands,
+    mut query: Query<(
+        &GlobalTransform,
+        &Velocity,
+        &PlayerInput,
+        &mut ThrusterEmitter,
+    )>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    particle_assets: Res<ParticleAssets>,
+    time: Res<Time>,
+) {
+    // 尾焰只是视觉效果，因此使用线程本地的随机数生成器
+    let mut rng = rand::thread_rng();
+    let delta_seconds = time.delta_seconds();
+
+    for (transform, velocity, input, mut emitter) in query.iter_mut() {
+        let (_, rotation, translation) = transform.to_scale_rotation_translation();
+        // 飞船的前方是本地的 +Z 方向
+        let forward = rotation * Vec3::Z;
+        let right = rotation * Vec3::X;
+        let up = rotation * Vec3::Y;
+
+        // 向前推进时从尾部向后喷出尾焰，倒退时从头部向前喷出
+        emitter.exhaust += EXHAUST_RATE * input.thrust.abs() * delta_seconds;
+        let nozzle = -forward * input.thrust.signum();
+        while emitter.exhaust >= 1.0 {
+            emitter.exhaust -= 1.0;
+            let jitter = Vec3::new(
+                rng.gen_range(-0.15..0.15),
+                rng.gen_range(-0.15..0.15),
+                rng.gen_range(-0.15..0.15),
+            );
+            let size = EXHAUST_SIZE * input.thrust.abs().max(0.3) * rng.gen_range(SIZE_JITTER);
+            spawn_particle(
+                &mut commands,
+                &mut materials,
+                &particle_assets,
+                ParticleColor::Glowing(EXHAUST_COLOR),
+                Transform::from_translation(translation + nozzle * ENGINE_OFFSET),
+                velocity.value + (nozzle + jitter) * EXHAUST_SPEED * input.thrust.abs(),
+                size,
+                Particle {
+                    lifetime: Timer::from_seconds(
+                        rng.gen_range(EXHAUST_LIFETIME_SECONDS),
+                        TimerMode::Once,
+                    ),
+                    size,
+                    spin: Vec3::ZERO,
+                },
+            );
+        }
+
+        // 转向时在机头的侧面喷气，翻滚时在两侧机翼的上下方向喷气
+        let rcs_input = input.turn.abs().max(input.roll.abs());
+        emitter.rcs += RCS_RATE * rcs_input * delta_seconds;
+        while emitter.rcs >= 1.0 {
+            emitter.rcs -= 1.0;
+            let puffs = if input.turn.abs() >= input.roll.abs() {
+                // 机头向本地 +X 方向偏转时，机头的喷口向 -X 方向喷气
+                let side = -right * input.turn.signum();
+                vec![(forward * RCS_OFFSET, side)]
+            } else {
+                // 右侧机翼向上翻滚时，右侧喷口向下喷气，左侧喷口向上喷气
+                let side = -up * input.roll.signum();
+                vec![(right * RCS_OFFSET, side), (-right * RCS_OFFSET, -side)]
+            };
+            for (offset, direction) in puffs {
+                spawn_particle(
+                    &mut commands,
+                    &mut materials,
+                    &particle_assets,
+                    ParticleColor::Lit(RCS_COLOR),
+                    Transform::from_translation(translation + offset),
+                    velocity.value + direction * RCS_SPEED,
+                    RCS_SIZE,
+                    Particle {
+                        lifetime: Timer::from_seconds(
+                            rng.gen_range(RCS_LIFETIME_SECONDS),
+                            TimerMode::Once,
+                        ),
+                        size: RCS_SIZE,
+                        spin: Vec3::ZERO,
+                    },
+                );
+            }
+        }
+    }
+}
+
+// `ParticleColor` 表示粒子的颜色，以及它是自己发光还是受光照影响
+#[derive(Debug, Clone, Copy)]
+enum ParticleColor {
+    Glowing(Color),
+    Lit(Color),
+}
+
+// 这个函数生成一个粒子，每个粒子拥有自己的材质，从而可以单独淡出
+#[allow(clippy::too_many_arguments)]
+fn spawn_particle(
+    commands: &mut Commands,
+    materials: &mut Assets<StandardMaterial>,
+    particle_assets: &ParticleAssets,
+    color: ParticleColor,
+    transform: Transform,
+    velocity: Vec3,
+    size: f32,
+    particle: Particle,
+) {
+    let material = match color {
+        ParticleColor::Glowing(color) => StandardMaterial {
+            base_color: color,
+            emissive: color,
+            unlit: true,
+            alpha_mode: AlphaMode::Blend,
+            ..default()
+        },
+        ParticleColor::Lit(color) => StandardMaterial {
+            base_color: color,
+            alpha_mode: AlphaMode::Blend,
+            ..default()
+        },
+    };
+    commands.spawn((
+        PbrBundle {
+            mesh: particle_assets.mesh.clone(),
+            material: materials.add(material),
+            transform: transform.with_scale(Vec3::splat(size)),
+            ..default()
+        },
+        Velocity::new(velocity),
+        particle,
+    ));
+}
+
+// 这个函数让碎片减速、旋转、缩小并淡出，存在时间结束的碎片会被销毁
+fn update_particles(
+    mut commands: Commands,
+    mut query: Query<(
+        Entity,
+        &mut Particle,
+        &mut Transform,
+        &mut Velocity,
+        &Handle<StandardMaterial>,
+    )>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    time: Res<Time>,
+) {
+    let delta_seconds = time.delta_seconds();
+    for (entity, mut particle, mut transform, mut velocity, material) in query.iter_mut() {
+        if particle.lifetime.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        let remaining = 1.0 - particle.lifetime.fraction();
+
+        velocity.value *= (1.0 - DRAG * delta_seconds).max(0.0);
+        let spin = particle.spin * delta_seconds;
+        transform.rotate(Quat::from_euler(EulerRot::XYZ, spin.x, spin.y, spin.z));
+        transform.scale = Vec3::splat(particle.size * remaining);
+
+        if let Some(material) = materials.get_mut(material) {
+            material.base_color.set_a(remaining);
+        }
+    }
+}
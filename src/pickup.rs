@@ -0,0 +1,280 @@
+use bevy::prelude::*;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    collision_detection::{Collider, DestroyedKind, EntityDestroyed, Sensor},
+    movement::{Acceleration, MovingObjectBundle, Velocity},
+    player::PlayerId,
+    rng::GameRng,
+    schedule::InGameSet,
+    score::Score,
+    spaceship::{Lives, ShieldEnergy, Spaceship, SpaceshipWeapon},
+};
+
+// 定义小行星被摧毁时掉落道具的概率
+const DROP_CHANCE: f64 = 0.2;
+
+// 定义道具的半径
+const PICKUP_RADIUS: f32 = 1.5;
+
+// 定义道具模型的大小，模型比碰撞器略小
+const PICKUP_SIZE: f32 = 1.2;
+
+// 定义道具自发光的强度，使道具在黑暗的背景中比小行星更醒目
+const PICKUP_EMISSIVE_SCALE: f32 = 2.0;
+
+// 定义道具旋转的速度，单位为弧度每秒
+const PICKUP_ROTATE_SPEED: f32 = 1.5;
+
+// 定义道具漂移的速度
+const DRIFT_SPEED: f32 = 2.0;
+
+// 定义道具的存在时间，单位为秒，超时后道具消失
+const PICKUP_LIFETIME_SECONDS: f32 = 10.0;
+
+// 定义速射道具的持续时间，单位为秒
+const RAPID_FIRE_SECONDS: f32 = 8.0;
+
+// 定义追踪导弹道具的持续时间，单位为秒
+const HOMING_MISSILES_SECONDS: f32 = 8.0;
+
+// 定义分数倍数道具的倍数和持续时间
+const SCORE_MULTIPLIER: u64 = 2;
+const SCORE_MULTIPLIER_SECONDS: f32 = 10.0;
+
+// `PickupKind` 表示道具的种类
+#[derive(Reflect, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PickupKind {
+    // 恢复护盾能量
+    ShieldRecharge,
+    // 增加一艘备用飞船
+    ExtraLife,
+    // 提升武器等级
+    WeaponUpgrade,
+    // 在一段时间内缩短开火间隔
+    RapidFire,
+    // 在一段时间内获得双倍分数
+    ScoreMultiplier,
+    // 在一段时间内发射追踪导弹
+    HomingMissiles,
+    // 在一段时间内放慢游戏时间
+    BulletTime,
+}
+
+impl PickupKind {
+    const ALL: [PickupKind; 7] = [
+        PickupKind::ShieldRecharge,
+        PickupKind::ExtraLife,
+        PickupKind::WeaponUpgrade,
+        PickupKind::RapidFire,
+        PickupKind::ScoreMultiplier,
+        PickupKind::HomingMissiles,
+        PickupKind::BulletTime,
+    ];
+
+    // 返回道具的颜色，每种道具的颜色都不相同
+    fn color(&self) -> Color {
+        match self {
+            PickupKind::ShieldRecharge => Color::rgb(0.2, 0.8, 1.0),
+            PickupKind::ExtraLife => Color::rgb(0.2, 1.0, 0.3),
+            PickupKind::WeaponUpgrade => Color::rgb(1.0, 0.2, 0.2),
+            PickupKind::RapidFire => Color::rgb(1.0, 0.6, 0.1),
+            PickupKind::ScoreMultiplier => Color::rgb(1.0, 0.9, 0.2),
+            PickupKind::HomingMissiles => Color::rgb(0.9, 0.3, 1.0),
+            PickupKind::BulletTime => Color::rgb(0.9, 0.9, 1.0),
+        }
+    }
+
+    // 返回道具的模型，形状和颜色一起区分道具的种类
+    fn mesh(&self) -> Mesh {
+        let size = PICKUP_SIZE;
+        match self {
+            PickupKind::ShieldRecharge => Sphere::new(size * 0.5).into(),
+            PickupKind::ExtraLife => Capsule3d::new(size * 0.3, size * 0.5).into(),
+            PickupKind::WeaponUpgrade => Cuboid::from_size(Vec3::splat(size * 0.8)).into(),
+            PickupKind::RapidFire => Cylinder::new(size * 0.3, size).into(),
+            PickupKind::ScoreMultiplier => Torus::new(size * 0.2, size * 0.5).into(),
+            PickupKind::HomingMissiles => Cuboid::new(size * 0.3, size * 0.3, size).into(),
+            PickupKind::BulletTime => Torus::new(size * 0.35, size * 0.5).into(),
+        }
+    }
+}
+
+// `Pickup` 组件标记道具实体
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+pub struct Pickup {
+    pub kind: PickupKind,
+    lifetime: Timer,
+}
+
+impl Pickup {
+    // 创建一个刚刚生成的道具
+    pub fn new(kind: PickupKind) -> Self {
+        Self {
+            kind,
+            lifetime: Timer::from_seconds(PICKUP_LIFETIME_SECONDS, TimerMode::Once),
+        }
+    }
+}
+
+// `PickupCollected` 事件在玩家拾取道具时发送
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PickupCollected {
+    pub kind: PickupKind,
+    pub player: PlayerId,
+    pub translation: Vec3,
+}
+
+pub struct PickupPlugin;
+
+impl Plugin for PickupPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Pickup>()
+            .register_type::<PickupKind>()
+            .register_type::<GameRng>()
+            .register_type::<TimerMode>()
+            .init_resource::<GameRng>()
+            .add_event::<PickupCollected>()
+            .add_systems(
+                Update,
+                (drop_pickups, expire_pickups, rotate_pickups).in_set(InGameSet::EntityUpdates),
+            )
+            .add_systems(Update, collect_pickups.in_set(InGameSet::DespawnEntities))
+            .add_systems(Update, add_pickup_visuals);
+    }
+}
+
+// 这个函数生成一个道具，道具以随机的方向缓慢漂移，它的模型由 `add_pickup_visuals` 添加
+pub fn spawn_pickup(
+    commands: &mut Commands,
+    rng: &mut impl Rng,
+    kind: PickupKind,
+    translation: Vec3,
+) {
+    let direction =
+        Vec3::new(rng.gen_range(-1.0..1.0), 0., rng.gen_range(-1.0..1.0)).normalize_or_zero();
+    commands.spawn((
+        MovingObjectBundle {
+            velocity: Velocity::new(direction * DRIFT_SPEED),
+            acceleration: Acceleration::new(Vec3::ZERO),
+            collider: Collider::new(PICKUP_RADIUS),
+            model: SceneBundle {
+                transform: Transform::from_translation(translation),
+                ..default()
+            },
+        },
+        Pickup::new(kind),
+        Sensor,
+    ));
+}
+
+// 这个函数让被摧毁的小行星按一定概率掉落随机种类的道具
+fn drop_pickups(
+    mut commands: Commands,
+    mut destroyed: EventReader<EntityDestroyed>,
+    mut rng: ResMut<GameRng>,
+) {
+    for event in destroyed.read() {
+        if event.kind != DestroyedKind::Asteroid || !rng.gen_bool(DROP_CHANCE) {
+            continue;
+        }
+        let kind = *PickupKind::ALL.choose(rng.as_mut()).unwrap();
+        spawn_pickup(&mut commands, rng.as_mut(), kind, event.translation);
+    }
+}
+
+// 这个函数销毁存在时间已经结束的道具
+fn expire_pickups(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Pickup)>,
+    time: Res<Time>,
+) {
+    for (entity, mut pickup) in query.iter_mut() {
+        if pickup.lifetime.tick(time.delta()).just_finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+// 这个函数让道具缓慢旋转，与翻滚的小行星区分开
+fn rotate_pickups(mut query: Query<&mut Transform, With<Pickup>>, time: Res<Time>) {
+    for mut transform in query.iter_mut() {
+        transform.rotate_y(PICKUP_ROTATE_SPEED * time.delta_seconds());
+    }
+}
+
+// 这个函数为新生成的道具添加与种类对应的自发光模型
+// 无窗口运行时没有网格和材质资源，此时道具没有模型；网络客户端也用它为同步来的道具添加模型
+pub fn add_pickup_visuals(
+    mut commands: Commands,
+    query: Query<(Entity, &Pickup), Added<Pickup>>,
+    meshes: Option<ResMut<Assets<Mesh>>>,
+    materials: Option<ResMut<Assets<StandardMaterial>>>,
+) {
+    let (Some(mut meshes), Some(mut materials)) = (meshes, materials) else {
+        return;
+    };
+    for (entity, pickup) in query.iter() {
+        let color = pickup.kind.color();
+        let visual = commands
+            .spawn(PbrBundle {
+                mesh: meshes.add(pickup.kind.mesh()),
+                material: materials.add(StandardMaterial {
+                    base_color: color,
+                    emissive: color * PICKUP_EMISSIVE_SCALE,
+                    ..default()
+                }),
+                ..default()
+            })
+            .id();
+        commands.entity(entity).add_child(visual);
+    }
+}
+
+// 这个函数处理道具与飞船的接触：道具被拾取并生效，飞船不会因此被摧毁
+fn collect_pickups(
+    mut commands: Commands,
+    pickup_query: Query<(Entity, &Pickup, &Collider, &GlobalTransform)>,
+    mut spaceship_query: Query<
+        (&PlayerId, &mut SpaceshipWeapon, &mut ShieldEnergy),
+        With<Spaceship>,
+    >,
+    mut lives: ResMut<Lives>,
+    mut score: ResMut<Score>,
+    mut collected: EventWriter<PickupCollected>,
+) {
+    for (entity, pickup, collider, transform) in pickup_query.iter() {
+        // 找到第一艘接触到这个道具的飞船
+        let Some(&spaceship) = collider
+            .colliding_entities
+            .iter()
+            .find(|&&collided_entity| spaceship_query.contains(collided_entity))
+        else {
+            continue;
+        };
+        let Ok((&player, mut weapon, mut shield)) = spaceship_query.get_mut(spaceship) else {
+            continue;
+        };
+
+        match pickup.kind {
+            PickupKind::ShieldRecharge => shield.recharge(),
+            PickupKind::ExtraLife => lives.remaining += 1,
+            PickupKind::WeaponUpgrade => weapon.upgrade(),
+            PickupKind::RapidFire => weapon.rapid_fire_seconds = RAPID_FIRE_SECONDS,
+            PickupKind::HomingMissiles => weapon.homing_seconds = HOMING_MISSILES_SECONDS,
+            PickupKind::ScoreMultiplier => {
+                score.apply_multiplier(SCORE_MULTIPLIER, SCORE_MULTIPLIER_SECONDS)
+            }
+            // 子弹时间由 `TimeControlPlugin` 监听 `PickupCollected` 事件开始
+            PickupKind::BulletTime => {}
+        }
+        commands.entity(entity).despawn_recursive();
+        collected.send(PickupCollected {
+            kind: pickup.kind,
+            player,
+            translation: transform.translation(),
+        });
+    }
+}
@@ -0,0 +1,158 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::schedule::InGameSet;
+
+// 定义玩家的最大数量，本地分屏模式最多支持两名玩家
+pub const MAX_PLAYERS: usize = 2;
+
+// `PlayerId` 组件用于标记实体属于哪一名玩家（从 0 开始编号）
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[reflect(Component)]
+pub struct PlayerId(pub usize);
+
+// `PlayerInput` 组件保存玩家在当前帧的输入动作，与具体的按键无关
+// 飞船的控制系统只读取这个组件，因此输入既可以来自键盘，也可以来自其他来源
+#[derive(Component, Reflect, Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[reflect(Component)]
+pub struct PlayerInput {
+    // 推力，1.0 表示前进，-1.0 表示后退
+    pub thrust: f32,
+    // 转向，1.0 表示向左转，-1.0 表示向右转
+    pub turn: f32,
+    // 翻滚，1.0 表示向右翻滚，-1.0 表示向左翻滚
+    pub roll: f32,
+    // 是否开火
+    pub fire: bool,
+    // 是否开启护盾
+    pub shield: bool,
+}
+
+// `KeyBindings` 组件保存某一名玩家的按键绑定
+// 只有拥有这个组件的飞船才会读取本地键盘输入
+#[derive(Component, Reflect, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[reflect(Component)]
+pub struct KeyBindings {
+    pub forward: KeyCode,
+    pub backward: KeyCode,
+    pub turn_left: KeyCode,
+    pub turn_right: KeyCode,
+    pub roll_left: KeyCode,
+    pub roll_right: KeyCode,
+    pub fire: KeyCode,
+    pub shield: KeyCode,
+}
+
+impl KeyBindings {
+    // 返回指定玩家的默认按键绑定
+    // 玩家一使用 WASD，玩家二使用方向键
+    pub fn for_player(player: PlayerId) -> Self {
+        match player.0 {
+            0 => Self {
+                forward: KeyCode::KeyW,
+                backward: KeyCode::KeyS,
+                turn_left: KeyCode::KeyA,
+                turn_right: KeyCode::KeyD,
+                roll_left: KeyCode::ShiftLeft,
+                roll_right: KeyCode::ControlLeft,
+                fire: KeyCode::Space,
+                shield: KeyCode::Tab,
+            },
+            _ => Self {
+                forward: KeyCode::ArrowUp,
+                backward: KeyCode::ArrowDown,
+                turn_left: KeyCode::ArrowLeft,
+                turn_right: KeyCode::ArrowRight,
+                roll_left: KeyCode::ShiftRight,
+                roll_right: KeyCode::ControlRight,
+                fire: KeyCode::Enter,
+                shield: KeyCode::Backslash,
+            },
+        }
+    }
+}
+
+// `PlayerConfig` 资源描述本局游戏的玩家设置
+#[derive(Resource, Reflect, Debug, Clone, Copy, PartialEq, Eq)]
+#[reflect(Resource)]
+pub struct PlayerConfig {
+    // 本地玩家的数量，大于 1 时启用分屏，为 0 时不生成本地飞船（例如专用服务器）
+    pub player_count: usize,
+    // 是否允许玩家的导弹击毁其他玩家的飞船
+    pub friendly_fire: bool,
+}
+
+impl Default for PlayerConfig {
+    fn default() -> Self {
+        Self {
+            player_count: 1,
+            friendly_fire: false,
+        }
+    }
+}
+
+impl PlayerConfig {
+    // 根据命令行参数创建玩家设置
+    // `--two-players` 启用双人分屏模式，`--friendly-fire` 启用友军伤害
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Self {
+        let mut config = Self::default();
+        for arg in args {
+            match arg.as_str() {
+                "--two-players" => config.player_count = MAX_PLAYERS,
+                "--friendly-fire" => config.friendly_fire = true,
+                _ => {}
+            }
+        }
+        config
+    }
+
+    // 返回本局游戏中所有玩家的编号
+    pub fn players(&self) -> impl Iterator<Item = PlayerId> {
+        (0..self.player_count.min(MAX_PLAYERS)).map(PlayerId)
+    }
+
+    // 是否处于分屏模式
+    pub fn is_split_screen(&self) -> bool {
+        self.player_count > 1
+    }
+}
+
+pub struct PlayerPlugin;
+
+impl Plugin for PlayerPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<PlayerId>()
+            .register_type::<PlayerInput>()
+            .register_type::<KeyBindings>()
+            .register_type::<PlayerConfig>()
+            .init_resource::<PlayerConfig>()
+            .add_systems(Update, read_keyboard_input.in_set(InGameSet::UserInput));
+    }
+}
+
+// 这个函数根据每名玩家的按键绑定，把键盘状态转换为 `PlayerInput`
+pub fn read_keyboard_input(
+    mut query: Query<(&KeyBindings, &mut PlayerInput)>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+) {
+    for (bindings, mut input) in query.iter_mut() {
+        // 辅助函数：把一对按键转换为 -1.0、0.0 或 1.0
+        let axis = |positive: KeyCode, negative: KeyCode| {
+            if keyboard_input.pressed(negative) {
+                -1.0
+            } else if keyboard_input.pressed(positive) {
+                1.0
+            } else {
+                0.0
+            }
+        };
+
+        *input = PlayerInput {
+            thrust: axis(bindings.forward, bindings.backward),
+            turn: axis(bindings.turn_left, bindings.turn_right),
+            roll: axis(bindings.roll_right, bindings.roll_left),
+            fire: keyboard_input.pressed(bindings.fire),
+            shield: keyboard_input.pressed(bindings.shield),
+        };
+    }
+}
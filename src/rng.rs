@@ -0,0 +1,41 @@
+use bevy::prelude::*;
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+// `GameRng` 资源是所有游戏逻辑共用的随机数生成器
+// 它的状态可以被序列化，因此保存和读取游戏状态后，随后生成的小行星、敌人和道具与保存时完全一致
+#[derive(Resource, Reflect, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[reflect_value(Resource, Serialize, Deserialize)]
+pub struct GameRng(ChaCha8Rng);
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self(ChaCha8Rng::from_entropy())
+    }
+}
+
+impl GameRng {
+    // 使用固定的种子创建随机数生成器，相同的种子总是产生相同的随机数序列
+    pub fn from_seed(seed: u64) -> Self {
+        Self(ChaCha8Rng::seed_from_u64(seed))
+    }
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.0.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.0.try_fill_bytes(dest)
+    }
+}
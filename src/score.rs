@@ -0,0 +1,83 @@
+use bevy::prelude::*;
+
+use crate::{
+    collision_detection::{DestroyedKind, EntityDestroyed},
+    schedule::InGameSet,
+};
+
+// 定义摧毁各种实体获得的基础分数
+const ASTEROID_POINTS: u64 = 10;
+const ENEMY_POINTS: u64 = 50;
+const BOSS_PART_POINTS: u64 = 25;
+const BOSS_POINTS: u64 = 500;
+
+// `Score` 资源记录本局游戏的分数
+#[derive(Resource, Reflect, Debug, Clone, PartialEq)]
+#[reflect(Resource)]
+pub struct Score {
+    pub points: u64,
+    // 当前的分数倍数
+    pub multiplier: u64,
+    // 分数倍数剩余的持续时间，单位为秒，结束后倍数恢复为 1
+    pub multiplier_seconds: f32,
+}
+
+impl Default for Score {
+    fn default() -> Self {
+        Self {
+            points: 0,
+            multiplier: 1,
+            multiplier_seconds: 0.0,
+        }
+    }
+}
+
+impl Score {
+    // 在一段时间内使用指定的分数倍数
+    pub fn apply_multiplier(&mut self, multiplier: u64, seconds: f32) {
+        self.multiplier = multiplier;
+        self.multiplier_seconds = seconds;
+    }
+}
+
+pub struct ScorePlugin;
+
+impl Plugin for ScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Score>()
+            .init_resource::<Score>()
+            .add_systems(
+                Update,
+                (award_points, tick_multiplier).in_set(InGameSet::EntityUpdates),
+            );
+    }
+}
+
+// 这个函数为玩家导弹摧毁的实体加分
+fn award_points(mut score: ResMut<Score>, mut destroyed: EventReader<EntityDestroyed>) {
+    for event in destroyed.read() {
+        if event.by_player.is_none() {
+            continue;
+        }
+        let points = match event.kind {
+            DestroyedKind::Asteroid => ASTEROID_POINTS,
+            DestroyedKind::Enemy => ENEMY_POINTS,
+            DestroyedKind::BossPart => BOSS_PART_POINTS,
+            DestroyedKind::Boss => BOSS_POINTS,
+            DestroyedKind::Spaceship => 0,
+        };
+        score.points += points * score.multiplier;
+    }
+}
+
+// 这个函数在分数倍数到期后把它恢复为 1
+fn tick_multiplier(mut score: ResMut<Score>, time: Res<Time>) {
+    if score.multiplier_seconds <= 0.0 {
+        return;
+    }
+    score.multiplier_seconds -= time.delta_seconds();
+    if score.multiplier_seconds <= 0.0 {
+        score.multiplier_seconds = 0.0;
+        score.multiplier = 1;
+    }
+}
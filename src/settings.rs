@@ -0,0 +1,539 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use bevy::{
+    input::InputSystem,
+    prelude::*,
+    window::{PresentMode, PrimaryWindow, WindowMode},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    audio::AudioVolume,
+    camera::CameraMode,
+    difficulty::Difficulty,
+    player::{KeyBindings, PlayerId, MAX_PLAYERS},
+    schedule::InGameSet,
+};
+
+// 定义打开或关闭设置菜单的按键
+const TOGGLE_KEY: KeyCode = KeyCode::F10;
+
+// 定义设置文件所在的目录名和文件名
+const SETTINGS_DIR_NAME: &str = "spaceship_game";
+const SETTINGS_FILE_NAME: &str = "settings.ron";
+
+// 定义每次调整音量的步长
+const VOLUME_STEP: f32 = 0.1;
+
+// 定义按键绑定中每个动作的名称，顺序与 `binding_mut` 一致
+const BINDING_NAMES: [&str; 8] = [
+    "Forward",
+    "Backward",
+    "Turn left",
+    "Turn right",
+    "Roll left",
+    "Roll right",
+    "Fire",
+    "Shield",
+];
+
+// `GraphicsSettings` 保存需要在创建窗口时应用的图形选项
+#[derive(Reflect, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GraphicsSettings {
+    pub fullscreen: bool,
+    pub vsync: bool,
+    pub msaa: bool,
+}
+
+impl Default for GraphicsSettings {
+    fn default() -> Self {
+        Self {
+            fullscreen: false,
+            vsync: true,
+            msaa: true,
+        }
+    }
+}
+
+impl GraphicsSettings {
+    // 返回窗口的显示模式
+    pub fn window_mode(&self) -> WindowMode {
+        if self.fullscreen {
+            WindowMode::BorderlessFullscreen
+        } else {
+            WindowMode::Windowed
+        }
+    }
+
+    // 返回窗口的呈现模式，关闭垂直同步时不限制帧率
+    pub fn present_mode(&self) -> PresentMode {
+        if self.vsync {
+            PresentMode::AutoVsync
+        } else {
+            PresentMode::AutoNoVsync
+        }
+    }
+
+    // 返回多重采样抗锯齿的设置
+    pub fn msaa(&self) -> Msaa {
+        if self.msaa {
+            Msaa::Sample4
+        } else {
+            Msaa::Off
+        }
+    }
+}
+
+// `Settings` 资源保存玩家的设置，包括音量、按键绑定、难度、摄像机模式和图形选项
+// 设置在修改后立即写入设置文件，文件中缺少的字段使用默认值
+#[derive(Resource, Reflect, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[reflect(Resource)]
+#[serde(default)]
+pub struct Settings {
+    pub volume: AudioVolume,
+    // 每名玩家的按键绑定，按玩家编号排列
+    pub key_bindings: Vec<KeyBindings>,
+    pub difficulty: Difficulty,
+    pub camera_mode: CameraMode,
+    pub graphics: GraphicsSettings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            volume: AudioVolume::default(),
+            key_bindings: (0..MAX_PLAYERS)
+                .map(|player| KeyBindings::for_player(PlayerId(player)))
+                .collect(),
+            difficulty: Difficulty::default(),
+            camera_mode: CameraMode::default(),
+            graphics: GraphicsSettings::default(),
+        }
+    }
+}
+
+impl Settings {
+    // 从 `path` 读取 RON 格式的设置文件
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path).map_err(|error| error.to_string())?;
+        ron::from_str(&contents).map_err(|error| error.to_string())
+    }
+
+    // 把设置写入 `path`，目录不存在时会先创建目录
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|error| error.to_string())?;
+        }
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|error| error.to_string())?;
+        fs::write(path, contents).map_err(|error| error.to_string())
+    }
+
+    // 返回指定玩家的按键绑定，设置中没有这名玩家时使用默认的按键绑定
+    pub fn key_bindings(&self, player: PlayerId) -> KeyBindings {
+        self.key_bindings
+            .get(player.0)
+            .cloned()
+            .unwrap_or_else(|| KeyBindings::for_player(player))
+    }
+
+    // 返回按照图形选项创建主窗口的 `WindowPlugin`，用于在 `DefaultPlugins` 创建窗口之前应用设置
+    pub fn window_plugin(&self) -> WindowPlugin {
+        WindowPlugin {
+            primary_window: Some(Window {
+                mode: self.graphics.window_mode(),
+                present_mode: self.graphics.present_mode(),
+                ..default()
+            }),
+            ..default()
+        }
+    }
+}
+
+// `SettingsFile` 资源保存设置文件的路径，为 `None` 时设置不会被保存
+#[derive(Resource, Debug, Clone, PartialEq, Eq)]
+pub struct SettingsFile(pub Option<PathBuf>);
+
+impl Default for SettingsFile {
+    // 默认的路径位于用户的配置目录中
+    fn default() -> Self {
+        Self(config_dir().map(|dir| dir.join(SETTINGS_DIR_NAME).join(SETTINGS_FILE_NAME)))
+    }
+}
+
+impl SettingsFile {
+    // 读取设置文件，文件不存在或者无法解析时使用默认设置
+    // 这个函数在日志插件添加之前调用，因此错误直接输出到标准错误
+    pub fn load(&self) -> Settings {
+        let Some(path) = self.0.as_deref().filter(|path| path.exists()) else {
+            return Settings::default();
+        };
+        Settings::load(path).unwrap_or_else(|error| {
+            eprintln!("无法读取设置文件 {}: {}", path.display(), error);
+            Settings::default()
+        })
+    }
+}
+
+// 这个函数返回当前平台的用户配置目录
+// Windows 使用 `%APPDATA%`，macOS 使用 `~/Library/Application Support`，其他平台遵循 XDG 规范
+pub fn config_dir() -> Option<PathBuf> {
+    let from_env = |name: &str| {
+        env::var_os(name)
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+    };
+    if cfg!(target_os = "windows") {
+        from_env("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        from_env("HOME").map(|home| home.join("Library").join("Application Support"))
+    } else {
+        from_env("XDG_CONFIG_HOME").or_else(|| from_env("HOME").map(|home| home.join(".config")))
+    }
+}
+
+// `OptionsMenu` 资源记录设置菜单是否打开、当前选中的行，以及是否正在等待新的按键
+#[derive(Resource, Debug, Default)]
+pub struct OptionsMenu {
+    pub open: bool,
+    pub selected: usize,
+    pub rebinding: bool,
+}
+
+// `OptionRow` 表示设置菜单中的一行
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OptionRow {
+    MasterVolume,
+    MusicVolume,
+    EffectsVolume,
+    Difficulty,
+    CameraMode,
+    Fullscreen,
+    Vsync,
+    Msaa,
+    // 玩家和按键绑定动作的序号
+    KeyBinding(PlayerId, usize),
+    ResetDefaults,
+}
+
+// 这个函数返回设置菜单中的所有行
+fn option_rows() -> Vec<OptionRow> {
+    let mut rows = vec![
+        OptionRow::MasterVolume,
+        OptionRow::MusicVolume,
+        OptionRow::EffectsVolume,
+        OptionRow::Difficulty,
+        OptionRow::CameraMode,
+        OptionRow::Fullscreen,
+        OptionRow::Vsync,
+        OptionRow::Msaa,
+    ];
+    for player in 0..MAX_PLAYERS {
+        rows.extend(
+            (0..BINDING_NAMES.len()).map(|action| OptionRow::KeyBinding(PlayerId(player), action)),
+        );
+    }
+    rows.push(OptionRow::ResetDefaults);
+    rows
+}
+
+// 这个函数返回按键绑定中第 `action` 个动作的按键
+fn binding_mut(bindings: &mut KeyBindings, action: usize) -> &mut KeyCode {
+    match action {
+        0 => &mut bindings.forward,
+        1 => &mut bindings.backward,
+        2 => &mut bindings.turn_left,
+        3 => &mut bindings.turn_right,
+        4 => &mut bindings.roll_left,
+        5 => &mut bindings.roll_right,
+        6 => &mut bindings.fire,
+        _ => &mut bindings.shield,
+    }
+}
+
+// `OptionsRoot` 组件标记设置菜单的根节点
+#[derive(Component, Debug)]
+struct OptionsRoot;
+
+// `OptionsText` 组件标记设置菜单的文本
+#[derive(Component, Debug)]
+struct OptionsText;
+
+// 定义一个名为 `SettingsPlugin` 的结构体
+// 它把 `Settings` 资源应用到音量、按键绑定、难度、摄像机模式和窗口上，并在设置改变时写入设置文件
+// 按 F10 打开设置菜单，上下方向键选择，左右方向键调整，回车键切换选项或重新绑定按键，设置菜单打开时会清空键盘状态
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Settings>()
+            .register_type::<GraphicsSettings>()
+            .register_type::<Vec<KeyBindings>>()
+            .init_resource::<Settings>()
+            .init_resource::<SettingsFile>()
+            .init_resource::<OptionsMenu>()
+            .add_systems(Startup, spawn_options_menu)
+            .add_systems(PreUpdate, options_input.after(InputSystem))
+            .add_systems(
+                Update,
+                (
+                    apply_settings,
+                    apply_key_bindings,
+                    save_settings,
+                    update_options_ui,
+                )
+                    .chain()
+                    .before(InGameSet::UserInput),
+            );
+    }
+}
+
+// 这个函数在设置改变时更新音量、难度、摄像机模式、抗锯齿和窗口
+fn apply_settings(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    commands.insert_resource(settings.volume);
+    commands.insert_resource(settings.difficulty);
+    commands.insert_resource(settings.camera_mode);
+    commands.insert_resource(settings.graphics.msaa());
+    for mut window in windows.iter_mut() {
+        let (mode, present_mode) = (
+            settings.graphics.window_mode(),
+            settings.graphics.present_mode(),
+        );
+        if window.mode != mode {
+            window.mode = mode;
+        }
+        if window.present_mode != present_mode {
+            window.present_mode = present_mode;
+        }
+    }
+}
+
+// 这个函数使每艘本地飞船的按键绑定与设置保持一致，包括设置改变之后新生成的飞船
+fn apply_key_bindings(settings: Res<Settings>, mut query: Query<(&PlayerId, &mut KeyBindings)>) {
+    for (&player, mut bindings) in query.iter_mut() {
+        let wanted = settings.key_bindings(player);
+        if *bindings != wanted {
+            *bindings = wanted;
+        }
+    }
+}
+
+// 这个函数在设置被修改后把设置写入设置文件
+fn save_settings(settings: Res<Settings>, settings_file: Res<SettingsFile>) {
+    if !settings.is_changed() || settings.is_added() {
+        return;
+    }
+    let Some(path) = &settings_file.0 else {
+        return;
+    };
+    if let Err(error) = settings.save(path) {
+        error!("无法保存设置文件 {}: {}", path.display(), error);
+    }
+}
+
+// 这个函数生成设置菜单的界面，设置菜单一开始是隐藏的
+fn spawn_options_menu(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.75).into(),
+                visibility: Visibility::Hidden,
+                z_index: ZIndex::Global(90),
+                ..default()
+            },
+            OptionsRoot,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 16.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ),
+                OptionsText,
+            ));
+        });
+}
+
+// 这个函数处理设置菜单的键盘输入
+fn options_input(
+    mut menu: ResMut<OptionsMenu>,
+    mut settings: ResMut<Settings>,
+    mut keyboard_input: ResMut<ButtonInput<KeyCode>>,
+) {
+    let rows = option_rows();
+
+    // 正在重新绑定按键时，下一个按下的按键成为新的绑定，按 Esc 取消
+    if menu.rebinding {
+        if let Some(&key) = keyboard_input.get_just_pressed().next() {
+            menu.rebinding = false;
+            if let Some(&OptionRow::KeyBinding(player, action)) = rows.get(menu.selected) {
+                if key != KeyCode::Escape {
+                    set_key_binding(&mut settings, player, action, key);
+                }
+            }
+        }
+        keyboard_input.reset_all();
+        return;
+    }
+
+    if keyboard_input.just_pressed(TOGGLE_KEY) {
+        menu.open = !menu.open;
+        keyboard_input.reset_all();
+        return;
+    }
+    if !menu.open {
+        return;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::ArrowUp) {
+        menu.selected = (menu.selected + rows.len() - 1) % rows.len();
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowDown) {
+        menu.selected = (menu.selected + 1) % rows.len();
+    }
+    let row = rows[menu.selected.min(rows.len() - 1)];
+    if keyboard_input.just_pressed(KeyCode::ArrowLeft) {
+        adjust_option(&mut settings, row, -1);
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowRight) {
+        adjust_option(&mut settings, row, 1);
+    }
+    if keyboard_input.just_pressed(KeyCode::Enter) {
+        match row {
+            OptionRow::KeyBinding(..) => menu.rebinding = true,
+            OptionRow::ResetDefaults => *settings = Settings::default(),
+            _ => adjust_option(&mut settings, row, 1),
+        }
+    }
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        menu.open = false;
+    }
+    // 清空键盘状态，避免游戏逻辑在设置菜单打开时响应按键
+    keyboard_input.reset_all();
+}
+
+// 这个函数根据 `step` 的方向调整一行设置，开关类的设置在两个方向上都会切换
+fn adjust_option(settings: &mut Settings, row: OptionRow, step: i32) {
+    // 辅助函数：按照步长调整音量，并避免浮点误差累积
+    let adjust_volume = |volume: &mut f32| {
+        let steps = (*volume / VOLUME_STEP).round() as i32 + step;
+        *volume = (steps as f32 * VOLUME_STEP).clamp(0.0, 1.0);
+    };
+    match row {
+        OptionRow::MasterVolume => adjust_volume(&mut settings.volume.master),
+        OptionRow::MusicVolume => adjust_volume(&mut settings.volume.music),
+        OptionRow::EffectsVolume => adjust_volume(&mut settings.volume.effects),
+        OptionRow::Difficulty => {
+            let count = Difficulty::ALL.len() as i32;
+            let index = Difficulty::ALL
+                .iter()
+                .position(|&difficulty| difficulty == settings.difficulty)
+                .unwrap_or_default() as i32;
+            settings.difficulty = Difficulty::ALL[(index + step).rem_euclid(count) as usize];
+        }
+        OptionRow::CameraMode => {
+            settings.camera_mode = match settings.camera_mode {
+                CameraMode::Fixed => CameraMode::Follow,
+                CameraMode::Follow => CameraMode::Fixed,
+            };
+        }
+        OptionRow::Fullscreen => settings.graphics.fullscreen = !settings.graphics.fullscreen,
+        OptionRow::Vsync => settings.graphics.vsync = !settings.graphics.vsync,
+        OptionRow::Msaa => settings.graphics.msaa = !settings.graphics.msaa,
+        OptionRow::KeyBinding(..) | OptionRow::ResetDefaults => {}
+    }
+}
+
+// 这个函数把玩家的第 `action` 个动作绑定到 `key`，设置中缺少的玩家使用默认的按键绑定补齐
+fn set_key_binding(settings: &mut Settings, player: PlayerId, action: usize, key: KeyCode) {
+    while settings.key_bindings.len() <= player.0 {
+        let next = PlayerId(settings.key_bindings.len());
+        settings.key_bindings.push(KeyBindings::for_player(next));
+    }
+    *binding_mut(&mut settings.key_bindings[player.0], action) = key;
+}
+
+// 这个函数返回一行设置的显示文本
+fn option_label(settings: &Settings, row: OptionRow) -> String {
+    let on_off = |value: bool| if value { "On" } else { "Off" };
+    let percent = |volume: f32| format!("{:.0}%", volume * 100.0);
+    match row {
+        OptionRow::MasterVolume => format!("Master volume: {}", percent(settings.volume.master)),
+        OptionRow::MusicVolume => format!("Music volume: {}", percent(settings.volume.music)),
+        OptionRow::EffectsVolume => format!("Effects volume: {}", percent(settings.volume.effects)),
+        OptionRow::Difficulty => format!("Difficulty: {:?}", settings.difficulty),
+        OptionRow::CameraMode => format!("Camera: {:?}", settings.camera_mode),
+        OptionRow::Fullscreen => format!("Fullscreen: {}", on_off(settings.graphics.fullscreen)),
+        OptionRow::Vsync => format!("VSync: {}", on_off(settings.graphics.vsync)),
+        OptionRow::Msaa => format!("MSAA: {}", on_off(settings.graphics.msaa)),
+        OptionRow::KeyBinding(player, action) => {
+            let mut bindings = settings.key_bindings(player);
+            format!(
+                "Player {} {}: {:?}",
+                player.0 + 1,
+                BINDING_NAMES[action],
+                binding_mut(&mut bindings, action)
+            )
+        }
+        OptionRow::ResetDefaults => "Reset to defaults".to_string(),
+    }
+}
+
+// 这个函数更新设置菜单的界面
+// Bevy 的默认字体不包含中文字符，因此设置菜单使用英文
+fn update_options_ui(
+    menu: Res<OptionsMenu>,
+    settings: Res<Settings>,
+    mut root_query: Query<&mut Visibility, With<OptionsRoot>>,
+    mut text_query: Query<&mut Text, With<OptionsText>>,
+) {
+    if !menu.is_changed() && !settings.is_changed() {
+        return;
+    }
+    for mut visibility in root_query.iter_mut() {
+        *visibility = if menu.open {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+    }
+    for mut text in text_query.iter_mut() {
+        let mut lines = vec![
+            "OPTIONS".to_string(),
+            "Up/Down select, Left/Right change, Enter toggle or rebind, Esc close".to_string(),
+            String::new(),
+        ];
+        for (index, row) in option_rows().into_iter().enumerate() {
+            let cursor = if index == menu.selected { "> " } else { "  " };
+            let mut line = format!("{cursor}{}", option_label(&settings, row));
+            if index == menu.selected && menu.rebinding {
+                line.push_str("  [press a key]");
+            }
+            lines.push(line);
+        }
+        text.sections[0].value = lines.join("\n");
+    }
+}
@@ -0,0 +1,157 @@
+use std::fmt;
+
+use bevy::prelude::*;
+
+use crate::{
+    asteroids::Asteroid,
+    player::PlayerInput,
+    spaceship::{Spaceship, SpaceshipMissile},
+};
+
+// `ScriptStep` 结构体表示输入脚本中的一步：从 `tick` 帧开始使用 `input` 作为输入，直到下一步开始
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptStep {
+    pub tick: u64,
+    pub input: PlayerInput,
+}
+
+// `InputScript` 资源保存无窗口模拟中所有飞船使用的脚本化输入
+#[derive(Resource, Debug, Clone, Default, PartialEq)]
+pub struct InputScript {
+    steps: Vec<ScriptStep>,
+}
+
+impl InputScript {
+    // 根据步骤创建输入脚本，步骤会按照开始帧排序
+    pub fn new(mut steps: Vec<ScriptStep>) -> Self {
+        steps.sort_by_key(|step| step.tick);
+        Self { steps }
+    }
+
+    // 解析文本形式的输入脚本
+    // 脚本由空白分隔的步骤组成，每一步的格式为 `帧:动作,动作,...`
+    // 动作可以是 `thrust=<数值>`、`turn=<数值>`、`roll=<数值>`、`fire` 或 `shield`，动作为空表示停止所有输入
+    // 例如 `0:thrust=1,fire 120:turn=-1 240:` 表示先前进并开火，第 120 帧开始右转，第 240 帧停止
+    pub fn parse(script: &str) -> Result<Self, String> {
+        let mut steps = vec![];
+        for step in script.split_whitespace() {
+            let (tick, actions) = step
+                .split_once(':')
+                .ok_or_else(|| format!("步骤 `{}` 缺少 `:`", step))?;
+            let tick = tick
+                .parse()
+                .map_err(|_| format!("步骤 `{}` 的帧数不合法", step))?;
+            let mut input = PlayerInput::default();
+            for action in actions.split(',').filter(|action| !action.is_empty()) {
+                let (name, value) = match action.split_once('=') {
+                    Some((name, value)) => {
+                        let value: f32 = value
+                            .parse()
+                            .map_err(|_| format!("动作 `{}` 的数值不合法", action))?;
+                        (name, value.clamp(-1.0, 1.0))
+                    }
+                    None => (action, 1.0),
+                };
+                match name {
+                    "thrust" => input.thrust = value,
+                    "turn" => input.turn = value,
+                    "roll" => input.roll = value,
+                    "fire" => input.fire = value > 0.0,
+                    "shield" => input.shield = value > 0.0,
+                    _ => return Err(format!("未知的动作 `{}`", name)),
+                }
+            }
+            steps.push(ScriptStep { tick, input });
+        }
+        Ok(Self::new(steps))
+    }
+
+    // 返回第 `tick` 帧的输入，即开始帧不晚于 `tick` 的最后一步的输入
+    pub fn input_at(&self, tick: u64) -> PlayerInput {
+        self.steps
+            .iter()
+            .take_while(|step| step.tick <= tick)
+            .last()
+            .map(|step| step.input)
+            .unwrap_or_default()
+    }
+}
+
+// `SimulationStats` 资源记录模拟过程中的统计数据
+#[derive(Resource, Debug, Clone, Default, PartialEq)]
+pub struct SimulationStats {
+    // 已经运行的帧数
+    pub ticks: u64,
+    // 生成的小行星数量
+    pub asteroids_spawned: u64,
+    // 被移除的小行星数量（包括被导弹击毁和飞出边界的）
+    pub asteroids_removed: u64,
+    // 发射的导弹数量
+    pub missiles_fired: u64,
+    // 同一时刻存在的小行星的最大数量
+    pub max_asteroids: usize,
+    // 飞船被摧毁时的帧数，飞船存活到模拟结束时为 `None`
+    pub spaceship_destroyed_at: Option<u64>,
+}
+
+impl fmt::Display for SimulationStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "ticks: {}", self.ticks)?;
+        writeln!(f, "asteroids_spawned: {}", self.asteroids_spawned)?;
+        writeln!(f, "asteroids_removed: {}", self.asteroids_removed)?;
+        writeln!(f, "max_asteroids: {}", self.max_asteroids)?;
+        writeln!(f, "missiles_fired: {}", self.missiles_fired)?;
+        match self.spaceship_destroyed_at {
+            Some(tick) => write!(f, "spaceship_destroyed_at: {}", tick),
+            None => write!(f, "spaceship_destroyed_at: none"),
+        }
+    }
+}
+
+pub struct SimulationPlugin;
+
+// 输入在 `PreUpdate` 阶段写入，这样游戏逻辑在 `Update` 阶段读到的就是本帧的脚本输入
+// 统计在 `Last` 阶段进行，此时本帧生成和销毁实体的命令都已经执行
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InputScript>()
+            .init_resource::<SimulationStats>()
+            .add_systems(PreUpdate, apply_input_script)
+            .add_systems(Last, collect_stats);
+    }
+}
+
+// 这个函数把当前帧的脚本输入写入所有飞船
+fn apply_input_script(
+    mut query: Query<&mut PlayerInput, With<Spaceship>>,
+    script: Res<InputScript>,
+    stats: Res<SimulationStats>,
+) {
+    let input = script.input_at(stats.ticks);
+    for mut player_input in query.iter_mut() {
+        *player_input = input;
+    }
+}
+
+// 这个函数统计本帧生成和移除的实体
+fn collect_stats(
+    mut stats: ResMut<SimulationStats>,
+    added_asteroids: Query<(), Added<Asteroid>>,
+    asteroids: Query<(), With<Asteroid>>,
+    added_missiles: Query<(), Added<SpaceshipMissile>>,
+    spaceships: Query<(), With<Spaceship>>,
+    mut removed_asteroids: RemovedComponents<Asteroid>,
+    mut removed_spaceships: RemovedComponents<Spaceship>,
+) {
+    stats.asteroids_spawned += added_asteroids.iter().count() as u64;
+    stats.asteroids_removed += removed_asteroids.read().count() as u64;
+    stats.missiles_fired += added_missiles.iter().count() as u64;
+    stats.max_asteroids = stats.max_asteroids.max(asteroids.iter().count());
+    if removed_spaceships.read().count() > 0
+        && spaceships.is_empty()
+        && stats.spaceship_destroyed_at.is_none()
+    {
+        stats.spaceship_destroyed_at = Some(stats.ticks);
+    }
+    stats.ticks += 1;
+}
@@ -0,0 +1,277 @@
+use std::{fs, path::Path};
+
+use bevy::{ecs::entity::EntityHashMap, prelude::*, scene::serde::SceneDeserializer};
+use serde::de::DeserializeSeed;
+
+use crate::{
+    asset_loader::SceneAssets,
+    asteroids::{Asteroid, AsteroidBehavior, AsteroidKind, Tumble},
+    boss::{Boss, BossHealthBarRoot, BossPart},
+    collider_shape::ColliderShape,
+    collision_detection::Collider,
+    console::{parse_optional_arg, ConsoleCommandsExt},
+    enemy::{Enemy, EnemyProjectile},
+    hazards::Hazard,
+    health::Health,
+    homing::HomingMissile,
+    movement::{Acceleration, Velocity},
+    particles::Particle,
+    pickup::Pickup,
+    player::{KeyBindings, PlayerConfig, PlayerId},
+    rng::GameRng,
+    schedule::InGameSet,
+    score::Score,
+    spaceship::{
+        spaceship_bundle, Lives, MissileOwner, ShieldEnergy, Spaceship, SpaceshipMissile,
+        SpaceshipWeapon,
+    },
+    wave::Wave,
+};
+
+// 定义快速保存的按键
+const QUICK_SAVE_KEY: KeyCode = KeyCode::F5;
+
+// 定义快速读取的按键
+const QUICK_LOAD_KEY: KeyCode = KeyCode::F9;
+
+// 定义默认的存档路径
+pub const DEFAULT_SNAPSHOT_PATH: &str = "snapshot.scn.ron";
+
+// `SnapshotFilter` 筛选需要保存到存档中的实体
+type SnapshotFilter = Or<(With<Asteroid>, With<Spaceship>, With<SpaceshipMissile>)>;
+
+// `ClearOnLoadFilter` 筛选读取存档前需要删除的所有游戏实体，存档中没有的敌人、首领、道具、环境危害和碎片也会被删除
+type ClearOnLoadFilter = Or<(
+    SnapshotFilter,
+    With<Enemy>,
+    With<EnemyProjectile>,
+    With<Boss>,
+    With<BossPart>,
+    With<BossHealthBarRoot>,
+    With<Pickup>,
+    With<Hazard>,
+    With<Particle>,
+)>;
+
+// `SnapshotPlugin` 插件把正在运行的游戏保存为 RON 格式的场景文件，并可以从文件中恢复
+// 存档包含小行星、飞船和导弹的位置、速度、加速度和碰撞器，小行星的种类、行为、翻滚和生命值，飞船的武器、护盾和生命值，导弹的追踪目标，以及分数、波数、备用飞船数量和随机数生成器的状态
+// 按 F5 快速保存，按 F9 快速读取，也可以使用控制台命令 `save` 和 `load`
+pub struct SnapshotPlugin;
+
+impl Plugin for SnapshotPlugin {
+    fn build(&self, app: &mut App) {
+        // 存档通过反射读写组件和资源，这些类型虽然也由各自的插件注册，这里再注册一次，使存档不依赖于其他插件是否被添加
+        app.register_type::<Asteroid>()
+            .register_type::<AsteroidKind>()
+            .register_type::<AsteroidBehavior>()
+            .register_type::<Tumble>()
+            .register_type::<Health>()
+            .register_type::<Spaceship>()
+            .register_type::<SpaceshipWeapon>()
+            .register_type::<ShieldEnergy>()
+            .register_type::<SpaceshipMissile>()
+            .register_type::<MissileOwner>()
+            .register_type::<HomingMissile>()
+            .register_type::<Option<Entity>>()
+            .register_type::<PlayerId>()
+            .register_type::<Velocity>()
+            .register_type::<Acceleration>()
+            .register_type::<Collider>()
+            .register_type::<ColliderShape>()
+            .register_type::<Option<Vec3>>()
+            .register_type::<Vec<Vec3>>()
+            .register_type::<Score>()
+            .register_type::<Wave>()
+            .register_type::<Lives>()
+            .register_type::<TimerMode>()
+            .register_type::<GameRng>()
+            .add_systems(Update, quick_save_and_load.before(InGameSet::UserInput))
+            .add_console_command(
+                "save",
+                "save [path] - save the game to a snapshot file",
+                save_command,
+            )
+            .add_console_command(
+                "load",
+                "load [path] - restore the game from a snapshot file",
+                load_command,
+            );
+    }
+}
+
+// 这个函数把所有需要保存的实体和资源写入 `path`，返回保存的实体数量
+pub fn save_snapshot(world: &mut World, path: &Path) -> Result<usize, String> {
+    let entities: Vec<Entity> = world
+        .query_filtered::<Entity, SnapshotFilter>()
+        .iter(world)
+        .collect();
+    let scene = DynamicSceneBuilder::from_world(world)
+        .allow::<Asteroid>()
+        .allow::<AsteroidKind>()
+        .allow::<AsteroidBehavior>()
+        .allow::<Tumble>()
+        .allow::<Health>()
+        .allow::<Spaceship>()
+        .allow::<SpaceshipWeapon>()
+        .allow::<ShieldEnergy>()
+        .allow::<SpaceshipMissile>()
+        .allow::<MissileOwner>()
+        .allow::<HomingMissile>()
+        .allow::<PlayerId>()
+        .allow::<Transform>()
+        .allow::<Velocity>()
+        .allow::<Acceleration>()
+        .allow::<Collider>()
+        .allow_resource::<Score>()
+        .allow_resource::<Wave>()
+        .allow_resource::<Lives>()
+        .allow_resource::<GameRng>()
+        .extract_entities(entities.iter().copied())
+        .extract_resources()
+        .build();
+    let contents = scene
+        .serialize_ron(world.resource::<AppTypeRegistry>())
+        .map_err(|error| error.to_string())?;
+    fs::write(path, contents).map_err(|error| error.to_string())?;
+    Ok(entities.len())
+}
+
+// 这个函数从 `path` 读取存档，删除当前所有的游戏实体并换成存档中的实体，然后恢复资源，返回读取的实体数量
+pub fn load_snapshot(world: &mut World, path: &Path) -> Result<usize, String> {
+    let contents = fs::read_to_string(path).map_err(|error| error.to_string())?;
+    let type_registry = world.resource::<AppTypeRegistry>().clone();
+    let scene = {
+        let type_registry = type_registry.read();
+        let mut deserializer =
+            ron::de::Deserializer::from_str(&contents).map_err(|error| error.to_string())?;
+        SceneDeserializer {
+            type_registry: &type_registry,
+        }
+        .deserialize(&mut deserializer)
+        .map_err(|error| error.to_string())?
+    };
+
+    let existing: Vec<Entity> = world
+        .query_filtered::<Entity, ClearOnLoadFilter>()
+        .iter(world)
+        .collect();
+    for entity in existing {
+        // 首领的部件可能已经随首领一起被删除
+        if world.get_entity(entity).is_some() {
+            despawn_with_children_recursive(world, entity);
+        }
+    }
+
+    let mut entity_map = EntityHashMap::default();
+    scene
+        .write_to_world(world, &mut entity_map)
+        .map_err(|error| error.to_string())?;
+    let entities: Vec<Entity> = entity_map.values().copied().collect();
+    for &entity in &entities {
+        restore_entity(world, entity);
+    }
+    Ok(entities.len())
+}
+
+// 这个函数为从存档中读取的实体补充存档中没有保存的模型和其他组件
+fn restore_entity(world: &mut World, entity: Entity) {
+    let scene_assets = world.resource::<SceneAssets>();
+    let (asteroid_scene, spaceship_scene, missile_scene) = (
+        scene_assets.asteroid.clone(),
+        scene_assets.spaceship.clone(),
+        scene_assets.missiles.clone(),
+    );
+    let is_local_player = |world: &World, player: PlayerId| {
+        world
+            .resource::<PlayerConfig>()
+            .players()
+            .any(|local_player| local_player == player)
+    };
+
+    let entity_ref = world.entity(entity);
+    let transform = entity_ref.get::<Transform>().copied().unwrap_or_default();
+    if entity_ref.contains::<Spaceship>() {
+        // 飞船使用完整的飞船组件组合，再恢复存档中的运动状态、武器、护盾和生命值
+        let player = entity_ref.get::<PlayerId>().copied().unwrap_or(PlayerId(0));
+        let velocity = entity_ref.get::<Velocity>().map(|velocity| velocity.value);
+        let acceleration = entity_ref
+            .get::<Acceleration>()
+            .map(|acceleration| acceleration.value);
+        let local = is_local_player(world, player);
+        let mut entity_mut = world.entity_mut(entity);
+        let weapon = entity_mut.take::<SpaceshipWeapon>();
+        let shield = entity_mut.take::<ShieldEnergy>();
+        let health = entity_mut.take::<Health>();
+        entity_mut.insert(spaceship_bundle(
+            spaceship_scene,
+            player,
+            transform.translation,
+        ));
+        entity_mut.insert((
+            transform,
+            Velocity::new(velocity.unwrap_or_default()),
+            Acceleration::new(acceleration.unwrap_or_default()),
+        ));
+        if let Some(weapon) = weapon {
+            entity_mut.insert(weapon);
+        }
+        if let Some(shield) = shield {
+            entity_mut.insert(shield);
+        }
+        if let Some(health) = health {
+            entity_mut.insert(health);
+        }
+        if local {
+            entity_mut.insert(KeyBindings::for_player(player));
+        }
+        return;
+    }
+
+    let scene = if entity_ref.contains::<Asteroid>() {
+        asteroid_scene
+    } else {
+        missile_scene
+    };
+    world.entity_mut(entity).insert(SceneBundle {
+        scene,
+        transform,
+        ..default()
+    });
+}
+
+// 这个函数在按下快速保存或快速读取的按键时，使用默认的存档路径保存或读取游戏
+// 没有键盘输入（例如无窗口模拟）时不做任何事
+fn quick_save_and_load(world: &mut World) {
+    let Some(keyboard_input) = world.get_resource::<ButtonInput<KeyCode>>() else {
+        return;
+    };
+    let save = keyboard_input.just_pressed(QUICK_SAVE_KEY);
+    let load = keyboard_input.just_pressed(QUICK_LOAD_KEY);
+    let path = Path::new(DEFAULT_SNAPSHOT_PATH);
+    if save {
+        match save_snapshot(world, path) {
+            Ok(count) => info!("已保存 {} 个实体到 {}", count, path.display()),
+            Err(error) => error!("无法保存游戏: {}", error),
+        }
+    }
+    if load {
+        match load_snapshot(world, path) {
+            Ok(count) => info!("已从 {} 读取 {} 个实体", path.display(), count),
+            Err(error) => error!("无法读取存档: {}", error),
+        }
+    }
+}
+
+// `save` 控制台命令保存游戏
+fn save_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let path: String = parse_optional_arg(args, 0, "path", DEFAULT_SNAPSHOT_PATH.to_string())?;
+    let count = save_snapshot(world, Path::new(&path))?;
+    Ok(format!("saved {count} entities to {path}"))
+}
+
+// `load` 控制台命令读取存档
+fn load_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let path: String = parse_optional_arg(args, 0, "path", DEFAULT_SNAPSHOT_PATH.to_string())?;
+    let count = load_snapshot(world, Path::new(&path))?;
+    Ok(format!("loaded {count} entities from {path}"))
+}
@@ -1,17 +1,30 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashMap};
 
 use crate::{
     asset_loader::SceneAssets,
     collision_detection::Collider,
-    movement::{Acceleration, MovingObjectBundle, Velocity},
+    console::{parse_arg, ConsoleCommandsExt},
+    hazards::InNebula,
+    health::Health,
+    homing::HomingMissile,
+    movement::{Acceleration, ExternalVelocity, MovingObjectBundle, Velocity},
+    network::{spawn_network_spaceship, ConnectedClients},
+    player::{read_keyboard_input, KeyBindings, PlayerConfig, PlayerId, PlayerInput},
     schedule::InGameSet,
 };
 
 // 定义飞船的初始位置，这是一个三维向量，初始值为 (0.0, 0.0, -20.0)
 const STARTING_TRANSLATION: Vec3 = Vec3::new(0.0, 0.0, -20.0);
 
-// 定义飞船的半径，初始值为 5.0
-const SPACESHIP_RADIUS: f32 = 5.0;
+// 定义分屏模式下两艘飞船在 X 轴上的间隔，初始值为 30.0
+const SPLIT_SCREEN_SPACING: f32 = 30.0;
+
+// 定义飞船胶囊体碰撞器的半径和中心线的一半长度，飞船模型沿前后方向较长，包围球半径为 5.0
+const SPACESHIP_RADIUS: f32 = 2.5;
+const SPACESHIP_HALF_LENGTH: f32 = 2.5;
+
+// 定义飞船的生命值，普通难度下一次撞击就会摧毁飞船
+const SPACESHIP_HEALTH: f32 = 1.0;
 
 // 定义飞船的速度，初始值为 25.0
 const SPACESHIP_SPEED: f32 = 25.0;
@@ -28,18 +41,164 @@ const MISSILE_SPEED: f32 = 50.0;
 // 定义导弹前向生成标量，初始值为 7.5
 const MISSILE_FORWARD_SPAWN_SCALAR: f32 = 7.5;
 
-// 定义导弹的半径，初始值为 1.0
-const MISSILE_RADIUS: f32 = 1.0;
+// 定义导弹胶囊体碰撞器的半径和中心线的一半长度，导弹又细又长，包围球半径为 1.0
+const MISSILE_RADIUS: f32 = 0.4;
+const MISSILE_HALF_LENGTH: f32 = 0.6;
+
+// 定义两次开火之间的最短间隔，单位为秒，初始值为 0.1
+const FIRE_INTERVAL_SECONDS: f32 = 0.1;
+
+// 定义速射状态下两次开火之间的最短间隔，单位为秒，初始值为 0.04
+const RAPID_FIRE_INTERVAL_SECONDS: f32 = 0.04;
+
+// 定义武器的最高等级
+const MAX_WEAPON_LEVEL: u32 = 3;
+
+// 定义二级武器两枚平行导弹之间的一半间距
+const TWIN_MISSILE_OFFSET: f32 = 1.5;
 
-#[derive(Component, Debug)]
+// 定义三级武器散射导弹之间的夹角，单位为弧度
+const SPREAD_MISSILE_ANGLE: f32 = 0.15;
+
+// 定义护盾能量的最大值，即护盾可以连续开启的秒数，初始值为 5.0
+const SHIELD_MAX_ENERGY: f32 = 5.0;
+
+// 定义开局时的备用飞船数量
+const STARTING_LIVES: u32 = 2;
+
+// 定义飞船被摧毁后重新生成的等待时间，单位为秒
+const RESPAWN_DELAY_SECONDS: f32 = 2.0;
+
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
 pub struct Spaceship;
 
-#[derive(Component, Debug)]
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
 pub struct SpaceshipShield;
 
-#[derive(Component, Debug)]
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
 pub struct SpaceshipMissile;
 
+// `MissileOwner` 组件记录导弹是由哪一名玩家发射的，用于判断友军伤害
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq, Eq)]
+#[reflect(Component)]
+pub struct MissileOwner(pub PlayerId);
+
+// `SpaceshipWeapon` 组件保存飞船武器的状态
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+pub struct SpaceshipWeapon {
+    // 武器等级，等级越高每次发射的导弹越多
+    pub level: u32,
+    // 速射状态剩余的时间，单位为秒
+    pub rapid_fire_seconds: f32,
+    // 追踪导弹状态剩余的时间，单位为秒，期间发射的导弹会自动追踪目标
+    pub homing_seconds: f32,
+    cooldown: Timer,
+}
+
+impl Default for SpaceshipWeapon {
+    fn default() -> Self {
+        // 冷却计时器一开始就处于结束状态，使飞船生成后可以立即开火
+        let mut cooldown = Timer::from_seconds(FIRE_INTERVAL_SECONDS, TimerMode::Once);
+        cooldown.tick(cooldown.duration());
+        Self {
+            level: 1,
+            rapid_fire_seconds: 0.0,
+            homing_seconds: 0.0,
+            cooldown,
+        }
+    }
+}
+
+impl SpaceshipWeapon {
+    // 提升一级武器等级，最高为 `MAX_WEAPON_LEVEL`
+    pub fn upgrade(&mut self) {
+        self.level = (self.level + 1).min(MAX_WEAPON_LEVEL);
+    }
+
+    // 返回当前的开火间隔
+    fn fire_interval(&self) -> f32 {
+        if self.rapid_fire_seconds > 0.0 {
+            RAPID_FIRE_INTERVAL_SECONDS
+        } else {
+            FIRE_INTERVAL_SECONDS
+        }
+    }
+}
+
+// `ShieldEnergy` 组件保存飞船护盾的能量，开启护盾会持续消耗能量，能量只能通过道具恢复
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub struct ShieldEnergy {
+    pub current: f32,
+    pub max: f32,
+}
+
+impl Default for ShieldEnergy {
+    fn default() -> Self {
+        Self {
+            current: SHIELD_MAX_ENERGY,
+            max: SHIELD_MAX_ENERGY,
+        }
+    }
+}
+
+impl ShieldEnergy {
+    // 把护盾能量恢复到最大值
+    pub fn recharge(&mut self) {
+        self.current = self.max;
+    }
+}
+
+// `Lives` 资源记录所有玩家共享的备用飞船数量
+#[derive(Resource, Reflect, Debug, Clone, Copy, PartialEq, Eq)]
+#[reflect(Resource)]
+pub struct Lives {
+    pub remaining: u32,
+}
+
+impl Default for Lives {
+    fn default() -> Self {
+        Self {
+            remaining: STARTING_LIVES,
+        }
+    }
+}
+
+// `SpaceshipSettings` 资源保存可以在运行时调整的飞船参数，初始值来自上面的常量
+#[derive(Resource, Reflect, Debug, Clone, PartialEq)]
+#[reflect(Resource)]
+pub struct SpaceshipSettings {
+    pub speed: f32,
+    pub rotation_speed: f32,
+    pub missile_speed: f32,
+}
+
+impl Default for SpaceshipSettings {
+    fn default() -> Self {
+        Self {
+            speed: SPACESHIP_SPEED,
+            rotation_speed: SPACESHIP_ROTATION_SPEED,
+            missile_speed: MISSILE_SPEED,
+        }
+    }
+}
+
+// `GodMode` 资源开启时，玩家的飞船不会被摧毁
+#[derive(Resource, Reflect, Debug, Default)]
+#[reflect(Resource)]
+pub struct GodMode {
+    pub enabled: bool,
+}
+
+// `RespawnTimers` 资源记录每名飞船被摧毁的玩家距离重新生成还剩多少时间
+#[derive(Resource, Reflect, Debug, Default)]
+#[reflect(Resource)]
+struct RespawnTimers(HashMap<PlayerId, Timer>);
+
 pub struct SpaceshipPlugin;
 
 // 为 `SpaceshipPlugin` 实现 `Plugin` trait
@@ -47,148 +206,313 @@ impl Plugin for SpaceshipPlugin {
     // 在 `build` 方法中，配置 `PostStartup` 阶段的系统集，包括 `spawn_spaceship` 系统
     // 并在更新阶段添加 `spaceship_movement_controls`、`spaceship_weapon_controls` 和 `spaceship_shield_controls` 系统，这些系统在 `InGameSet::UserInput` 集合中运行
     fn build(&self, app: &mut App) {
-        app.add_systems(PostStartup, spawn_spaceship).add_systems(
-            Update,
-            (
-                spaceship_movement_controls,
-                spaceship_weapon_controls,
-                spaceship_shield_controls,
+        app.register_type::<Spaceship>()
+            .register_type::<SpaceshipShield>()
+            .register_type::<SpaceshipMissile>()
+            .register_type::<MissileOwner>()
+            .register_type::<SpaceshipWeapon>()
+            .register_type::<ShieldEnergy>()
+            .register_type::<Lives>()
+            .register_type::<SpaceshipSettings>()
+            .register_type::<GodMode>()
+            .register_type::<RespawnTimers>()
+            .register_type::<HashMap<PlayerId, Timer>>()
+            .register_type::<TimerMode>()
+            .init_resource::<Lives>()
+            .init_resource::<RespawnTimers>()
+            .init_resource::<SpaceshipSettings>()
+            .init_resource::<GodMode>()
+            .add_systems(PostStartup, spawn_spaceship)
+            .add_systems(
+                Update,
+                (
+                    spaceship_movement_controls,
+                    spaceship_weapon_controls,
+                    spaceship_shield_controls,
+                )
+                    .chain()
+                    .after(read_keyboard_input)
+                    .in_set(InGameSet::UserInput),
+            )
+            .add_systems(Update, respawn_spaceships.in_set(InGameSet::EntityUpdates))
+            .add_console_command("god", "god - toggle spaceship invulnerability", god_command)
+            .add_console_command(
+                "set spaceship.speed",
+                "set spaceship.speed <value> - set the spaceship speed",
+                |world, args| set_setting(world, args, |settings| &mut settings.speed),
+            )
+            .add_console_command(
+                "set spaceship.rotation_speed",
+                "set spaceship.rotation_speed <value> - set the spaceship turn rate",
+                |world, args| set_setting(world, args, |settings| &mut settings.rotation_speed),
             )
-                .chain()
-                .in_set(InGameSet::UserInput),
-        );
+            .add_console_command(
+                "set missile.speed",
+                "set missile.speed <value> - set the missile speed",
+                |world, args| set_setting(world, args, |settings| &mut settings.missile_speed),
+            );
     }
 }
 
-// 定义一个名为 `spawn_spaceship` 的函数，它接受一个可变的 `Commands` 类型参数和一个 `SceneAssets` 资源引用参数
-fn spawn_spaceship(mut commands: Commands, scene_assets: Res<SceneAssets>) {
-    // 使用 `commands` 的 `spawn` 方法来创建一个新的实体。
-    // 这个新实体拥有 `MovingObjectBundle` 组件和 `Spaceship` 组件。
-    // `MovingObjectBundle` 组件包含 `velocity`、`acceleration`、`collider` 和 `model`。
-    // `velocity` 和 `acceleration` 被设置为零向量，表示飞船的初始速度和加速度都是零。
-    // `collider` 被设置为飞船的半径，用于碰撞检测。
-    // `model` 被设置为 `SceneBundle`，其中 `scene` 是从 `scene_assets` 中克隆的飞船模型，`transform` 是飞船的初始位置。
+// `god` 控制台命令切换无敌模式
+fn god_command(world: &mut World, _args: &[&str]) -> Result<String, String> {
+    let mut god_mode = world.resource_mut::<GodMode>();
+    god_mode.enabled = !god_mode.enabled;
+    Ok(format!(
+        "god mode {}",
+        if god_mode.enabled { "on" } else { "off" }
+    ))
+}
+
+// 这个函数供 `set` 控制台命令使用，把第一个参数写入 `field` 选择的飞船参数
+// `nan` 和 `inf` 也能被解析为浮点数，它们会使飞船的运动失去意义，因此被拒绝
+fn set_setting(
+    world: &mut World,
+    args: &[&str],
+    field: fn(&mut SpaceshipSettings) -> &mut f32,
+) -> Result<String, String> {
+    let value: f32 = parse_arg(args, 0, "value")?;
+    if !value.is_finite() {
+        return Err(format!("invalid value `{value}` for <value>"));
+    }
+    *field(&mut world.resource_mut::<SpaceshipSettings>()) = value;
+    Ok(format!("set to {value}"))
+}
+
+// 定义一个名为 `spawn_spaceship` 的函数，它接受一个可变的 `Commands` 类型参数、一个 `SceneAssets` 资源引用参数和一个 `PlayerConfig` 资源引用参数
+// 这个函数为每一名本地玩家生成一艘飞船
+fn spawn_spaceship(
+    mut commands: Commands,
+    scene_assets: Res<SceneAssets>,
+    player_config: Res<PlayerConfig>,
+) {
+    for player in player_config.players() {
+        spawn_local_spaceship(&mut commands, &scene_assets, &player_config, player);
+    }
+}
+
+// 这个函数为一名本地玩家生成飞船
+// 单人模式下飞船位于初始位置，分屏模式下两艘飞船沿 X 轴左右分开
+fn spawn_local_spaceship(
+    commands: &mut Commands,
+    scene_assets: &SceneAssets,
+    player_config: &PlayerConfig,
+    player: PlayerId,
+) {
+    let offset = if player_config.is_split_screen() {
+        (player.0 as f32 - 0.5) * SPLIT_SCREEN_SPACING
+    } else {
+        0.0
+    };
+    // 本地玩家的飞船额外拥有 `KeyBindings` 组件，从而读取键盘输入
     commands.spawn((
+        spaceship_bundle(
+            scene_assets.spaceship.clone(),
+            player,
+            STARTING_TRANSLATION + Vec3::X * offset,
+        ),
+        KeyBindings::for_player(player),
+    ));
+}
+
+// 这个函数在玩家的飞船被摧毁一段时间后，消耗一艘备用飞船重新生成它
+// 服务器上还会为已连接客户端的网络玩家重新生成飞船
+#[allow(clippy::too_many_arguments)]
+fn respawn_spaceships(
+    mut commands: Commands,
+    mut respawn_timers: ResMut<RespawnTimers>,
+    mut lives: ResMut<Lives>,
+    query: Query<&PlayerId, With<Spaceship>>,
+    player_config: Res<PlayerConfig>,
+    clients: Option<Res<ConnectedClients>>,
+    scene_assets: Res<SceneAssets>,
+    time: Res<Time>,
+) {
+    let local_players = player_config.players().map(|player| (player, true));
+    let network_players = clients
+        .iter()
+        .flat_map(|clients| clients.players())
+        .map(|player| (player, false));
+    for (player, local) in local_players.chain(network_players) {
+        if query
+            .iter()
+            .any(|&spaceship_player| spaceship_player == player)
+        {
+            respawn_timers.0.remove(&player);
+            continue;
+        }
+        if lives.remaining == 0 {
+            continue;
+        }
+        let timer = respawn_timers
+            .0
+            .entry(player)
+            .or_insert_with(|| Timer::from_seconds(RESPAWN_DELAY_SECONDS, TimerMode::Once));
+        if timer.tick(time.delta()).just_finished() {
+            lives.remaining -= 1;
+            respawn_timers.0.remove(&player);
+            if local {
+                spawn_local_spaceship(&mut commands, &scene_assets, &player_config, player);
+            } else {
+                spawn_network_spaceship(&mut commands, &scene_assets, player);
+            }
+        }
+    }
+}
+
+// 这个函数返回生成一艘玩家飞船所需的组件，供本地玩家和网络玩家共用
+// 其中 `velocity` 和 `acceleration` 被设置为零向量，表示飞船的初始速度和加速度都是零。
+// `collider` 被设置为沿飞船前后方向延伸的胶囊体，用于碰撞检测。
+// `model` 被设置为 `SceneBundle`，其中 `scene` 是飞船模型，`transform` 是飞船的初始位置。
+// 飞船的速度每一帧都由输入重新设置，因此力场的效果保存在 `ExternalVelocity` 中。
+pub fn spaceship_bundle(scene: Handle<Scene>, player: PlayerId, translation: Vec3) -> impl Bundle {
+    (
         MovingObjectBundle {
             velocity: Velocity::new(Vec3::ZERO),
             acceleration: Acceleration::new(Vec3::ZERO),
-            collider: Collider::new(SPACESHIP_RADIUS),
+            collider: Collider::capsule(SPACESHIP_RADIUS, SPACESHIP_HALF_LENGTH),
             model: SceneBundle {
-                scene: scene_assets.spaceship.clone(),
-                transform: Transform::from_translation(STARTING_TRANSLATION),
+                scene,
+                transform: Transform::from_translation(translation),
                 ..default()
             },
         },
         Spaceship,
-    ));
+        player,
+        PlayerInput::default(),
+        SpaceshipWeapon::default(),
+        ShieldEnergy::default(),
+        ExternalVelocity::default(),
+        Health::new(SPACESHIP_HEALTH),
+    )
 }
 
-// 定义一个名为 `spaceship_movement_controls` 的函数，它接受一个可变的 `Query` 类型参数，一个 `ButtonInput<KeyCode>` 资源引用参数和一个 `Time` 资源引用参数
+// 定义一个名为 `spaceship_movement_controls` 的函数，它接受一个可变的 `Query` 类型参数和一个 `Time` 资源引用参数
+// 这个函数根据每艘飞船的 `PlayerInput` 更新飞船的方向和速度
 fn spaceship_movement_controls(
-    mut query: Query<(&mut Transform, &mut Velocity), With<Spaceship>>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut query: Query<(&mut Transform, &mut Velocity, &PlayerInput), With<Spaceship>>,
+    settings: Res<SpaceshipSettings>,
     time: Res<Time>,
 ) {
-    // 从查询中获取单个飞船的变换和速度
-    // 如果没有找到飞船或者发生其他错误，则直接返回并不执行后续代码
-    let Ok((mut transform, mut velocity)) = query.get_single_mut() else {
-        return;
-    };
-    // 初始化旋转、翻滚和移动的变量
-    let mut rotation = 0.0;
-    let mut roll = 0.0;
-    let mut movement = 0.0;
-
-    // 如果按下了 "D" 键，那么飞船将向右旋转
-    if keyboard_input.pressed(KeyCode::KeyD) {
-        rotation = -SPACESHIP_ROTATION_SPEED * time.delta_seconds();
-    }
-    // 如果按下了 "A" 键，那么飞船将向左旋转
-    else if keyboard_input.pressed(KeyCode::KeyA) {
-        rotation = SPACESHIP_ROTATION_SPEED * time.delta_seconds();
-    }
-
-    // 如果按下了 "S" 键，那么飞船将向后移动
-    if keyboard_input.pressed(KeyCode::KeyS) {
-        movement = -SPACESHIP_SPEED;
-    }
-    // 如果按下了 "W" 键，那么飞船将向前移动
-    else if keyboard_input.pressed(KeyCode::KeyW) {
-        movement = SPACESHIP_SPEED;
-    }
-
-    // 如果按下了左 Shift 键，那么飞船将向左翻滚
-    if keyboard_input.pressed(KeyCode::ShiftLeft) {
-        roll = -SPACESHIP_ROLL_SPEED * time.delta_seconds();
-    }
-    // 如果按下了左 Control 键，那么飞船将向右翻滚
-    else if keyboard_input.pressed(KeyCode::ControlLeft) {
-        roll = SPACESHIP_ROLL_SPEED * time.delta_seconds();
-    }
+    for (mut transform, mut velocity, input) in query.iter_mut() {
+        // 根据输入计算旋转、翻滚和移动的值
+        let rotation = input.turn * settings.rotation_speed * time.delta_seconds();
+        let roll = input.roll * SPACESHIP_ROLL_SPEED * time.delta_seconds();
+        let movement = input.thrust * settings.speed;
 
-    // 根据旋转值，使飞船绕 Y 轴旋转
-    transform.rotate_y(rotation);
+        // 根据旋转值，使飞船绕 Y 轴旋转
+        transform.rotate_y(rotation);
 
-    // 根据翻滚值，使飞船绕本地 Z 轴旋转
-    transform.rotate_local_z(roll);
+        // 根据翻滚值，使飞船绕本地 Z 轴旋转
+        transform.rotate_local_z(roll);
 
-    // 根据新的方向，更新飞船的速度
-    velocity.value = -transform.forward() * movement;
+        // 根据新的方向，更新飞船的速度
+        velocity.value = -transform.forward() * movement;
+    }
 }
 
-// 定义一个名为 `spaceship_weapon_controls` 的函数，它接受一个可变的 `Commands` 类型参数，一个 `Query` 类型参数，一个 `ButtonInput<KeyCode>` 资源引用参数和一个 `SceneAssets` 资源引用参数
+// 定义一个名为 `spaceship_weapon_controls` 的函数，它接受一个可变的 `Commands` 类型参数，一个 `Query` 类型参数，一个 `SceneAssets` 资源引用参数和一个 `Time` 资源引用参数
 fn spaceship_weapon_controls(
     mut commands: Commands,
-    query: Query<&Transform, With<Spaceship>>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut query: Query<(&Transform, &PlayerId, &PlayerInput, &mut SpaceshipWeapon), With<Spaceship>>,
     scene_assets: Res<SceneAssets>,
+    settings: Res<SpaceshipSettings>,
+    time: Res<Time>,
 ) {
-    // 从查询中获取飞船的变换
-    let Ok(transform) = query.get_single() else {
-        return;
-    };
-    // 如果按下了空格键
-    if keyboard_input.pressed(KeyCode::Space) {
-        // 使用 `commands` 的 `spawn` 方法来创建一个新的实体。
-        // 这个新实体拥有 `MovingObjectBundle` 组件和 `SpaceshipMissile` 组件。
-        // `MovingObjectBundle` 组件包含 `velocity`、`acceleration`、`collider` 和 `model`。
-        // `velocity` 被设置为飞船前方的反方向乘以导弹的速度，表示导弹的初始速度。
-        // `acceleration` 被设置为零向量，表示导弹的初始加速度是零。
-        // `collider` 被设置为导弹的半径，用于碰撞检测。
-        // `model` 被设置为 `SceneBundle`，其中 `scene` 是从 `scene_assets` 中克隆的导弹模型，`transform` 是导弹的初始位置，初始位置是在飞船前方一定距离的位置。
-        commands.spawn((
-            MovingObjectBundle {
-                velocity: Velocity::new(-transform.forward() * MISSILE_SPEED),
-                acceleration: Acceleration::new(Vec3::ZERO),
-                collider: Collider::new(MISSILE_RADIUS),
-                model: SceneBundle {
-                    scene: scene_assets.missiles.clone(),
-                    transform: Transform::from_translation(
-                        transform.translation + -transform.forward() * MISSILE_FORWARD_SPAWN_SCALAR,
-                    ),
-                    ..default()
+    for (transform, &player, input, mut weapon) in query.iter_mut() {
+        weapon.rapid_fire_seconds = (weapon.rapid_fire_seconds - time.delta_seconds()).max(0.0);
+        weapon.homing_seconds = (weapon.homing_seconds - time.delta_seconds()).max(0.0);
+        weapon.cooldown.tick(time.delta());
+        // 如果玩家没有开火，或者武器还在冷却，那么跳过这艘飞船
+        if !input.fire || !weapon.cooldown.finished() {
+            continue;
+        }
+        let interval = weapon.fire_interval();
+        weapon
+            .cooldown
+            .set_duration(std::time::Duration::from_secs_f32(interval));
+        weapon.cooldown.reset();
+
+        // 根据武器等级决定发射的导弹：一级发射一枚，二级发射两枚平行的导弹，三级发射三枚散射的导弹
+        let forward = -transform.forward();
+        let right = transform.right();
+        let missiles: Vec<(Vec3, Vec3)> = match weapon.level {
+            1 => vec![(Vec3::ZERO, *forward)],
+            2 => vec![
+                (right * TWIN_MISSILE_OFFSET, *forward),
+                (-right * TWIN_MISSILE_OFFSET, *forward),
+            ],
+            _ => [-SPREAD_MISSILE_ANGLE, 0.0, SPREAD_MISSILE_ANGLE]
+                .into_iter()
+                .map(|angle| (Vec3::ZERO, Quat::from_rotation_y(angle) * *forward))
+                .collect(),
+        };
+
+        for (offset, direction) in missiles {
+            // 使用 `commands` 的 `spawn` 方法来创建一个新的实体。
+            // 这个新实体拥有 `MovingObjectBundle` 组件、`SpaceshipMissile` 组件和记录发射者的 `MissileOwner` 组件。
+            // `velocity` 被设置为导弹的方向乘以导弹的速度，表示导弹的初始速度。
+            // `acceleration` 被设置为零向量，表示导弹的初始加速度是零。
+            // `collider` 被设置为沿导弹飞行方向延伸的胶囊体，用于碰撞检测。
+            // `model` 被设置为 `SceneBundle`，其中 `scene` 是从 `scene_assets` 中克隆的导弹模型，`transform` 是导弹的初始位置，初始位置是在飞船前方一定距离的位置。
+            let mut missile = commands.spawn((
+                MovingObjectBundle {
+                    velocity: Velocity::new(direction * settings.missile_speed),
+                    acceleration: Acceleration::new(Vec3::ZERO),
+                    collider: Collider::capsule(MISSILE_RADIUS, MISSILE_HALF_LENGTH),
+                    model: SceneBundle {
+                        scene: scene_assets.missiles.clone(),
+                        // 导弹朝向它的飞行方向，使胶囊体碰撞器沿飞行方向延伸
+                        transform: Transform::from_translation(
+                            transform.translation
+                                + offset
+                                + direction * MISSILE_FORWARD_SPAWN_SCALAR,
+                        )
+                        .looking_to(-direction, Vec3::Y),
+                        ..default()
+                    },
                 },
-            },
-            SpaceshipMissile,
-        ));
+                SpaceshipMissile,
+                MissileOwner(player),
+            ));
+            // 追踪导弹状态下，导弹额外拥有 `HomingMissile` 组件
+            if weapon.homing_seconds > 0.0 {
+                missile.insert(HomingMissile::default());
+            }
+        }
     }
 }
 
-// 定义一个名为 `spaceship_shield_controls` 的函数，它接受三个参数：一个可变的 `Commands` 类型参数、一个 `Query` 类型参数和一个 `ButtonInput<KeyCode>` 资源引用
-// 这个函数用于处理飞船的护盾控制
+// `ShieldControlQuery` 查询飞船的输入、护盾能量、是否开启了护盾以及是否位于星云中
+type ShieldControlQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static PlayerInput,
+        &'static mut ShieldEnergy,
+        Has<SpaceshipShield>,
+        Has<InNebula>,
+    ),
+    With<Spaceship>,
+>;
+
+// 定义一个名为 `spaceship_shield_controls` 的函数，它接受三个参数：一个可变的 `Commands` 类型参数、一个 `Query` 类型参数和一个 `Time` 资源引用参数
+// 这个函数用于处理飞船的护盾控制，护盾开启时持续消耗能量，能量耗尽后或者飞船进入星云时护盾自动关闭
 fn spaceship_shield_controls(
     mut commands: Commands,
-    query: Query<Entity, With<Spaceship>>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut query: ShieldControlQuery,
+    time: Res<Time>,
 ) {
-    // 从查询结果中获取单个飞船实体，如果获取失败（例如没有飞船实体），那么直接返回，不进行后续操作
-    let spaceship = match query.get_single() {
-        Ok(spaceship) => spaceship,
-        Err(_) => return,
-    };
-
-    // 如果用户按下了 Tab 键，那么给飞船实体添加 `SpaceshipShield` 组件
-    if keyboard_input.pressed(KeyCode::Tab) {
-        commands.entity(spaceship).insert(SpaceshipShield);
+    for (spaceship, input, mut energy, shielded, in_nebula) in query.iter_mut() {
+        // 如果玩家按下了护盾键、还有能量并且不在星云中，那么给飞船实体添加 `SpaceshipShield` 组件
+        if input.shield && energy.current > 0.0 && !in_nebula {
+            energy.current = (energy.current - time.delta_seconds()).max(0.0);
+            if !shielded {
+                commands.entity(spaceship).insert(SpaceshipShield);
+            }
+        } else if shielded {
+            commands.entity(spaceship).remove::<SpaceshipShield>();
+        }
     }
 }
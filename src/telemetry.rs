@@ -0,0 +1,359 @@
+use std::{
+    fmt::Write as _,
+    fs::File,
+    io::{BufWriter, Write},
+    path::PathBuf,
+};
+
+use bevy::prelude::*;
+
+use crate::{
+    asteroids::Asteroid,
+    collision_detection::Collider,
+    enemy::Enemy,
+    movement::{Acceleration, Velocity},
+    spaceship::{Spaceship, SpaceshipMissile},
+};
+
+// `TelemetryTarget` 表示要记录的实体种类，通过实体上的标记组件筛选
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TelemetryTarget {
+    Asteroid,
+    Spaceship,
+    Missile,
+    Enemy,
+}
+
+impl TelemetryTarget {
+    const ALL: [TelemetryTarget; 4] = [
+        TelemetryTarget::Asteroid,
+        TelemetryTarget::Spaceship,
+        TelemetryTarget::Missile,
+        TelemetryTarget::Enemy,
+    ];
+
+    // 返回实体种类在输出中使用的名称
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Asteroid => "asteroid",
+            Self::Spaceship => "spaceship",
+            Self::Missile => "missile",
+            Self::Enemy => "enemy",
+        }
+    }
+}
+
+// `TelemetryField` 表示要记录的组件数据
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TelemetryField {
+    Translation,
+    Rotation,
+    Velocity,
+    Acceleration,
+    Radius,
+}
+
+impl TelemetryField {
+    // 返回字段在输出中使用的名称
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Translation => "translation",
+            Self::Rotation => "rotation",
+            Self::Velocity => "velocity",
+            Self::Acceleration => "acceleration",
+            Self::Radius => "radius",
+        }
+    }
+
+    // 返回字段在 CSV 中展开后的列名
+    fn columns(self) -> Vec<String> {
+        let components: &[&str] = match self {
+            Self::Rotation => &["x", "y", "z", "w"],
+            Self::Radius => &[""],
+            _ => &["x", "y", "z"],
+        };
+        components
+            .iter()
+            .map(|component| {
+                if component.is_empty() {
+                    self.name().to_string()
+                } else {
+                    format!("{}_{}", self.name(), component)
+                }
+            })
+            .collect()
+    }
+}
+
+// `TelemetryFormat` 表示遥测数据的输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TelemetryFormat {
+    // 使用 `info!` 输出到日志
+    Log,
+    // 每个样本一行的 CSV 文件
+    Csv,
+    // 每个样本一个 JSON 对象的 JSON Lines 文件
+    JsonLines,
+}
+
+// `TelemetryConfig` 资源决定记录哪些实体的哪些数据、多久记录一次以及输出到哪里
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct TelemetryConfig {
+    pub targets: Vec<TelemetryTarget>,
+    pub fields: Vec<TelemetryField>,
+    // 每隔多少帧记录一次，1 表示每一帧都记录
+    pub sample_every: u32,
+    pub format: TelemetryFormat,
+    // 输出文件的路径，只用于 `Csv` 和 `JsonLines` 格式
+    pub path: Option<PathBuf>,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            targets: TelemetryTarget::ALL.to_vec(),
+            fields: vec![TelemetryField::Translation, TelemetryField::Velocity],
+            sample_every: 1,
+            format: TelemetryFormat::Log,
+            path: None,
+        }
+    }
+}
+
+impl TelemetryConfig {
+    // 从命令行参数中读取遥测配置，没有 `--telemetry` 参数时返回 `None`
+    // `--telemetry <种类,...>` 选择要记录的实体种类，可以是 `all`、`asteroid`、`spaceship`、`missile`、`enemy`
+    // `--telemetry-fields <字段,...>` 选择要记录的数据，可以是 `translation`、`rotation`、`velocity`、`acceleration`、`radius`
+    // `--telemetry-every <帧数>` 设置采样间隔
+    // `--telemetry-out <路径>` 把数据写入文件，扩展名为 `.csv` 时使用 CSV 格式，否则使用 JSON Lines 格式
+    pub fn from_args(args: &[String]) -> Result<Option<Self>, String> {
+        let value = |name: &str| {
+            args.iter()
+                .position(|arg| arg == name)
+                .and_then(|index| args.get(index + 1))
+                .map(String::as_str)
+        };
+        let Some(targets) = value("--telemetry") else {
+            return Ok(None);
+        };
+
+        let mut config = Self::default();
+        if targets != "all" {
+            config.targets = parse_list(targets, &TelemetryTarget::ALL, |target| target.name())?;
+        }
+        if let Some(fields) = value("--telemetry-fields") {
+            config.fields = parse_list(
+                fields,
+                &[
+                    TelemetryField::Translation,
+                    TelemetryField::Rotation,
+                    TelemetryField::Velocity,
+                    TelemetryField::Acceleration,
+                    TelemetryField::Radius,
+                ],
+                |field| field.name(),
+            )?;
+        }
+        if let Some(sample_every) = value("--telemetry-every") {
+            config.sample_every = sample_every
+                .parse()
+                .ok()
+                .filter(|&sample_every| sample_every > 0)
+                .ok_or_else(|| {
+                    format!("`--telemetry-every` 必须是正整数，而不是 `{sample_every}`")
+                })?;
+        }
+        if let Some(path) = value("--telemetry-out") {
+            let path = PathBuf::from(path);
+            config.format = if path.extension().is_some_and(|extension| extension == "csv") {
+                TelemetryFormat::Csv
+            } else {
+                TelemetryFormat::JsonLines
+            };
+            config.path = Some(path);
+        }
+        Ok(Some(config))
+    }
+}
+
+// 这个函数解析逗号分隔的名称列表
+fn parse_list<T: Copy>(
+    list: &str,
+    options: &[T],
+    name: impl Fn(T) -> &'static str,
+) -> Result<Vec<T>, String> {
+    list.split(',')
+        .map(|item| {
+            options
+                .iter()
+                .copied()
+                .find(|&option| name(option) == item.trim())
+                .ok_or_else(|| format!("未知的遥测选项 `{item}`"))
+        })
+        .collect()
+}
+
+// `TelemetryWriter` 资源保存打开的输出文件和已经运行的帧数
+#[derive(Resource, Debug, Default)]
+struct TelemetryWriter {
+    file: Option<BufWriter<File>>,
+    tick: u64,
+}
+
+// `TelemetryPlugin` 插件按照 `TelemetryConfig` 记录实体的数据，使用它的应用需要提供 `TelemetryConfig` 资源
+pub struct TelemetryPlugin;
+
+impl Plugin for TelemetryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TelemetryWriter>()
+            .add_systems(Startup, open_telemetry_output)
+            .add_systems(Last, record_telemetry);
+    }
+}
+
+// 这个函数创建输出文件，CSV 格式会先写入表头
+fn open_telemetry_output(config: Res<TelemetryConfig>, mut writer: ResMut<TelemetryWriter>) {
+    let Some(path) = config.path.as_ref() else {
+        return;
+    };
+    let file = match File::create(path) {
+        Ok(file) => file,
+        Err(error) => {
+            error!("无法创建遥测文件 {}: {}", path.display(), error);
+            return;
+        }
+    };
+    let mut file = BufWriter::new(file);
+    if config.format == TelemetryFormat::Csv {
+        let mut header = vec![
+            "tick".to_string(),
+            "time".to_string(),
+            "entity".to_string(),
+            "kind".to_string(),
+        ];
+        header.extend(config.fields.iter().flat_map(|field| field.columns()));
+        if let Err(error) = writeln!(file, "{}", header.join(",")) {
+            error!("无法写入遥测文件: {}", error);
+        }
+    }
+    writer.file = Some(file);
+}
+
+// `TelemetryQuery` 查询所有带有 `TelemetryTarget` 对应标记组件的实体，以及可以记录的组件
+type TelemetryQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static Transform,
+        Option<&'static Velocity>,
+        Option<&'static Acceleration>,
+        Option<&'static Collider>,
+        AnyOf<(
+            &'static Asteroid,
+            &'static Spaceship,
+            &'static SpaceshipMissile,
+            &'static Enemy,
+        )>,
+    ),
+>;
+
+// 这个函数每隔 `sample_every` 帧记录一次所有符合条件的实体的数据
+fn record_telemetry(
+    config: Res<TelemetryConfig>,
+    mut writer: ResMut<TelemetryWriter>,
+    query: TelemetryQuery,
+    time: Res<Time>,
+) {
+    let tick = writer.tick;
+    writer.tick += 1;
+    if tick % config.sample_every.max(1) as u64 != 0 {
+        return;
+    }
+
+    for (entity, transform, velocity, acceleration, collider, markers) in query.iter() {
+        let kind = match markers {
+            (Some(_), ..) => TelemetryTarget::Asteroid,
+            (_, Some(_), ..) => TelemetryTarget::Spaceship,
+            (_, _, Some(_), _) => TelemetryTarget::Missile,
+            _ => TelemetryTarget::Enemy,
+        };
+        if !config.targets.contains(&kind) {
+            continue;
+        }
+        // 每个字段展开为若干个数值，组件不存在时数值为空
+        let values: Vec<(TelemetryField, Option<Vec<f32>>)> = config
+            .fields
+            .iter()
+            .map(|&field| {
+                let value = match field {
+                    TelemetryField::Translation => Some(transform.translation.to_array().to_vec()),
+                    TelemetryField::Rotation => Some(transform.rotation.to_array().to_vec()),
+                    TelemetryField::Velocity => {
+                        velocity.map(|velocity| velocity.value.to_array().to_vec())
+                    }
+                    TelemetryField::Acceleration => {
+                        acceleration.map(|acceleration| acceleration.value.to_array().to_vec())
+                    }
+                    TelemetryField::Radius => collider.map(|collider| vec![collider.radius]),
+                };
+                (field, value)
+            })
+            .collect();
+        let seconds = time.elapsed_seconds_f64();
+
+        let line = match config.format {
+            TelemetryFormat::Log | TelemetryFormat::JsonLines => {
+                let mut line = format!(
+                    "{{\"tick\":{tick},\"time\":{seconds:.4},\"entity\":\"{entity:?}\",\"kind\":\"{}\"",
+                    kind.name()
+                );
+                for (field, value) in values {
+                    let value = match value {
+                        Some(value) if value.len() == 1 => value[0].to_string(),
+                        Some(value) => format!(
+                            "[{}]",
+                            value
+                                .iter()
+                                .map(f32::to_string)
+                                .collect::<Vec<_>>()
+                                .join(",")
+                        ),
+                        None => "null".to_string(),
+                    };
+                    let _ = write!(line, ",\"{}\":{}", field.name(), value);
+                }
+                line.push('}');
+                line
+            }
+            TelemetryFormat::Csv => {
+                let mut cells = vec![
+                    tick.to_string(),
+                    format!("{seconds:.4}"),
+                    format!("{entity:?}"),
+                    kind.name().to_string(),
+                ];
+                for (field, value) in values {
+                    match value {
+                        Some(value) => cells.extend(value.iter().map(f32::to_string)),
+                        None => cells.extend(field.columns().iter().map(|_| String::new())),
+                    }
+                }
+                cells.join(",")
+            }
+        };
+
+        match writer.file.as_mut() {
+            Some(file) => {
+                if let Err(error) = writeln!(file, "{line}") {
+                    error!("无法写入遥测文件: {}", error);
+                }
+            }
+            None => info!("{}", line),
+        }
+    }
+    // 每次采样后刷新文件，使程序意外退出时已经记录的数据不会丢失
+    if let Some(file) = writer.file.as_mut() {
+        let _ = file.flush();
+    }
+}
@@ -0,0 +1,197 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, time::TimeSystem};
+
+use crate::{
+    console::{parse_arg, parse_optional_arg, ConsoleCommandsExt},
+    pickup::{PickupCollected, PickupKind},
+};
+
+// 定义子弹时间道具生效时的时间倍率
+const BULLET_TIME_SCALE: f32 = 0.4;
+
+// 定义子弹时间道具的持续时间，单位为真实时间的秒数
+const BULLET_TIME_SECONDS: f32 = 5.0;
+
+// 定义暂停时单步执行的每一帧的时长，单位为秒
+const STEP_SECONDS: f32 = 1.0 / 60.0;
+
+// 定义控制台允许设置的最大时间倍率，过大的倍率会让虚拟时间的计算溢出
+const MAX_TIME_SCALE: f32 = 10.0;
+
+// 定义暂停和继续游戏的按键
+const PAUSE_KEY: KeyCode = KeyCode::KeyP;
+
+// 定义暂停时单步执行一帧的按键
+const STEP_KEY: KeyCode = KeyCode::Period;
+
+// `TimeScale` 资源决定游戏时间相对于真实时间的速度
+// 所有读取 `Time` 的游戏逻辑（移动、生成、旋转、护盾等）都使用虚拟时间，因此都会受到它的影响
+#[derive(Resource, Reflect, Debug, Clone, PartialEq)]
+#[reflect(Resource)]
+pub struct TimeScale {
+    // 基础的时间倍率，可以通过控制台调整
+    pub base: f32,
+    // 子弹时间剩余的真实时间，单位为秒
+    pub bullet_time_seconds: f32,
+}
+
+impl Default for TimeScale {
+    fn default() -> Self {
+        Self {
+            base: 1.0,
+            bullet_time_seconds: 0.0,
+        }
+    }
+}
+
+impl TimeScale {
+    // 返回当前实际使用的时间倍率
+    pub fn effective(&self) -> f32 {
+        if self.bullet_time_seconds > 0.0 {
+            self.base * BULLET_TIME_SCALE
+        } else {
+            self.base
+        }
+    }
+}
+
+// `FrameStepper` 资源记录暂停时还需要单步执行的帧数
+#[derive(Resource, Reflect, Debug, Default)]
+#[reflect(Resource)]
+pub struct FrameStepper {
+    pub pending_steps: u32,
+}
+
+// `TimeControlPlugin` 插件控制游戏时间的速度，提供子弹时间道具的效果，以及用于调试的暂停和单步执行
+// 按 P 暂停或继续游戏，暂停时按 . 单步执行一帧
+pub struct TimeControlPlugin;
+
+impl Plugin for TimeControlPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<TimeScale>()
+            .register_type::<FrameStepper>()
+            .init_resource::<TimeScale>()
+            .init_resource::<FrameStepper>()
+            .add_event::<PickupCollected>()
+            .add_systems(First, step_paused_time.after(TimeSystem))
+            .add_systems(
+                PreUpdate,
+                (start_bullet_time, pause_controls, apply_time_scale).chain(),
+            )
+            .add_console_command(
+                "timescale",
+                "timescale <scale> - set the speed of game time",
+                timescale_command,
+            )
+            .add_console_command("pause", "pause - pause or resume the game", pause_command)
+            .add_console_command(
+                "step",
+                "step [frames] - advance a paused game frame by frame",
+                step_command,
+            );
+    }
+}
+
+// 这个函数在玩家拾取子弹时间道具时开始子弹时间
+fn start_bullet_time(
+    mut time_scale: ResMut<TimeScale>,
+    mut collected: EventReader<PickupCollected>,
+) {
+    for event in collected.read() {
+        if event.kind == PickupKind::BulletTime {
+            time_scale.bullet_time_seconds = BULLET_TIME_SECONDS;
+        }
+    }
+}
+
+// 这个函数处理暂停和单步执行的按键，没有键盘输入（例如无窗口模拟）时不做任何事
+fn pause_controls(
+    keyboard_input: Option<Res<ButtonInput<KeyCode>>>,
+    mut time: ResMut<Time<Virtual>>,
+    mut stepper: ResMut<FrameStepper>,
+) {
+    let Some(keyboard_input) = keyboard_input else {
+        return;
+    };
+    if keyboard_input.just_pressed(PAUSE_KEY) {
+        toggle_pause(&mut time);
+    }
+    if keyboard_input.just_pressed(STEP_KEY) && time.is_paused() {
+        stepper.pending_steps += 1;
+    }
+}
+
+// 这个函数根据 `TimeScale` 设置虚拟时间的速度，子弹时间按真实时间倒计时
+fn apply_time_scale(
+    mut time_scale: ResMut<TimeScale>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    real_time: Res<Time<Real>>,
+) {
+    if time_scale.bullet_time_seconds > 0.0 {
+        time_scale.bullet_time_seconds =
+            (time_scale.bullet_time_seconds - real_time.delta_seconds()).max(0.0);
+    }
+    let scale = time_scale.effective();
+    if virtual_time.relative_speed() != scale {
+        virtual_time.set_relative_speed(scale);
+    }
+}
+
+// 这个函数在暂停时单步执行一帧：让虚拟时间前进固定的时长，使这一帧的游戏逻辑像正常运行时一样更新
+fn step_paused_time(
+    mut stepper: ResMut<FrameStepper>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    mut time: ResMut<Time>,
+) {
+    if !virtual_time.is_paused() || stepper.pending_steps == 0 {
+        return;
+    }
+    stepper.pending_steps -= 1;
+    virtual_time.advance_by(Duration::from_secs_f32(STEP_SECONDS));
+    *time = virtual_time.as_generic();
+}
+
+// 这个函数暂停或继续虚拟时间
+fn toggle_pause(time: &mut Time<Virtual>) {
+    if time.is_paused() {
+        time.unpause();
+        info!("游戏继续");
+    } else {
+        time.pause();
+        info!("游戏暂停");
+    }
+}
+
+// `timescale` 控制台命令设置基础的时间倍率
+fn timescale_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let scale: f32 = parse_arg(args, 0, "scale")?;
+    // `contains` 对 NaN 返回 false，因此非有限的倍率也会被拒绝
+    if !(0.0..=MAX_TIME_SCALE).contains(&scale) {
+        return Err(format!("scale must be between 0 and {MAX_TIME_SCALE}"));
+    }
+    world.resource_mut::<TimeScale>().base = scale;
+    Ok(format!("time scale set to {scale}"))
+}
+
+// `pause` 控制台命令暂停或继续游戏
+fn pause_command(world: &mut World, _args: &[&str]) -> Result<String, String> {
+    let mut time = world.resource_mut::<Time<Virtual>>();
+    toggle_pause(&mut time);
+    Ok(if time.is_paused() {
+        "paused"
+    } else {
+        "resumed"
+    }
+    .to_string())
+}
+
+// `step` 控制台命令在暂停时单步执行若干帧
+fn step_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let frames: u32 = parse_optional_arg(args, 0, "frames", 1)?;
+    if !world.resource::<Time<Virtual>>().is_paused() {
+        return Err("the game is not paused".to_string());
+    }
+    world.resource_mut::<FrameStepper>().pending_steps += frames;
+    Ok(format!("stepping {frames} frames"))
+}
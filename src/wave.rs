@@ -0,0 +1,87 @@
+use bevy::prelude::*;
+
+use crate::{
+    console::{parse_arg, ConsoleCommandsExt},
+    schedule::InGameSet,
+};
+
+// 定义每一波持续的时间，单位为秒，初始值为 30.0
+const WAVE_DURATION_SECONDS: f32 = 30.0;
+
+// 定义每隔多少波出现一次首领
+pub const BOSS_WAVE_INTERVAL: u32 = 5;
+
+// `Wave` 资源记录当前的波数，波数从 1 开始，每隔 `WAVE_DURATION_SECONDS` 秒增加一次
+#[derive(Resource, Reflect, Debug)]
+#[reflect(Resource)]
+pub struct Wave {
+    pub number: u32,
+    timer: Timer,
+}
+
+impl Default for Wave {
+    fn default() -> Self {
+        Self {
+            number: 1,
+            timer: Timer::from_seconds(WAVE_DURATION_SECONDS, TimerMode::Repeating),
+        }
+    }
+}
+
+impl Wave {
+    // 当前波是否是首领波
+    pub fn is_boss_wave(&self) -> bool {
+        self.number % BOSS_WAVE_INTERVAL == 0
+    }
+
+    // 直接跳到指定的波数，并重新开始计时
+    pub fn set(&mut self, number: u32) {
+        self.number = number.max(1);
+        self.timer.reset();
+    }
+}
+
+// `WaveStarted` 事件在新的一波开始时发送
+#[derive(Event, Debug, Clone, Copy)]
+pub struct WaveStarted {
+    pub number: u32,
+}
+
+pub struct WavePlugin;
+
+impl Plugin for WavePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Wave>()
+            .register_type::<TimerMode>()
+            .init_resource::<Wave>()
+            .add_event::<WaveStarted>()
+            .add_systems(Update, advance_wave.in_set(InGameSet::EntityUpdates))
+            .add_console_command("wave", "wave <number> - jump to a wave", wave_command);
+    }
+}
+
+// `wave` 控制台命令直接跳到指定的波数，并发送 `WaveStarted` 事件，跳到首领波时首领会出现
+fn wave_command(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let number: u32 = parse_arg(args, 0, "number")?;
+    let mut wave = world.resource_mut::<Wave>();
+    wave.set(number);
+    let number = wave.number;
+    world.send_event(WaveStarted { number });
+    Ok(format!("wave {number} started"))
+}
+
+// 这个函数在计时结束时进入下一波
+fn advance_wave(
+    mut wave: ResMut<Wave>,
+    mut wave_started: EventWriter<WaveStarted>,
+    time: Res<Time>,
+) {
+    wave.timer.tick(time.delta());
+    if wave.timer.just_finished() {
+        wave.number += 1;
+        info!("第 {} 波开始", wave.number);
+        wave_started.send(WaveStarted {
+            number: wave.number,
+        });
+    }
+}
@@ -0,0 +1,2194 @@
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use bevy::{
+    ecs::system::CommandQueue, prelude::*, reflect::GetPath, time::TimeUpdateStrategy,
+    utils::HashMap, window::WindowResized,
+};
+use rand::Rng;
+
+use spaceship_game::{
+    asset_loader::{AudioAssets, SceneAssets},
+    asteroids::{
+        asteroid_bundle, Asteroid, AsteroidBehavior, AsteroidKind, AsteroidPlugin,
+        AsteroidSpawnConfig, AsteroidVariants, SpawnPattern,
+    },
+    audio::{music_intensity, AudioVolume, EngineSound, GameAudioPlugin},
+    boss::{BossHealthBar, BossHealthBarRoot, BossPart, BossPlugin},
+    camera::{CameraMode, CameraPlugin, MainCamera, PlayerCamera},
+    collider_shape::{shapes_intersect, ColliderShape, ShapeInstance},
+    collision_detection::{
+        swept_distance, Collider, CollisionDetectionPlugin, DestroyedKind, EntityDestroyed,
+    },
+    console::{
+        execute_command, ConsoleCommands, ConsoleCommandsPlugin, ConsolePlugin, ConsoleState,
+    },
+    despawn::{DespawnPlugin, DESPAWN_DISTANCE},
+    difficulty::{Difficulty, DifficultyPlugin, DifficultyTuning, PlayerPerformance},
+    enemy::{arrive, flee, seek, Enemy, EnemyBehavior, EnemyPlugin, EnemyProjectile, EnemyWeapon},
+    hazards::{spawn_hazard, Hazard, HazardPlugin, HazardRadius, HazardVisual, InNebula},
+    health::Health,
+    homing::{HomingMissile, HomingPlugin},
+    movement::{Acceleration, ExternalVelocity, MovementPlugin, MovingObjectBundle, Velocity},
+    network::{
+        decode, encode, ClientMessage, ClientSocket, ClientState, ConnectedClients,
+        LocalController, NetEntity, NetKind, NetworkClientPlugin, NetworkServerPlugin, Replicated,
+        ReplicatedBoss, ServerMessage, ServerSocket, Snapshot,
+    },
+    particles::{Particle, ParticlePlugin},
+    pickup::{spawn_pickup, Pickup, PickupKind, PickupPlugin},
+    player::{KeyBindings, PlayerConfig, PlayerId, PlayerInput},
+    rng::GameRng,
+    schedule::SchedulePlugin,
+    score::{Score, ScorePlugin},
+    settings::{OptionsMenu, Settings, SettingsFile, SettingsPlugin},
+    snapshot::{load_snapshot, save_snapshot, SnapshotPlugin},
+    spaceship::{
+        spaceship_bundle, GodMode, Lives, MissileOwner, ShieldEnergy, Spaceship, SpaceshipMissile,
+        SpaceshipPlugin, SpaceshipSettings, SpaceshipShield, SpaceshipWeapon,
+    },
+    telemetry::{TelemetryConfig, TelemetryPlugin},
+    time_control::{TimeControlPlugin, TimeScale},
+    wave::{Wave, WavePlugin, WaveStarted, BOSS_WAVE_INTERVAL},
+};
+
+// 测试中每一帧的固定时长，单位为秒
+const TICK_SECONDS: f32 = 0.1;
+
+// 创建一个只包含游戏逻辑插件的应用，每一帧前进固定的时长
+fn test_app() -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugins((TransformPlugin, HierarchyPlugin))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+            TICK_SECONDS,
+        )))
+        .init_resource::<SceneAssets>()
+        .init_resource::<PlayerConfig>()
+        .add_plugins((
+            MovementPlugin,
+            CollisionDetectionPlugin,
+            DespawnPlugin,
+            SchedulePlugin,
+        ));
+    app
+}
+
+// 生成一个运动物体
+fn spawn_moving_object(
+    app: &mut App,
+    translation: Vec3,
+    velocity: Vec3,
+    acceleration: Vec3,
+    radius: f32,
+) -> Entity {
+    app.world
+        .spawn(MovingObjectBundle {
+            velocity: Velocity::new(velocity),
+            acceleration: Acceleration::new(acceleration),
+            collider: Collider::new(radius),
+            model: SceneBundle {
+                transform: Transform::from_translation(translation),
+                ..default()
+            },
+        })
+        .id()
+}
+
+// 生成一个静止的带碰撞器的物体
+fn spawn_collider(app: &mut App, translation: Vec3, radius: f32) -> Entity {
+    spawn_moving_object(app, translation, Vec3::ZERO, Vec3::ZERO, radius)
+}
+
+// 连续运行若干帧
+fn run_ticks(app: &mut App, ticks: usize) {
+    for _ in 0..ticks {
+        app.update();
+    }
+}
+
+// 创建一个实体被摧毁的事件
+fn destroyed_event(kind: DestroyedKind, translation: Vec3, radius: f32) -> EntityDestroyed {
+    EntityDestroyed {
+        entity: Entity::PLACEHOLDER,
+        kind,
+        translation,
+        radius,
+        by_player: None,
+    }
+}
+
+// 生成玩家一的飞船
+fn spawn_spaceship(app: &mut App, translation: Vec3) -> Entity {
+    app.world
+        .spawn(spaceship_bundle(
+            Handle::default(),
+            PlayerId(0),
+            translation,
+        ))
+        .id()
+}
+
+#[test]
+fn velocity_moves_entity_each_tick() {
+    let mut app = test_app();
+    let entity = spawn_moving_object(&mut app, Vec3::ZERO, Vec3::X * 10.0, Vec3::ZERO, 1.0);
+
+    run_ticks(&mut app, 10);
+
+    let translation = app.world.get::<Transform>(entity).unwrap().translation;
+    let elapsed = app.world.resource::<Time>().elapsed_seconds();
+    assert!((translation.x - 10.0 * elapsed).abs() < 1e-3);
+    assert_eq!(translation.y, 0.0);
+    assert_eq!(translation.z, 0.0);
+}
+
+#[test]
+fn acceleration_changes_velocity() {
+    let mut app = test_app();
+    let entity = spawn_moving_object(&mut app, Vec3::ZERO, Vec3::ZERO, Vec3::Z * 2.0, 1.0);
+
+    run_ticks(&mut app, 5);
+
+    let velocity = app.world.get::<Velocity>(entity).unwrap().value;
+    let elapsed = app.world.resource::<Time>().elapsed_seconds();
+    assert!((velocity.z - 2.0 * elapsed).abs() < 1e-3);
+    // 速度先于位置更新，因此物体已经开始沿 Z 轴移动
+    assert!(app.world.get::<Transform>(entity).unwrap().translation.z > 0.0);
+}
+
+#[test]
+fn overlapping_colliders_report_each_other() {
+    let mut app = test_app();
+    let a = spawn_collider(&mut app, Vec3::new(-1.0, 0.0, 0.0), 1.5);
+    let b = spawn_collider(&mut app, Vec3::new(1.0, 0.0, 0.0), 1.5);
+    let far = spawn_collider(&mut app, Vec3::new(50.0, 0.0, 0.0), 1.5);
+
+    run_ticks(&mut app, 2);
+
+    assert_eq!(
+        app.world.get::<Collider>(a).unwrap().colliding_entities,
+        vec![b]
+    );
+    assert_eq!(
+        app.world.get::<Collider>(b).unwrap().colliding_entities,
+        vec![a]
+    );
+    assert!(app
+        .world
+        .get::<Collider>(far)
+        .unwrap()
+        .colliding_entities
+        .is_empty());
+}
+
+#[test]
+fn missile_destroys_asteroid() {
+    let mut app = test_app();
+    let asteroid = spawn_collider(&mut app, Vec3::ZERO, 2.5);
+    app.world.entity_mut(asteroid).insert(Asteroid);
+    let missile = spawn_collider(&mut app, Vec3::new(3.0, 0.0, 0.0), 1.0);
+    app.world.entity_mut(missile).insert(SpaceshipMissile);
+
+    run_ticks(&mut app, 3);
+
+    assert!(app.world.get_entity(asteroid).is_none());
+    assert!(app.world.get_entity(missile).is_some());
+}
+
+#[test]
+fn missile_damage_to_health_is_scaled_by_difficulty() {
+    // 在指定的难度下用一发导弹击中生命值为 1.2 的小行星，返回小行星剩余的生命值
+    let hit = |difficulty: Difficulty| {
+        let mut app = test_app();
+        app.insert_resource(DifficultyTuning::for_level(difficulty.level().unwrap()));
+        let asteroid = spawn_collider(&mut app, Vec3::ZERO, 2.5);
+        app.world
+            .entity_mut(asteroid)
+            .insert((Asteroid, Health::new(1.2)));
+        let missile = spawn_collider(&mut app, Vec3::new(3.0, 0.0, 0.0), 1.0);
+        app.world
+            .entity_mut(missile)
+            .insert((SpaceshipMissile, MissileOwner(PlayerId(0))));
+        run_ticks(&mut app, 3);
+        app.world
+            .get::<Health>(asteroid)
+            .map(|health| health.current)
+    };
+
+    let normal = hit(Difficulty::Normal).expect("普通难度下一发导弹不足以摧毁小行星");
+    assert!((normal - 0.2).abs() < 1e-5);
+    assert_eq!(hit(Difficulty::Easy), None);
+}
+
+#[test]
+fn asteroids_do_not_destroy_each_other() {
+    let mut app = test_app();
+    let a = spawn_collider(&mut app, Vec3::ZERO, 2.5);
+    let b = spawn_collider(&mut app, Vec3::new(1.0, 0.0, 0.0), 2.5);
+    app.world.entity_mut(a).insert(Asteroid);
+    app.world.entity_mut(b).insert(Asteroid);
+
+    run_ticks(&mut app, 3);
+
+    assert!(app.world.get_entity(a).is_some());
+    assert!(app.world.get_entity(b).is_some());
+}
+
+#[test]
+fn network_messages_round_trip_through_encoding() {
+    let input = ClientMessage::Input(PlayerInput {
+        thrust: 1.0,
+        turn: -0.5,
+        roll: 0.0,
+        fire: true,
+        shield: false,
+    });
+    for message in [ClientMessage::Hello, input, ClientMessage::Disconnect] {
+        assert_eq!(decode::<ClientMessage>(&encode(&message)), Some(message));
+    }
+
+    let snapshot = ServerMessage::Snapshot(Snapshot {
+        tick: 42,
+        entities: vec![NetEntity {
+            id: 7,
+            kind: NetKind::Spaceship { player: 1 },
+            translation: [1.0, 0.0, -2.0],
+            rotation: Quat::from_rotation_y(0.5).to_array(),
+            scale: [1.0; 3],
+            velocity: [0.0, 0.0, 3.0],
+        }],
+    });
+    for message in [ServerMessage::Welcome { player: 1 }, snapshot] {
+        assert_eq!(decode::<ServerMessage>(&encode(&message)), Some(message));
+    }
+    // 不合法的数据解码失败，而不是导致崩溃
+    assert_eq!(decode::<ServerMessage>(&[0xff; 3]), None);
+}
+
+// 创建一个在回环地址上监听的服务器，以及一个连接到它的客户端
+fn loopback_server_and_client() -> (App, App) {
+    let server_socket = ServerSocket::bind("127.0.0.1:0").unwrap();
+    let server_addr = server_socket.local_addr().unwrap();
+    let mut server = test_app();
+    server
+        .insert_resource(server_socket)
+        .add_plugins(NetworkServerPlugin);
+    let mut client = test_app();
+    client
+        .insert_resource(ClientSocket::connect(server_addr).unwrap())
+        .add_plugins(NetworkClientPlugin);
+    (server, client)
+}
+
+// 交替运行客户端和服务器，直到消息在两者之间往返
+fn exchange(client: &mut App, server: &mut App) {
+    for _ in 0..50 {
+        client.update();
+        server.update();
+        std::thread::sleep(Duration::from_millis(2));
+    }
+}
+
+#[test]
+fn client_joins_loopback_server_and_replicates_state() {
+    let (mut server, mut client) = loopback_server_and_client();
+    exchange(&mut client, &mut server);
+
+    assert_eq!(server.world.resource::<ConnectedClients>().len(), 1);
+    assert_eq!(
+        client.world.resource::<ClientState>().player,
+        Some(PlayerId(0))
+    );
+    let replicated_ships = client
+        .world
+        .query_filtered::<&PlayerId, (With<Replicated>, With<Spaceship>)>()
+        .iter(&client.world)
+        .copied()
+        .collect::<Vec<_>>();
+    assert_eq!(replicated_ships, vec![PlayerId(0)]);
+
+    // 客户端的输入被写入服务器上对应玩家的飞船
+    client
+        .world
+        .query_filtered::<&mut PlayerInput, With<LocalController>>()
+        .single_mut(&mut client.world)
+        .fire = true;
+    exchange(&mut client, &mut server);
+    let input = server
+        .world
+        .query_filtered::<&PlayerInput, With<Spaceship>>()
+        .single(&server.world);
+    assert!(input.fire);
+}
+
+#[test]
+fn destroyed_network_spaceship_respawns_on_loopback_server() {
+    let (mut server, mut client) = loopback_server_and_client();
+    // 服务器没有本地玩家，只有网络玩家的飞船
+    server
+        .init_resource::<ButtonInput<KeyCode>>()
+        .insert_resource(PlayerConfig {
+            player_count: 0,
+            friendly_fire: true,
+        })
+        .add_plugins(SpaceshipPlugin);
+    exchange(&mut client, &mut server);
+    let mut ships = server.world.query_filtered::<Entity, With<Spaceship>>();
+    let ship = ships.single(&server.world);
+
+    // 摧毁网络玩家的飞船，重新生成的延迟过后它会消耗一艘备用飞船回来
+    let lives = server.world.resource::<Lives>().remaining;
+    server.world.entity_mut(ship).despawn_recursive();
+    exchange(&mut client, &mut server);
+
+    let respawned = ships.single(&server.world);
+    assert_ne!(respawned, ship);
+    assert_eq!(server.world.resource::<Lives>().remaining, lives - 1);
+    let replicated_ships = client
+        .world
+        .query_filtered::<&PlayerId, (With<Replicated>, With<Spaceship>)>()
+        .iter(&client.world)
+        .copied()
+        .collect::<Vec<_>>();
+    assert_eq!(replicated_ships, vec![PlayerId(0)]);
+}
+
+#[test]
+fn client_replicates_asteroid_kind_and_scale() {
+    let (mut server, mut client) = loopback_server_and_client();
+    let variant = AsteroidVariants::default()
+        .get(AsteroidKind::Ice)
+        .unwrap()
+        .clone();
+    let asteroid = server
+        .world
+        .spawn(asteroid_bundle(
+            Handle::default(),
+            &variant,
+            Vec3::new(10.0, 0.0, 0.0),
+            Vec3::ZERO,
+            Vec3::ZERO,
+            &mut rand::thread_rng(),
+        ))
+        .id();
+    exchange(&mut client, &mut server);
+
+    let scale = server.world.get::<Transform>(asteroid).unwrap().scale;
+    let (&kind, transform) = client
+        .world
+        .query_filtered::<(&AsteroidKind, &Transform), (With<Replicated>, With<Asteroid>)>()
+        .single(&client.world);
+    assert_eq!(kind, AsteroidKind::Ice);
+    assert_eq!(transform.scale, scale);
+}
+
+#[test]
+fn client_replicates_boss_parts_and_health() {
+    let (mut server, mut client) = loopback_server_and_client();
+    server.add_plugins((WavePlugin, BossPlugin));
+    exchange(&mut client, &mut server);
+    server.world.resource_mut::<Wave>().set(BOSS_WAVE_INTERVAL);
+    server.world.send_event(WaveStarted {
+        number: BOSS_WAVE_INTERVAL,
+    });
+    exchange(&mut client, &mut server);
+
+    let parts = client
+        .world
+        .query_filtered::<&BossPart, With<Replicated>>()
+        .iter(&client.world)
+        .count();
+    assert_eq!(parts, 5);
+    let boss = *client
+        .world
+        .query::<&ReplicatedBoss>()
+        .single(&client.world);
+    assert!(boss.max_health > 0.0);
+    assert_eq!(boss.health, boss.max_health);
+    // 客户端为同步而来的首领显示血条
+    assert_eq!(
+        client
+            .world
+            .query_filtered::<(), With<BossHealthBar>>()
+            .iter(&client.world)
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn client_replicates_pickups_with_their_visuals() {
+    let (mut server, mut client) = loopback_server_and_client();
+    client
+        .add_plugins(AssetPlugin::default())
+        .init_asset::<Mesh>()
+        .init_asset::<StandardMaterial>();
+    let mut queue = CommandQueue::default();
+    spawn_pickup(
+        &mut Commands::new(&mut queue, &server.world),
+        &mut rand::thread_rng(),
+        PickupKind::ExtraLife,
+        Vec3::new(5.0, 0.0, 5.0),
+    );
+    queue.apply(&mut server.world);
+    exchange(&mut client, &mut server);
+
+    let (pickup, children) = client
+        .world
+        .query_filtered::<(&Pickup, &Children), With<Replicated>>()
+        .single(&client.world);
+    assert_eq!(pickup.kind, PickupKind::ExtraLife);
+    assert_eq!(children.len(), 1);
+}
+
+#[test]
+fn client_replicates_hazards_with_their_radius() {
+    let (mut server, mut client) = loopback_server_and_client();
+    client
+        .add_plugins(AssetPlugin::default())
+        .init_asset::<Mesh>()
+        .init_asset::<StandardMaterial>();
+    let mut queue = CommandQueue::default();
+    spawn_hazard(
+        &mut Commands::new(&mut queue, &server.world),
+        Hazard::BlackHole,
+        Vec3::new(10.0, 0.0, 0.0),
+    );
+    queue.apply(&mut server.world);
+    exchange(&mut client, &mut server);
+
+    let (&hazard, &radius, transform) = client
+        .world
+        .query_filtered::<(&Hazard, &HazardRadius, &Transform), With<Replicated>>()
+        .single(&client.world);
+    assert_eq!(hazard, Hazard::BlackHole);
+    assert_eq!(radius, HazardRadius(Hazard::BlackHole.radius()));
+    assert_eq!(transform.translation, Vec3::new(10.0, 0.0, 0.0));
+    // 客户端为同步而来的危害显示表示影响范围的圆盘
+    assert_eq!(
+        client
+            .world
+            .query_filtered::<(), With<HazardVisual>>()
+            .iter(&client.world)
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn entities_past_despawn_distance_are_removed() {
+    let mut app = test_app();
+    let near = spawn_collider(&mut app, Vec3::new(50.0, 0.0, 0.0), 1.0);
+    let far = spawn_collider(&mut app, Vec3::new(150.0, 0.0, 0.0), 1.0);
+    // 这个物体一开始在边界内，之后会飞出边界
+    let leaving = spawn_moving_object(
+        &mut app,
+        Vec3::new(0.0, 0.0, 95.0),
+        Vec3::Z * 100.0,
+        Vec3::ZERO,
+        1.0,
+    );
+
+    run_ticks(&mut app, 3);
+
+    assert!(app.world.get_entity(near).is_some());
+    assert!(app.world.get_entity(far).is_none());
+    assert!(app.world.get_entity(leaving).is_none());
+}
+
+#[test]
+fn boss_health_bar_is_not_despawned_after_layout() {
+    let mut app = test_app();
+    app.add_plugins((WavePlugin, BossPlugin));
+    run_ticks(&mut app, 1);
+    app.world.resource_mut::<Wave>().set(BOSS_WAVE_INTERVAL);
+    app.world.send_event(WaveStarted {
+        number: BOSS_WAVE_INTERVAL,
+    });
+    run_ticks(&mut app, 1);
+
+    // 界面布局会把节点放到像素坐标上，远远超出销毁距离
+    let root = app
+        .world
+        .query_filtered::<Entity, With<BossHealthBarRoot>>()
+        .single(&app.world);
+    app.world
+        .entity_mut(root)
+        .insert(Transform::from_xyz(640.0, 22.0, 0.0));
+
+    run_ticks(&mut app, 3);
+
+    assert!(app.world.get_entity(root).is_some());
+    assert_eq!(
+        app.world
+            .query_filtered::<(), With<BossHealthBar>>()
+            .iter(&app.world)
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn asteroid_destroys_spaceship() {
+    let mut app = test_app();
+    let spaceship = spawn_collider(&mut app, Vec3::ZERO, 5.0);
+    app.world
+        .entity_mut(spaceship)
+        .insert((Spaceship, PlayerId(0)));
+    let asteroid = spawn_collider(&mut app, Vec3::new(6.0, 0.0, 0.0), 2.5);
+    app.world.entity_mut(asteroid).insert(Asteroid);
+
+    run_ticks(&mut app, 3);
+
+    assert!(app.world.get_entity(spaceship).is_none());
+}
+
+#[test]
+fn difficulty_presets_scale_damage_taken_by_spaceships() {
+    // 在指定的难度下用一颗敌方子弹击中飞船，返回飞船是否还存在以及子弹是否还存在
+    let hit = |difficulty: Difficulty| {
+        let mut app = test_app();
+        app.insert_resource(DifficultyTuning::for_level(difficulty.level().unwrap()));
+        let ship = app
+            .world
+            .spawn(spaceship_bundle(Handle::default(), PlayerId(0), Vec3::ZERO))
+            .id();
+        let projectile = spawn_collider(&mut app, Vec3::new(3.0, 0.0, 0.0), 1.0);
+        app.world.entity_mut(projectile).insert(EnemyProjectile);
+        run_ticks(&mut app, 3);
+        (
+            app.world.get_entity(ship).is_some(),
+            app.world.get_entity(projectile).is_some(),
+        )
+    };
+
+    assert_eq!(hit(Difficulty::Normal), (false, true));
+    // 简单难度下飞船承受住了一次击中，子弹被吸收
+    assert_eq!(hit(Difficulty::Easy), (true, false));
+}
+
+#[test]
+fn difficulty_presets_scale_damage_dealt_to_enemies() {
+    // 在指定的难度下用一发导弹击中敌方飞船，返回敌方飞船是否还存在
+    let hit = |difficulty: Difficulty| {
+        let mut app = test_app();
+        app.insert_resource(DifficultyTuning::for_level(difficulty.level().unwrap()))
+            .add_plugins(EnemyPlugin);
+        let enemy = spawn_collider(&mut app, Vec3::ZERO, 4.0);
+        app.world
+            .entity_mut(enemy)
+            .insert((Enemy, Health::new(1.0)));
+        let missile = spawn_collider(&mut app, Vec3::new(4.5, 0.0, 0.0), 1.0);
+        app.world
+            .entity_mut(missile)
+            .insert((SpaceshipMissile, MissileOwner(PlayerId(0))));
+        run_ticks(&mut app, 3);
+        app.world.get_entity(enemy).is_some()
+    };
+
+    assert!(!hit(Difficulty::Normal));
+    assert!(hit(Difficulty::Hard));
+}
+
+// 生成一艘带有指定行为的敌方飞船
+fn spawn_enemy(app: &mut App, translation: Vec3, behavior: EnemyBehavior) -> Entity {
+    let enemy = spawn_collider(app, translation, 4.0);
+    app.world
+        .entity_mut(enemy)
+        .insert((Enemy, behavior, EnemyWeapon::default(), Health::new(1.0)));
+    enemy
+}
+
+#[test]
+fn seek_steers_towards_target_at_max_speed() {
+    let steering = seek(Vec3::ZERO, Vec3::ZERO, Vec3::new(10.0, 0.0, 0.0), 15.0);
+    assert_eq!(steering, Vec3::new(15.0, 0.0, 0.0));
+    // 已经在移动时，转向力抵消当前的速度
+    let steering = seek(Vec3::ZERO, Vec3::Z * 5.0, Vec3::new(10.0, 0.0, 0.0), 15.0);
+    assert_eq!(steering, Vec3::new(15.0, 0.0, -5.0));
+}
+
+#[test]
+fn flee_steers_away_from_target_at_max_speed() {
+    let steering = flee(Vec3::ZERO, Vec3::ZERO, Vec3::new(10.0, 0.0, 0.0), 15.0);
+    assert_eq!(steering, Vec3::new(-15.0, 0.0, 0.0));
+    let steering = flee(Vec3::ZERO, Vec3::X * 5.0, Vec3::new(10.0, 0.0, 0.0), 15.0);
+    assert_eq!(steering, Vec3::new(-20.0, 0.0, 0.0));
+}
+
+#[test]
+fn arrive_slows_down_inside_slowing_radius() {
+    let target = Vec3::new(30.0, 0.0, 0.0);
+    // 在减速半径之外与寻找行为相同
+    assert_eq!(
+        arrive(Vec3::ZERO, Vec3::ZERO, target, 15.0, 15.0),
+        seek(Vec3::ZERO, Vec3::ZERO, target, 15.0)
+    );
+    // 在减速半径之内，期望速度与到目标的距离成正比
+    let steering = arrive(Vec3::new(25.0, 0.0, 0.0), Vec3::ZERO, target, 15.0, 15.0);
+    assert!((steering - Vec3::new(5.0, 0.0, 0.0)).length() < 1e-5);
+    // 停在目标处时不再需要转向力
+    assert_eq!(arrive(target, Vec3::ZERO, target, 15.0, 15.0), Vec3::ZERO);
+}
+
+#[test]
+fn seeking_enemy_chases_the_nearest_spaceship() {
+    let mut app = test_app();
+    app.add_plugins(EnemyPlugin);
+    let enemy = spawn_enemy(&mut app, Vec3::ZERO, EnemyBehavior::Seek);
+    app.world.spawn(spaceship_bundle(
+        Handle::default(),
+        PlayerId(0),
+        Vec3::new(30.0, 0.0, 0.0),
+    ));
+    app.world.spawn(spaceship_bundle(
+        Handle::default(),
+        PlayerId(1),
+        Vec3::new(0.0, 0.0, -50.0),
+    ));
+    run_ticks(&mut app, 5);
+
+    let velocity = app.world.get::<Velocity>(enemy).unwrap().value;
+    assert!(velocity.x > 0.0);
+    assert!(velocity.z.abs() < 1e-5);
+}
+
+#[test]
+fn orbiting_enemy_flees_from_a_close_spaceship() {
+    let mut app = test_app();
+    app.add_plugins(EnemyPlugin);
+    let enemy = spawn_enemy(&mut app, Vec3::ZERO, EnemyBehavior::Orbit);
+    app.world.spawn(spaceship_bundle(
+        Handle::default(),
+        PlayerId(0),
+        Vec3::new(10.0, 0.0, 0.0),
+    ));
+    run_ticks(&mut app, 3);
+
+    assert!(app.world.get::<Velocity>(enemy).unwrap().value.x < 0.0);
+}
+
+#[test]
+fn enemy_fires_at_spaceship_in_range() {
+    let mut app = test_app();
+    app.add_plugins(EnemyPlugin);
+    let enemy = spawn_enemy(&mut app, Vec3::ZERO, EnemyBehavior::Orbit);
+    app.world.spawn(spaceship_bundle(
+        Handle::default(),
+        PlayerId(0),
+        Vec3::new(40.0, 0.0, 0.0),
+    ));
+    // 开火间隔是两秒，第一帧的时长为零
+    run_ticks(&mut app, 21);
+
+    let enemy_translation = app.world.get::<Transform>(enemy).unwrap().translation;
+    let mut projectiles = app
+        .world
+        .query_filtered::<(&Transform, &Velocity), With<EnemyProjectile>>();
+    let (transform, velocity) = projectiles.single(&app.world);
+    assert!(velocity.value.x > 0.0);
+    assert!(transform.translation.x > enemy_translation.x);
+}
+
+#[test]
+fn enemy_does_not_fire_without_a_spaceship_in_range() {
+    let mut app = test_app();
+    app.add_plugins(EnemyPlugin);
+    spawn_enemy(&mut app, Vec3::ZERO, EnemyBehavior::Orbit);
+    run_ticks(&mut app, 21);
+
+    let mut projectiles = app.world.query_filtered::<(), With<EnemyProjectile>>();
+    assert_eq!(projectiles.iter(&app.world).count(), 0);
+}
+
+#[test]
+fn missile_destroys_enemy_and_credits_its_owner() {
+    let mut app = test_app();
+    app.add_plugins(EnemyPlugin);
+    let enemy = spawn_enemy(&mut app, Vec3::ZERO, EnemyBehavior::Orbit);
+    let missile = spawn_collider(&mut app, Vec3::new(4.5, 0.0, 0.0), 1.0);
+    app.world
+        .entity_mut(missile)
+        .insert((SpaceshipMissile, MissileOwner(PlayerId(1))));
+    run_ticks(&mut app, 3);
+
+    assert!(app.world.get_entity(enemy).is_none());
+    let events = app.world.resource::<Events<EntityDestroyed>>();
+    let destroyed: Vec<_> = events
+        .get_reader()
+        .read(events)
+        .map(|event| (event.kind, event.by_player))
+        .collect();
+    assert_eq!(destroyed, vec![(DestroyedKind::Enemy, Some(PlayerId(1)))]);
+}
+
+#[test]
+fn friendly_fire_only_applies_when_enabled() {
+    for friendly_fire in [false, true] {
+        let mut app = test_app();
+        app.insert_resource(PlayerConfig {
+            player_count: 2,
+            friendly_fire,
+        });
+        let spaceship = spawn_collider(&mut app, Vec3::ZERO, 5.0);
+        app.world
+            .entity_mut(spaceship)
+            .insert((Spaceship, PlayerId(0)));
+        let own_missile = spawn_collider(&mut app, Vec3::new(5.0, 0.0, 0.0), 1.0);
+        app.world
+            .entity_mut(own_missile)
+            .insert((SpaceshipMissile, MissileOwner(PlayerId(0))));
+
+        run_ticks(&mut app, 3);
+        // 自己的导弹永远不会击毁自己的飞船
+        assert!(app.world.get_entity(spaceship).is_some());
+
+        let other_missile = spawn_collider(&mut app, Vec3::new(-5.0, 0.0, 0.0), 1.0);
+        app.world
+            .entity_mut(other_missile)
+            .insert((SpaceshipMissile, MissileOwner(PlayerId(1))));
+
+        run_ticks(&mut app, 3);
+        assert_eq!(app.world.get_entity(spaceship).is_none(), friendly_fire);
+    }
+}
+
+#[test]
+fn split_screen_cameras_follow_ships_past_despawn_distance() {
+    let mut app = test_app();
+    app.insert_resource(PlayerConfig {
+        player_count: 2,
+        ..default()
+    })
+    .add_event::<WindowResized>()
+    .add_plugins(CameraPlugin);
+    app.world.spawn(spaceship_bundle(
+        Handle::default(),
+        PlayerId(1),
+        Vec3::new(0.0, 0.0, -90.0),
+    ));
+    run_ticks(&mut app, 3);
+
+    let mut cameras = app.world.query::<(&PlayerCamera, &GlobalTransform)>();
+    assert_eq!(cameras.iter(&app.world).count(), 2);
+    let (_, transform) = cameras
+        .iter(&app.world)
+        .find(|(camera, _)| camera.0 == PlayerId(1))
+        .unwrap();
+    assert_eq!(transform.translation().z, -90.0);
+}
+
+#[test]
+fn child_colliders_use_global_transform_and_ignore_siblings() {
+    let mut app = test_app();
+    let parent = app
+        .world
+        .spawn(SpatialBundle::from_transform(Transform::from_xyz(
+            40.0, 0.0, 0.0,
+        )))
+        .id();
+    let spawn_part = |app: &mut App, offset: Vec3| {
+        let part = app
+            .world
+            .spawn((
+                SpatialBundle::from_transform(Transform::from_translation(offset)),
+                Collider::new(2.0),
+            ))
+            .id();
+        app.world.entity_mut(parent).add_child(part);
+        part
+    };
+    let left = spawn_part(&mut app, Vec3::new(-1.0, 0.0, 0.0));
+    let right = spawn_part(&mut app, Vec3::new(1.0, 0.0, 0.0));
+    // 这个物体与右侧部件的全局位置重叠，但与父实体的局部位置相距很远
+    let other = spawn_collider(&mut app, Vec3::new(43.0, 0.0, 0.0), 1.0);
+
+    run_ticks(&mut app, 2);
+
+    assert!(app
+        .world
+        .get::<Collider>(left)
+        .unwrap()
+        .colliding_entities
+        .is_empty());
+    assert_eq!(
+        app.world.get::<Collider>(right).unwrap().colliding_entities,
+        vec![other]
+    );
+}
+
+#[test]
+fn spaceship_collects_pickup_without_being_destroyed() {
+    let mut app = test_app();
+    app.init_resource::<Lives>()
+        .add_plugins((ScorePlugin, PickupPlugin));
+    let spaceship = app
+        .world
+        .spawn(spaceship_bundle(Handle::default(), PlayerId(0), Vec3::ZERO))
+        .id();
+    let mut queue = CommandQueue::default();
+    let mut commands = Commands::new(&mut queue, &app.world);
+    spawn_pickup(
+        &mut commands,
+        &mut GameRng::from_seed(0),
+        PickupKind::WeaponUpgrade,
+        Vec3::X,
+    );
+    queue.apply(&mut app.world);
+
+    run_ticks(&mut app, 3);
+
+    assert!(app.world.get_entity(spaceship).is_some());
+    assert_eq!(
+        app.world.get::<SpaceshipWeapon>(spaceship).unwrap().level,
+        2
+    );
+    let mut pickups = app.world.query::<&Pickup>();
+    assert_eq!(pickups.iter(&app.world).count(), 0);
+}
+
+#[test]
+fn each_pickup_kind_has_its_own_glowing_model() {
+    let mut app = test_app();
+    app.init_resource::<Lives>()
+        .init_resource::<Assets<Mesh>>()
+        .init_resource::<Assets<StandardMaterial>>()
+        .add_plugins((ScorePlugin, PickupPlugin));
+    let mut queue = CommandQueue::default();
+    let mut commands = Commands::new(&mut queue, &app.world);
+    let mut rng = GameRng::from_seed(0);
+    for (index, kind) in [PickupKind::ShieldRecharge, PickupKind::ExtraLife]
+        .into_iter()
+        .enumerate()
+    {
+        spawn_pickup(&mut commands, &mut rng, kind, Vec3::X * 20.0 * index as f32);
+    }
+    queue.apply(&mut app.world);
+    app.update();
+
+    let mut pickups = app.world.query::<(&Pickup, &Children)>();
+    let models: Vec<_> = pickups
+        .iter(&app.world)
+        .map(|(_, children)| {
+            let model = app.world.entity(children[0]);
+            let material = model.get::<Handle<StandardMaterial>>().unwrap();
+            let material = app
+                .world
+                .resource::<Assets<StandardMaterial>>()
+                .get(material)
+                .unwrap();
+            let mesh = model.get::<Handle<Mesh>>().unwrap();
+            (
+                app.world
+                    .resource::<Assets<Mesh>>()
+                    .get(mesh)
+                    .unwrap()
+                    .count_vertices(),
+                material.base_color,
+                material.emissive,
+            )
+        })
+        .collect();
+    assert_eq!(models.len(), 2);
+    assert_ne!(models[0].0, models[1].0);
+    assert_ne!(models[0].1, models[1].1);
+    assert!(models.iter().all(|model| model.2 != Color::BLACK));
+}
+
+#[test]
+fn homing_missile_turns_towards_target_in_cone() {
+    let mut app = test_app();
+    app.add_plugins(HomingPlugin);
+    let asteroid = spawn_collider(&mut app, Vec3::new(10.0, 0.0, 10.0), 2.5);
+    app.world.entity_mut(asteroid).insert(Asteroid);
+    let missile = spawn_moving_object(
+        &mut app,
+        Vec3::new(0.0, 0.0, -20.0),
+        Vec3::Z * 10.0,
+        Vec3::ZERO,
+        1.0,
+    );
+    app.world
+        .entity_mut(missile)
+        .insert((SpaceshipMissile, HomingMissile::default()));
+
+    run_ticks(&mut app, 2);
+
+    assert_eq!(
+        app.world.get::<HomingMissile>(missile).unwrap().target,
+        Some(asteroid)
+    );
+    let velocity = app.world.get::<Velocity>(missile).unwrap().value;
+    assert!(velocity.x > 0.0);
+    assert!((velocity.length() - 10.0).abs() < 1e-3);
+
+    // 目标被销毁后，导弹会重新锁定锥形范围内的其他目标
+    app.world.despawn(asteroid);
+    let translation = app.world.get::<Transform>(missile).unwrap().translation;
+    let next = spawn_collider(&mut app, translation + velocity * 3.0, 2.5);
+    app.world.entity_mut(next).insert(Asteroid);
+    // 新生成的实体在第一帧之后才有正确的全局变换，第一帧时它位于原点
+    run_ticks(&mut app, 2);
+    assert_eq!(
+        app.world.get::<HomingMissile>(missile).unwrap().target,
+        Some(next)
+    );
+}
+
+#[test]
+fn colliding_asteroids_bounce_and_conserve_momentum() {
+    let mut app = test_app();
+    // 先让两颗小行星运行一帧，使它们拥有正确的全局变换
+    let a = spawn_moving_object(
+        &mut app,
+        Vec3::new(-20.0, 0.0, 0.0),
+        Vec3::ZERO,
+        Vec3::ZERO,
+        2.0,
+    );
+    let b = spawn_moving_object(
+        &mut app,
+        Vec3::new(20.0, 0.0, 0.0),
+        Vec3::ZERO,
+        Vec3::ZERO,
+        4.0,
+    );
+    app.world.entity_mut(a).insert(Asteroid);
+    app.world.entity_mut(b).insert(Asteroid);
+    run_ticks(&mut app, 1);
+    app.world.get_mut::<Velocity>(a).unwrap().value = Vec3::X * 40.0;
+    app.world.get_mut::<Velocity>(b).unwrap().value = Vec3::X * -10.0;
+    let momentum = |app: &App| {
+        [a, b]
+            .into_iter()
+            .map(|entity| {
+                let mass = app.world.get::<Collider>(entity).unwrap().mass();
+                app.world.get::<Velocity>(entity).unwrap().value * mass
+            })
+            .sum::<Vec3>()
+    };
+    let before = momentum(&app);
+
+    run_ticks(&mut app, 10);
+
+    assert!(app.world.get_entity(a).is_some());
+    assert!(app.world.get_entity(b).is_some());
+    // 较轻的小行星被弹回，较重的小行星被推向前方
+    assert!(app.world.get::<Velocity>(a).unwrap().value.x < 0.0);
+    assert!(app.world.get::<Velocity>(b).unwrap().value.x > 0.0);
+    assert!((momentum(&app) - before).length() < 1e-2);
+    let distance = app
+        .world
+        .get::<Transform>(a)
+        .unwrap()
+        .translation
+        .distance(app.world.get::<Transform>(b).unwrap().translation);
+    assert!(distance >= 6.0 - 1e-3);
+}
+
+#[test]
+fn fast_missile_does_not_tunnel_through_asteroid_at_large_timestep() {
+    let mut app = test_app();
+    // 使用 0.25 秒的帧长，导弹每帧移动 12.5，远大于导弹和小行星的直径之和
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+        0.25,
+    )));
+    let asteroid = spawn_collider(&mut app, Vec3::new(30.0, 0.0, 0.0), 2.5);
+    app.world.entity_mut(asteroid).insert(Asteroid);
+    let missile = spawn_moving_object(&mut app, Vec3::ZERO, Vec3::X * 50.0, Vec3::ZERO, 1.0);
+    app.world.entity_mut(missile).insert(SpaceshipMissile);
+    // 这颗小行星位于导弹路径的一侧，不应该被击中
+    let bystander = spawn_collider(&mut app, Vec3::new(30.0, 0.0, 10.0), 2.5);
+    app.world.entity_mut(bystander).insert(Asteroid);
+
+    run_ticks(&mut app, 6);
+
+    // 导弹在任何一帧结束时都没有和小行星重叠，但它的路径穿过了小行星
+    assert!(app.world.get_entity(asteroid).is_none());
+    assert!(app.world.get_entity(bystander).is_some());
+}
+
+#[test]
+fn swept_distance_finds_closest_approach() {
+    // 两个物体相向而行，在中途相遇
+    let distance = swept_distance(
+        (Some(Vec3::new(-10.0, 0.0, 0.0)), Vec3::new(10.0, 0.0, 0.0)),
+        (Some(Vec3::new(10.0, 0.0, 1.0)), Vec3::new(-10.0, 0.0, 1.0)),
+    );
+    assert!((distance - 1.0).abs() < 1e-5);
+    // 没有上一次位置的物体视为静止
+    let distance = swept_distance((None, Vec3::ZERO), (None, Vec3::new(3.0, 0.0, 4.0)));
+    assert!((distance - 5.0).abs() < 1e-5);
+}
+
+// 创建一个位于世界空间中的形状
+fn shape(
+    shape: &ColliderShape,
+    radius: f32,
+    translation: Vec3,
+    rotation: Quat,
+) -> ShapeInstance<'_> {
+    ShapeInstance {
+        shape,
+        radius,
+        translation,
+        rotation,
+    }
+}
+
+#[test]
+fn shape_intersection_tests() {
+    let sphere = ColliderShape::Sphere;
+    let capsule = ColliderShape::Capsule {
+        radius: 1.0,
+        half_length: 3.0,
+    };
+    let cuboid = ColliderShape::Cuboid {
+        half_extents: Vec3::new(2.0, 1.0, 1.0),
+    };
+    let hull = ColliderShape::ConvexHull {
+        points: vec![
+            Vec3::new(0.0, 0.0, 2.0),
+            Vec3::new(-1.0, -1.0, -1.0),
+            Vec3::new(1.0, -1.0, -1.0),
+            Vec3::new(0.0, 1.0, -1.0),
+        ],
+    };
+    let identity = Quat::IDENTITY;
+    let quarter_turn = Quat::from_rotation_y(std::f32::consts::FRAC_PI_2);
+
+    // 球体与胶囊体：胶囊体沿 Z 轴延伸，靠近端点的球体相交，侧面较远的球体不相交
+    let capsule_at_origin = shape(&capsule, 4.0, Vec3::ZERO, identity);
+    assert!(shapes_intersect(
+        shape(&sphere, 1.0, Vec3::new(0.0, 0.0, 4.5), identity),
+        capsule_at_origin,
+    ));
+    assert!(!shapes_intersect(
+        shape(&sphere, 1.0, Vec3::new(2.5, 0.0, 0.0), identity),
+        capsule_at_origin,
+    ));
+    // 胶囊体旋转 90 度后沿 X 轴延伸
+    assert!(shapes_intersect(
+        shape(&sphere, 1.0, Vec3::new(4.5, 0.0, 0.0), identity),
+        shape(&capsule, 4.0, Vec3::ZERO, quarter_turn),
+    ));
+
+    // 球体与有向包围盒：包围盒沿 X 轴较长，旋转后沿 Z 轴较长
+    assert!(shapes_intersect(
+        shape(&sphere, 1.0, Vec3::new(2.5, 0.0, 0.0), identity),
+        shape(&cuboid, 2.5, Vec3::ZERO, identity),
+    ));
+    assert!(!shapes_intersect(
+        shape(&sphere, 1.0, Vec3::new(2.5, 0.0, 0.0), identity),
+        shape(&cuboid, 2.5, Vec3::ZERO, quarter_turn),
+    ));
+    // 球体靠近包围盒的角，但在角的外侧
+    assert!(!shapes_intersect(
+        shape(&sphere, 0.5, Vec3::new(2.5, 0.0, 1.5), identity),
+        shape(&cuboid, 2.5, Vec3::ZERO, identity),
+    ));
+
+    // 胶囊体与有向包围盒
+    assert!(shapes_intersect(
+        shape(&capsule, 4.0, Vec3::new(0.0, 0.0, 5.0), identity),
+        shape(&cuboid, 2.5, Vec3::ZERO, identity),
+    ));
+    assert!(!shapes_intersect(
+        shape(&capsule, 4.0, Vec3::new(3.5, 0.0, 0.0), identity),
+        shape(&cuboid, 2.5, Vec3::ZERO, identity),
+    ));
+
+    // 两个有向包围盒
+    assert!(shapes_intersect(
+        shape(&cuboid, 2.5, Vec3::new(3.5, 0.0, 0.0), identity),
+        shape(&cuboid, 2.5, Vec3::ZERO, identity),
+    ));
+    assert!(!shapes_intersect(
+        shape(&cuboid, 2.5, Vec3::new(3.5, 0.0, 0.0), quarter_turn),
+        shape(&cuboid, 2.5, Vec3::ZERO, identity),
+    ));
+
+    // 凸包与球体
+    assert!(shapes_intersect(
+        shape(&sphere, 0.5, Vec3::new(0.0, 0.0, 2.3), identity),
+        shape(&hull, 2.0, Vec3::ZERO, identity),
+    ));
+    assert!(!shapes_intersect(
+        shape(&sphere, 0.5, Vec3::new(0.0, 0.0, 2.6), identity),
+        shape(&hull, 2.0, Vec3::ZERO, identity),
+    ));
+}
+
+#[test]
+fn capsule_collider_misses_sphere_beside_it() {
+    let mut app = test_app();
+    let spaceship = app
+        .world
+        .spawn(spaceship_bundle(Handle::default(), PlayerId(0), Vec3::ZERO))
+        .id();
+    // 这颗小行星位于飞船的包围球内，但在胶囊体的侧面之外
+    let beside = spawn_collider(&mut app, Vec3::new(4.5, 0.0, 0.0), 1.0);
+    app.world.entity_mut(beside).insert(Asteroid);
+
+    run_ticks(&mut app, 3);
+
+    assert!(app.world.get_entity(spaceship).is_some());
+    assert!(app.world.get_entity(beside).is_some());
+
+    // 同样距离的小行星位于飞船的前方，会撞上飞船
+    let ahead = spawn_collider(&mut app, Vec3::new(0.0, 0.0, 4.5), 1.0);
+    app.world.entity_mut(ahead).insert(Asteroid);
+
+    run_ticks(&mut app, 3);
+
+    assert!(app.world.get_entity(spaceship).is_none());
+}
+
+#[cfg(feature = "debug-overlay")]
+#[test]
+fn debug_overlay_text_survives_layout() {
+    let mut app = test_app();
+    app.init_resource::<ButtonInput<KeyCode>>()
+        .add_plugins(spaceship_game::debug::DebugPlugin);
+    app.update();
+
+    // 界面布局会把文本放到像素坐标上，远远超出销毁距离
+    let text = app
+        .world
+        .query_filtered::<Entity, With<Text>>()
+        .single(&app.world);
+    app.world
+        .entity_mut(text)
+        .insert(Transform::from_xyz(120.0, 16.0, 0.0));
+    run_ticks(&mut app, 3);
+
+    assert!(app.world.get_entity(text).is_some());
+}
+
+#[test]
+fn console_commands_registered_by_plugins() {
+    let mut app = test_app();
+    app.add_plugins((
+        AsteroidPlugin,
+        SpaceshipPlugin,
+        WavePlugin,
+        ConsoleCommandsPlugin,
+    ));
+    run_ticks(&mut app, 1);
+    let asteroid_count = |app: &mut App| {
+        app.world
+            .query_filtered::<(), With<Asteroid>>()
+            .iter(&app.world)
+            .count()
+    };
+
+    let before = asteroid_count(&mut app);
+    assert!(execute_command(&mut app.world, "spawn asteroid 3").is_ok());
+    assert_eq!(asteroid_count(&mut app), before + 3);
+    assert!(execute_command(&mut app.world, "kill all").is_ok());
+    assert_eq!(asteroid_count(&mut app), 0);
+
+    assert!(execute_command(&mut app.world, "wave 5").is_ok());
+    assert_eq!(app.world.resource::<Wave>().number, 5);
+
+    assert!(execute_command(&mut app.world, "set spaceship.speed 40").is_ok());
+    assert_eq!(app.world.resource::<SpaceshipSettings>().speed, 40.0);
+    assert!(execute_command(&mut app.world, "set spaceship.speed fast").is_err());
+    assert!(execute_command(&mut app.world, "set spaceship.speed nan").is_err());
+    assert!(execute_command(&mut app.world, "set missile.speed inf").is_err());
+    assert_eq!(app.world.resource::<SpaceshipSettings>().speed, 40.0);
+    assert!(execute_command(&mut app.world, "teleport").is_err());
+
+    let commands = app.world.resource::<ConsoleCommands>();
+    assert_eq!(commands.complete("spawn a"), vec!["spawn asteroid"]);
+    assert_eq!(
+        commands.complete("set s"),
+        vec!["set spaceship.rotation_speed", "set spaceship.speed"]
+    );
+}
+
+#[test]
+fn console_survives_layout_and_opens() {
+    let mut app = test_app();
+    app.init_resource::<ButtonInput<KeyCode>>()
+        .add_event::<ReceivedCharacter>()
+        .add_plugins(ConsolePlugin);
+    app.update();
+
+    // 界面布局会把控制台的根节点放到像素坐标上，远远超出销毁距离
+    let root = app
+        .world
+        .query_filtered::<Entity, (With<Node>, Without<Parent>)>()
+        .single(&app.world);
+    app.world
+        .entity_mut(root)
+        .insert(Transform::from_xyz(640.0, 144.0, 0.0));
+    run_ticks(&mut app, 3);
+
+    app.world.resource_mut::<ConsoleState>().open = true;
+    app.update();
+    assert_eq!(
+        app.world.get::<Visibility>(root),
+        Some(&Visibility::Visible)
+    );
+}
+
+#[test]
+fn telemetry_writes_filtered_samples_to_csv() {
+    let path = std::env::temp_dir().join(format!("telemetry-{}.csv", std::process::id()));
+    let args: Vec<String> = [
+        "--telemetry",
+        "asteroid",
+        "--telemetry-fields",
+        "translation,radius",
+        "--telemetry-every",
+        "2",
+        "--telemetry-out",
+    ]
+    .into_iter()
+    .map(String::from)
+    .chain([path.display().to_string()])
+    .collect();
+    let config = TelemetryConfig::from_args(&args).unwrap().unwrap();
+
+    let mut app = test_app();
+    app.insert_resource(config).add_plugins(TelemetryPlugin);
+    let asteroid = spawn_collider(&mut app, Vec3::new(10.0, 0.0, 0.0), 2.5);
+    app.world.entity_mut(asteroid).insert(Asteroid);
+    // 这个物体不是小行星，不会被记录
+    spawn_collider(&mut app, Vec3::new(-10.0, 0.0, 0.0), 1.0);
+
+    run_ticks(&mut app, 4);
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let lines: Vec<&str> = contents.lines().collect();
+    assert_eq!(
+        lines[0],
+        "tick,time,entity,kind,translation_x,translation_y,translation_z,radius"
+    );
+    // 4 帧中每隔 2 帧记录一次，只记录一颗小行星
+    assert_eq!(lines.len(), 3);
+    assert!(lines[1].starts_with("0,"));
+    assert!(lines[1].ends_with(",asteroid,10,0,0,2.5"));
+    assert!(lines[2].starts_with("2,"));
+}
+
+#[test]
+fn time_scale_slows_movement_and_paused_game_steps_one_frame() {
+    let mut app = test_app();
+    app.add_plugins(TimeControlPlugin);
+    let object = spawn_moving_object(
+        &mut app,
+        Vec3::new(0.0, 0.0, -20.0),
+        Vec3::new(10.0, 0.0, 0.0),
+        Vec3::ZERO,
+        1.0,
+    );
+    app.update();
+    let x = |app: &App| app.world.get::<Transform>(object).unwrap().translation.x;
+
+    // 时间倍率为 0.5 时，每一帧移动的距离减半
+    assert!(execute_command(&mut app.world, "timescale 0.5").is_ok());
+    app.update();
+    let start = x(&app);
+    app.update();
+    assert!((x(&app) - start - 10.0 * TICK_SECONDS * 0.5).abs() < 1e-4);
+
+    // 子弹时间会在基础倍率上进一步放慢游戏
+    app.world.resource_mut::<TimeScale>().bullet_time_seconds = 1.0;
+    assert!(app.world.resource::<TimeScale>().effective() < 0.5);
+    app.world.resource_mut::<TimeScale>().bullet_time_seconds = 0.0;
+
+    // 暂停后物体不再移动，单步执行只前进一帧
+    assert!(execute_command(&mut app.world, "step").is_err());
+    assert!(execute_command(&mut app.world, "pause").is_ok());
+    app.update();
+    let paused = x(&app);
+    app.update();
+    assert_eq!(x(&app), paused);
+    assert!(execute_command(&mut app.world, "step").is_ok());
+    app.update();
+    let stepped = x(&app);
+    assert!(stepped > paused);
+    app.update();
+    assert_eq!(x(&app), stepped);
+}
+
+#[test]
+fn timescale_command_rejects_non_finite_and_huge_scales() {
+    let mut app = test_app();
+    app.add_plugins(TimeControlPlugin);
+    for command in [
+        "timescale -1",
+        "timescale nan",
+        "timescale inf",
+        "timescale 1e30",
+    ] {
+        assert!(execute_command(&mut app.world, command).is_err());
+    }
+    assert_eq!(app.world.resource::<TimeScale>().base, 1.0);
+    // 被拒绝的倍率不会传给虚拟时间，游戏照常运行
+    run_ticks(&mut app, 2);
+}
+
+#[test]
+fn snapshot_restores_entities_and_resources() {
+    let mut app = test_app();
+    app.add_plugins((ScorePlugin, WavePlugin, BossPlugin, SnapshotPlugin))
+        .insert_resource(GameRng::from_seed(7))
+        .init_resource::<Lives>();
+    let asteroid = spawn_moving_object(
+        &mut app,
+        Vec3::new(5.0, 0.0, 10.0),
+        Vec3::new(1.0, 0.0, 2.0),
+        Vec3::new(0.0, 0.0, -0.5),
+        2.5,
+    );
+    app.world.entity_mut(asteroid).insert(Asteroid);
+    let ship = app
+        .world
+        .spawn(spaceship_bundle(
+            Handle::default(),
+            PlayerId(0),
+            Vec3::new(-10.0, 0.0, 0.0),
+        ))
+        .id();
+    let missile = spawn_moving_object(
+        &mut app,
+        Vec3::new(-30.0, 0.0, -30.0),
+        Vec3::Z * 5.0,
+        Vec3::ZERO,
+        0.5,
+    );
+    app.world.entity_mut(missile).insert((
+        SpaceshipMissile,
+        MissileOwner(PlayerId(0)),
+        HomingMissile {
+            target: Some(asteroid),
+        },
+    ));
+    app.update();
+    // 飞船的武器升级、护盾能量和生命值都应当被保存
+    let mut weapon = app.world.get_mut::<SpaceshipWeapon>(ship).unwrap();
+    weapon.level = 3;
+    weapon.homing_seconds = 4.0;
+    app.world.get_mut::<ShieldEnergy>(ship).unwrap().current = 2.0;
+    app.world.get_mut::<Health>(ship).unwrap().current = 0.5;
+    app.world.resource_mut::<Score>().points = 120;
+    app.world.resource_mut::<Wave>().set(4);
+    app.world.resource_mut::<Lives>().remaining = 1;
+    let saved_translation = app.world.get::<Transform>(asteroid).unwrap().translation;
+
+    let path = std::env::temp_dir().join(format!("snapshot-{}.scn.ron", std::process::id()));
+    assert_eq!(save_snapshot(&mut app.world, &path), Ok(3));
+    let expected_random: u64 = app.world.resource_mut::<GameRng>().gen();
+
+    // 继续运行并修改状态后读取存档，状态应当与保存时一致
+    run_ticks(&mut app, 5);
+    app.world.resource_mut::<Score>().points = 0;
+    app.world.resource_mut::<Lives>().remaining = 5;
+    // 保存之后出现的首领、敌人、道具、环境危害和碎片都不在存档中，读取时应当被删除
+    app.world.resource_mut::<Wave>().set(BOSS_WAVE_INTERVAL);
+    app.world.send_event(WaveStarted {
+        number: BOSS_WAVE_INTERVAL,
+    });
+    run_ticks(&mut app, 1);
+    assert_eq!(app.world.query::<&BossPart>().iter(&app.world).count(), 5);
+    app.world.spawn(Enemy);
+    app.world.spawn(EnemyProjectile);
+    app.world.spawn(Pickup::new(PickupKind::ExtraLife));
+    app.world.spawn(Hazard::Nebula);
+    app.world.spawn(Particle {
+        lifetime: Timer::from_seconds(1.0, TimerMode::Once),
+        size: 1.0,
+        spin: Vec3::ZERO,
+    });
+    assert_eq!(load_snapshot(&mut app.world, &path), Ok(3));
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(app.world.resource::<Score>().points, 120);
+    assert_eq!(app.world.resource::<Wave>().number, 4);
+    assert_eq!(app.world.resource::<Lives>().remaining, 1);
+    let mut leftovers = app.world.query_filtered::<(), Or<(
+        With<BossPart>,
+        With<BossHealthBarRoot>,
+        With<Enemy>,
+        With<EnemyProjectile>,
+        With<Pickup>,
+        With<Hazard>,
+        With<Particle>,
+    )>>();
+    assert_eq!(leftovers.iter(&app.world).count(), 0);
+    assert_eq!(
+        app.world.resource_mut::<GameRng>().gen::<u64>(),
+        expected_random
+    );
+
+    let mut asteroids = app
+        .world
+        .query_filtered::<(Entity, &Transform, &Velocity, &Collider), With<Asteroid>>();
+    let (restored_asteroid, transform, velocity, collider) = asteroids.single(&app.world);
+    assert_eq!(transform.translation, saved_translation);
+    assert_eq!(velocity.value, Vec3::new(1.0, 0.0, 2.0));
+    assert_eq!(collider.radius, 2.5);
+    let mut spaceships = app
+        .world
+        .query_filtered::<(&PlayerId, &SpaceshipWeapon, &ShieldEnergy, &Health), With<Spaceship>>();
+    assert_eq!(spaceships.iter(&app.world).count(), 1);
+    let (&player, weapon, shield, health) = spaceships.single(&app.world);
+    assert_eq!(player, PlayerId(0));
+    assert_eq!(weapon.level, 3);
+    assert_eq!(weapon.homing_seconds, 4.0);
+    assert_eq!(shield.current, 2.0);
+    assert_eq!(health.current, 0.5);
+    // 追踪导弹仍然锁定读取后的小行星
+    let homing = app
+        .world
+        .query::<&HomingMissile>()
+        .single(&app.world)
+        .target;
+    assert_eq!(homing, Some(restored_asteroid));
+
+    // 读取的实体可以继续正常运动
+    run_ticks(&mut app, 1);
+    let (_, transform, ..) = asteroids.single(&app.world);
+    assert_ne!(transform.translation, saved_translation);
+}
+
+#[test]
+fn gameplay_types_are_registered_for_reflection() {
+    let mut app = test_app();
+    app.add_plugins((AsteroidPlugin, SpaceshipPlugin, ScorePlugin, WavePlugin));
+    let object = spawn_moving_object(
+        &mut app,
+        Vec3::ZERO,
+        Vec3::new(1.0, 0.0, 0.0),
+        Vec3::ZERO,
+        1.0,
+    );
+
+    let type_registry = app.world.resource::<AppTypeRegistry>().clone();
+    let type_registry = type_registry.read();
+    for type_path in [
+        "spaceship_game::movement::Velocity",
+        "spaceship_game::movement::Acceleration",
+        "spaceship_game::collision_detection::Collider",
+        "spaceship_game::asteroids::Asteroid",
+        "spaceship_game::asteroids::SpawnTimer",
+        "spaceship_game::spaceship::Spaceship",
+        "spaceship_game::spaceship::SpaceshipShield",
+        "spaceship_game::spaceship::SpaceshipMissile",
+        "spaceship_game::score::Score",
+        "spaceship_game::wave::Wave",
+    ] {
+        assert!(
+            type_registry.get_with_type_path(type_path).is_some(),
+            "{type_path} is not registered"
+        );
+    }
+
+    // 通过反射修改组件的字段，就像检查器窗口所做的那样
+    let reflect_component = type_registry
+        .get_type_data::<ReflectComponent>(std::any::TypeId::of::<Velocity>())
+        .unwrap();
+    let mut entity = app.world.entity_mut(object);
+    let mut velocity = reflect_component.reflect_mut(&mut entity).unwrap();
+    let Ok(value) = velocity.reflect_path_mut("value.x") else {
+        panic!("`Velocity` has no `value.x` field");
+    };
+    *value.downcast_mut::<f32>().unwrap() = 5.0;
+    assert_eq!(app.world.get::<Velocity>(object).unwrap().value.x, 5.0);
+}
+
+// 创建一个带粒子效果的应用
+fn particle_app() -> App {
+    let mut app = test_app();
+    app.add_plugins(AssetPlugin::default())
+        .init_asset::<Mesh>()
+        .init_asset::<StandardMaterial>()
+        .add_plugins(ParticlePlugin);
+    app
+}
+
+// 统计当前的粒子数量
+fn particle_count(app: &mut App) -> usize {
+    app.world.query::<&Particle>().iter(&app.world).count()
+}
+
+#[test]
+fn particle_bursts_scale_with_the_size_and_kind_of_destroyed_entity() {
+    let mut app = particle_app();
+    app.update();
+    let burst = |app: &mut App, kind, radius| {
+        let before = particle_count(app);
+        app.world
+            .send_event(destroyed_event(kind, Vec3::new(10.0, 0.0, 10.0), radius));
+        app.update();
+        particle_count(app) - before
+    };
+
+    let small = burst(&mut app, DestroyedKind::Asteroid, 1.0);
+    let large = burst(&mut app, DestroyedKind::Asteroid, 3.0);
+    let spaceship = burst(&mut app, DestroyedKind::Spaceship, 3.0);
+    assert!(small > 0);
+    assert!(large > small);
+    assert!(spaceship > large);
+}
+
+#[test]
+fn particles_shrink_and_disappear_after_their_lifetime() {
+    let mut app = particle_app();
+    app.update();
+    app.world.send_event(destroyed_event(
+        DestroyedKind::Spaceship,
+        Vec3::new(10.0, 0.0, 10.0),
+        3.0,
+    ));
+    app.update();
+    assert!(particle_count(&mut app) > 0);
+
+    run_ticks(&mut app, 3);
+    let mut particles = app.world.query::<(&Particle, &Transform)>();
+    assert!(particles
+        .iter(&app.world)
+        .all(|(particle, transform)| transform.scale.x < particle.size));
+    run_ticks(&mut app, 20);
+    assert_eq!(particle_count(&mut app), 0);
+}
+
+#[test]
+fn thrust_emits_exhaust_behind_the_ship_and_turning_emits_rcs_puffs() {
+    let mut app = particle_app();
+    let translation = Vec3::new(20.0, 0.0, 20.0);
+    let ship = spawn_spaceship(&mut app, translation);
+
+    // 没有输入时不喷出任何粒子
+    run_ticks(&mut app, 5);
+    assert_eq!(particle_count(&mut app), 0);
+
+    // 转向时只喷出少量姿态控制气团
+    app.world.get_mut::<PlayerInput>(ship).unwrap().turn = 1.0;
+    run_ticks(&mut app, 5);
+    let puffs = particle_count(&mut app);
+    assert!(puffs > 0);
+
+    let mut input = app.world.get_mut::<PlayerInput>(ship).unwrap();
+    input.turn = 0.0;
+    input.thrust = 1.0;
+    run_ticks(&mut app, 5);
+    let exhaust = particle_count(&mut app) - puffs;
+    assert!(exhaust > puffs);
+
+    // 飞船的前方是本地的 +Z 方向，尾焰位于飞船的后方
+    let mut particles = app.world.query::<(&Particle, &Transform, &Velocity)>();
+    let behind: Vec<_> = particles
+        .iter(&app.world)
+        .filter(|(_, _, velocity)| velocity.value.z < 0.0)
+        .collect();
+    assert!(!behind.is_empty());
+    assert!(behind
+        .iter()
+        .all(|(_, transform, _)| transform.translation.z < translation.z));
+}
+
+// 创建一个带音频的应用，所有声音都有音频文件，总音量和音效音量都减半
+fn audio_app() -> App {
+    let mut app = test_app();
+    app.insert_resource(AudioAssets {
+        fire: Some(Handle::default()),
+        explosion: Some(Handle::default()),
+        shield: Some(Handle::default()),
+        pickup: Some(Handle::default()),
+        engine: Some(Handle::default()),
+        music: Some(Handle::default()),
+        music_intense: Some(Handle::default()),
+    })
+    .insert_resource(AudioVolume {
+        master: 0.5,
+        music: 1.0,
+        effects: 0.5,
+    })
+    .add_plugins(GameAudioPlugin);
+    app
+}
+
+#[test]
+fn gameplay_events_play_positional_sounds_scaled_by_volume_settings() {
+    let mut app = audio_app();
+    let ship = spawn_spaceship(&mut app, Vec3::new(20.0, 0.0, 20.0));
+    app.update();
+    let engine = app
+        .world
+        .query_filtered::<&Parent, With<EngineSound>>()
+        .single(&app.world)
+        .get();
+    assert_eq!(engine, ship);
+
+    let translation = Vec3::new(-15.0, 0.0, 10.0);
+    app.world
+        .send_event(destroyed_event(DestroyedKind::Asteroid, translation, 2.0));
+    app.update();
+    let mut sounds = app
+        .world
+        .query_filtered::<(&PlaybackSettings, &Transform), Without<EngineSound>>();
+    let (settings, transform) = sounds
+        .iter(&app.world)
+        .find(|(settings, _)| settings.spatial)
+        .expect("爆炸应该播放空间音效");
+    assert_eq!(transform.translation, translation);
+    assert!(settings.volume.get() < 0.25 + f32::EPSILON);
+}
+
+#[test]
+fn sounds_without_audio_files_are_skipped() {
+    let mut app = audio_app();
+    app.insert_resource(AudioAssets::default());
+    spawn_spaceship(&mut app, Vec3::new(20.0, 0.0, 20.0));
+    app.world.send_event(destroyed_event(
+        DestroyedKind::Asteroid,
+        Vec3::new(-15.0, 0.0, 10.0),
+        2.0,
+    ));
+    app.update();
+
+    let mut sounds = app.world.query::<&Handle<AudioSource>>();
+    assert_eq!(sounds.iter(&app.world).count(), 0);
+}
+
+#[test]
+fn music_intensity_rises_with_wave_number() {
+    assert_eq!(music_intensity(1), 0.0);
+    assert!(music_intensity(4) > music_intensity(2));
+    assert_eq!(music_intensity(20), 1.0);
+}
+
+// 按下一个按键并运行一帧
+fn press_key(app: &mut App, key: KeyCode) {
+    app.world.resource_mut::<ButtonInput<KeyCode>>().press(key);
+    app.update();
+    app.world
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .release(key);
+}
+
+// 返回一个尚不存在的临时设置文件路径
+fn settings_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "spaceship_settings_{name}_{}.ron",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+// 创建一个带设置菜单的应用，设置保存在给定的文件中，摄像机使用跟随模式
+fn options_app(path: &Path) -> App {
+    let mut app = test_app();
+    app.init_resource::<ButtonInput<KeyCode>>()
+        .insert_resource(SettingsFile(Some(path.to_path_buf())))
+        .insert_resource(Settings {
+            camera_mode: CameraMode::Follow,
+            ..default()
+        })
+        .add_plugins(SettingsPlugin);
+    app
+}
+
+#[test]
+fn options_menu_changes_are_applied_and_persisted() {
+    let path = settings_path("options");
+    let mut app = options_app(&path);
+    let ship = app
+        .world
+        .spawn((
+            spaceship_bundle(Handle::default(), PlayerId(0), Vec3::new(20.0, 0.0, 20.0)),
+            KeyBindings::for_player(PlayerId(0)),
+        ))
+        .id();
+    run_ticks(&mut app, 3);
+    assert_eq!(*app.world.resource::<CameraMode>(), CameraMode::Follow);
+    assert!(!path.exists(), "没有修改的设置不应该写入文件");
+
+    // 打开设置菜单，把总音量降低一档
+    press_key(&mut app, KeyCode::F10);
+    assert!(app.world.resource::<OptionsMenu>().open);
+    press_key(&mut app, KeyCode::ArrowLeft);
+    app.update();
+    assert!((app.world.resource::<AudioVolume>().master - 0.9).abs() < 1e-5);
+
+    // 选中玩家一的前进按键并重新绑定为 I
+    for _ in 0..8 {
+        press_key(&mut app, KeyCode::ArrowDown);
+    }
+    press_key(&mut app, KeyCode::Enter);
+    assert!(app.world.resource::<OptionsMenu>().rebinding);
+    press_key(&mut app, KeyCode::KeyI);
+    app.update();
+    assert_eq!(
+        app.world.get::<KeyBindings>(ship).unwrap().forward,
+        KeyCode::KeyI
+    );
+
+    // 重新读取设置文件得到相同的设置
+    let saved = Settings::load(&path).unwrap();
+    assert_eq!(&saved, app.world.resource::<Settings>());
+    assert_eq!(saved.key_bindings[0].forward, KeyCode::KeyI);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn difficulty_command_updates_the_settings() {
+    let path = settings_path("difficulty");
+    let mut app = options_app(&path);
+    app.add_plugins((DifficultyPlugin, ConsoleCommandsPlugin));
+    app.update();
+
+    assert!(execute_command(&mut app.world, "difficulty hard").is_ok());
+    // 之后修改其他设置时难度不会被改回去
+    run_ticks(&mut app, 1);
+    app.world.resource_mut::<Settings>().volume.master = 0.3;
+    run_ticks(&mut app, 1);
+    assert_eq!(*app.world.resource::<Difficulty>(), Difficulty::Hard);
+    assert_eq!(
+        app.world.resource::<Settings>().difficulty,
+        Difficulty::Hard
+    );
+    assert_eq!(Settings::load(&path).unwrap().difficulty, Difficulty::Hard);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn options_menu_and_follow_camera_survive_far_from_origin() {
+    let mut app = test_app();
+    app.init_resource::<ButtonInput<KeyCode>>()
+        .add_event::<WindowResized>()
+        .insert_resource(Settings {
+            camera_mode: CameraMode::Follow,
+            ..default()
+        })
+        .add_plugins((SettingsPlugin, CameraPlugin));
+    // 飞船还在销毁距离之内，但跟随它的摄像机已经超出了销毁距离
+    app.world.spawn(spaceship_bundle(
+        Handle::default(),
+        PlayerId(0),
+        Vec3::new(90.0, 0.0, 0.0),
+    ));
+    app.update();
+
+    let root = app
+        .world
+        .query_filtered::<Entity, (With<Node>, Without<Parent>)>()
+        .single(&app.world);
+    app.world
+        .entity_mut(root)
+        .insert(Transform::from_xyz(640.0, 360.0, 0.0));
+    run_ticks(&mut app, 3);
+
+    assert!(app.world.get_entity(root).is_some());
+    let camera = app
+        .world
+        .query_filtered::<&GlobalTransform, With<MainCamera>>()
+        .single(&app.world);
+    assert!(camera.translation().length() > DESPAWN_DISTANCE);
+}
+
+// 在指定的难度下运行两秒，返回生成的每颗小行星的速度
+fn asteroid_speeds(difficulty: Difficulty) -> Vec<f32> {
+    let mut app = test_app();
+    app.insert_resource(GameRng::from_seed(7))
+        .insert_resource(difficulty)
+        .add_plugins((DifficultyPlugin, AsteroidPlugin));
+    run_ticks(&mut app, 20);
+    let mut query = app.world.query_filtered::<&Velocity, With<Asteroid>>();
+    query
+        .iter(&app.world)
+        .map(|velocity| velocity.value.length())
+        .collect()
+}
+
+#[test]
+fn harder_presets_spawn_more_and_faster_asteroids() {
+    let easy = asteroid_speeds(Difficulty::Easy);
+    let insane = asteroid_speeds(Difficulty::Insane);
+    assert!(insane.len() > easy.len());
+    let fastest = |speeds: &[f32]| speeds.iter().copied().fold(0.0, f32::max);
+    assert!(fastest(&insane) > fastest(&easy));
+}
+
+#[test]
+fn dynamic_difficulty_eases_off_after_repeated_deaths() {
+    let mut app = test_app();
+    app.insert_resource(Difficulty::Dynamic)
+        .add_plugins(DifficultyPlugin);
+    app.update();
+    for _ in 0..3 {
+        app.world
+            .send_event(destroyed_event(DestroyedKind::Spaceship, Vec3::ZERO, 2.5));
+    }
+    run_ticks(&mut app, 20);
+
+    // 三次死亡后难度等级逐渐下降，小行星生成变慢
+    let performance = app.world.resource::<PlayerPerformance>();
+    assert_eq!(performance.recent_deaths.len(), 3);
+    assert!(performance.level < 1.0);
+    assert!(
+        app.world
+            .resource::<DifficultyTuning>()
+            .spawn_interval_scale
+            > 1.0
+    );
+}
+
+#[test]
+fn edge_spawned_asteroids_appear_outside_the_view_heading_inward_and_away_from_ships() {
+    let mut app = test_app();
+    app.insert_resource(GameRng::from_seed(11))
+        .insert_resource(AsteroidSpawnConfig {
+            safe_radius: 30.0,
+            ..default()
+        })
+        // 飞船被撞毁后就不再有安全距离，因此让飞船无敌
+        .insert_resource(GodMode { enabled: true })
+        .add_plugins((DifficultyPlugin, AsteroidPlugin));
+    // 这里只检查生成的位置，去掉会分裂出碎片和会吸引飞船的小行星
+    app.world
+        .resource_mut::<AsteroidVariants>()
+        .variants
+        .retain(|variant| variant.behavior == AsteroidBehavior::Inert);
+    // 飞船停在场地上边缘之外，那里的小行星必须避开它
+    let ship = Vec3::new(0.0, 0.0, 40.0);
+    spawn_spaceship(&mut app, ship);
+
+    // 逐帧运行，记录每颗小行星第一次出现时的位置和速度
+    let mut first_seen: HashMap<Entity, (Vec3, Vec3)> = HashMap::new();
+    let mut query = app
+        .world
+        .query_filtered::<(Entity, &Transform, &Velocity), With<Asteroid>>();
+    for _ in 0..300 {
+        app.update();
+        for (entity, transform, velocity) in query.iter(&app.world) {
+            first_seen
+                .entry(entity)
+                .or_insert((transform.translation, velocity.value));
+        }
+    }
+    assert!(first_seen.len() > 10);
+    for (translation, velocity) in first_seen.values() {
+        assert!(translation.x.abs() > 59.0 || translation.z.abs() > 33.0);
+        assert!(translation.distance(ship) > 28.0);
+        assert!(velocity.dot(-*translation) > 0.0);
+    }
+}
+
+#[test]
+fn ring_pattern_spawns_a_full_ring_outside_the_view() {
+    let mut app = test_app();
+    app.insert_resource(GameRng::from_seed(3))
+        .insert_resource(AsteroidSpawnConfig {
+            pattern: SpawnPattern::Ring,
+            ..default()
+        })
+        .add_plugins((DifficultyPlugin, AsteroidPlugin));
+    run_ticks(&mut app, 15);
+
+    let mut query = app.world.query_filtered::<&Transform, With<Asteroid>>();
+    let ring: Vec<Vec3> = query
+        .iter(&app.world)
+        .map(|transform| transform.translation)
+        .collect();
+    assert_eq!(ring.len(), AsteroidSpawnConfig::default().ring_count);
+    assert!(ring
+        .iter()
+        .all(|translation| translation.x.abs() > 60.0 || translation.z.abs() > 34.0));
+}
+
+// 创建一个不会自动生成小行星的应用
+fn variant_app() -> App {
+    let mut app = test_app();
+    app.insert_resource(AsteroidSpawnConfig {
+        edges: Vec::new(),
+        ..default()
+    })
+    .add_plugins(AsteroidPlugin);
+    app
+}
+
+// 使用默认数据生成一颗指定种类的静止小行星
+fn spawn_variant(app: &mut App, kind: AsteroidKind, translation: Vec3) -> Entity {
+    let variant = AsteroidVariants::default().get(kind).unwrap().clone();
+    app.world
+        .spawn(asteroid_bundle(
+            Handle::default(),
+            &variant,
+            translation,
+            Vec3::ZERO,
+            Vec3::ZERO,
+            &mut rand::thread_rng(),
+        ))
+        .id()
+}
+
+// 在给定位置生成一枚玩家一的导弹
+fn fire_missile_at(app: &mut App, translation: Vec3) -> Entity {
+    let missile = spawn_collider(app, translation, 1.0);
+    app.world
+        .entity_mut(missile)
+        .insert((SpaceshipMissile, MissileOwner(PlayerId(0))));
+    missile
+}
+
+#[test]
+fn every_asteroid_kind_has_a_variant() {
+    let variants = AsteroidVariants::default();
+    assert!(AsteroidKind::ALL
+        .iter()
+        .all(|&kind| variants.get(kind).is_some()));
+}
+
+#[test]
+fn asteroid_scale_collider_and_tumble_come_from_its_variant() {
+    let mut app = variant_app();
+    let iron = spawn_variant(&mut app, AsteroidKind::Iron, Vec3::ZERO);
+    let (min_scale, max_scale) = AsteroidVariants::default()
+        .get(AsteroidKind::Iron)
+        .unwrap()
+        .scale;
+    let transform = *app.world.get::<Transform>(iron).unwrap();
+    let scale = transform.scale.x;
+    assert!((min_scale..=max_scale).contains(&scale));
+    assert_eq!(app.world.get::<Collider>(iron).unwrap().radius, 2.5 * scale);
+
+    // 小行星在飞行时翻滚
+    run_ticks(&mut app, 3);
+    assert_ne!(
+        app.world.get::<Transform>(iron).unwrap().rotation,
+        transform.rotation
+    );
+}
+
+#[test]
+fn iron_asteroid_absorbs_missiles_and_loses_health() {
+    let mut app = variant_app();
+    let iron = spawn_variant(&mut app, AsteroidKind::Iron, Vec3::ZERO);
+    let missile = fire_missile_at(&mut app, Vec3::new(3.0, 0.0, 0.0));
+    run_ticks(&mut app, 3);
+
+    assert!(app.world.get_entity(missile).is_none());
+    let health = app.world.get::<Health>(iron).unwrap();
+    assert_eq!(health.current, health.max - 1.0);
+}
+
+#[test]
+fn ice_asteroid_shatters_into_outward_shards() {
+    let mut app = variant_app();
+    let ice = spawn_variant(&mut app, AsteroidKind::Ice, Vec3::ZERO);
+    // 导弹击碎母体后没有被消耗，它继续穿过碎片飞行，碎片在保护时间内不会被立即击碎
+    let missile = spawn_moving_object(
+        &mut app,
+        Vec3::new(3.0, 0.0, 0.0),
+        Vec3::new(-50.0, 0.0, 0.0),
+        Vec3::ZERO,
+        1.0,
+    );
+    app.world
+        .entity_mut(missile)
+        .insert((SpaceshipMissile, MissileOwner(PlayerId(0))));
+    while app.world.get_entity(ice).is_some() {
+        app.update();
+    }
+    run_ticks(&mut app, 10);
+
+    let mut query = app
+        .world
+        .query_filtered::<(&Transform, &Velocity, &AsteroidKind, &AsteroidBehavior), With<Asteroid>>();
+    let shards: Vec<_> = query.iter(&app.world).collect();
+    assert_eq!(shards.len(), 5);
+    assert!(shards.iter().all(
+        |(transform, velocity, &kind, &behavior)| kind == AsteroidKind::Ice
+            && behavior == AsteroidBehavior::Inert
+            && velocity.value.dot(transform.translation) > 0.0
+    ));
+}
+
+#[test]
+fn explosive_asteroid_chain_destroys_nearby_asteroids_and_damages_iron() {
+    let mut app = variant_app();
+    let explosive = spawn_variant(&mut app, AsteroidKind::Explosive, Vec3::ZERO);
+    let rock = spawn_variant(&mut app, AsteroidKind::Rock, Vec3::new(0.0, 0.0, -8.0));
+    let iron = spawn_variant(&mut app, AsteroidKind::Iron, Vec3::new(8.0, 0.0, 0.0));
+    // 第二颗爆炸小行星在第一颗的范围内，它继续引爆更远处的岩石
+    let chained = spawn_variant(
+        &mut app,
+        AsteroidKind::Explosive,
+        Vec3::new(-10.0, 0.0, 0.0),
+    );
+    let far_rock = spawn_variant(&mut app, AsteroidKind::Rock, Vec3::new(-22.0, 0.0, 0.0));
+    fire_missile_at(&mut app, Vec3::new(0.0, 0.0, 3.0));
+    run_ticks(&mut app, 3);
+
+    for entity in [explosive, rock, chained, far_rock] {
+        assert!(app.world.get_entity(entity).is_none());
+    }
+    // 铁质小行星只受到伤害
+    let health = app.world.get::<Health>(iron).unwrap();
+    assert!(health.current < health.max && !health.is_depleted());
+    // 连锁爆炸摧毁的小行星都记在玩家的名下
+    let events = app.world.resource::<Events<EntityDestroyed>>();
+    let credited = events
+        .get_reader()
+        .read(events)
+        .filter(|event| event.by_player == Some(PlayerId(0)))
+        .count();
+    assert_eq!(credited, 4);
+}
+
+#[test]
+fn magnetic_asteroid_pulls_spaceships_in() {
+    let mut app = variant_app();
+    spawn_variant(&mut app, AsteroidKind::Magnetic, Vec3::ZERO);
+    let ship = spawn_spaceship(&mut app, Vec3::new(0.0, 0.0, 15.0));
+    run_ticks(&mut app, 5);
+
+    assert!(app.world.get::<Transform>(ship).unwrap().translation.z < 15.0);
+    // 拉力通过外力速度移动飞船，飞船自身的速度不受影响
+    assert!(app.world.get::<ExternalVelocity>(ship).unwrap().value.z < 0.0);
+    assert_eq!(app.world.get::<Velocity>(ship).unwrap().value, Vec3::ZERO);
+}
+
+// 在已经运行过一帧的应用的原点放置给定的危害
+fn spawn_hazard_at_origin(app: &mut App, hazard: Hazard) {
+    let mut queue = CommandQueue::default();
+    spawn_hazard(
+        &mut Commands::new(&mut queue, &app.world),
+        hazard,
+        Vec3::ZERO,
+    );
+    queue.apply(&mut app.world);
+}
+
+// 创建一个在原点放置了给定危害的应用
+fn hazard_app(hazard: Hazard) -> App {
+    let mut app = test_app();
+    app.add_plugins(HazardPlugin);
+    app.update();
+    spawn_hazard_at_origin(&mut app, hazard);
+    app
+}
+
+#[test]
+fn gravity_well_pulls_objects_in_range() {
+    let mut app = hazard_app(Hazard::GravityWell);
+    let object = spawn_collider(&mut app, Vec3::new(10.0, 0.0, 0.0), 1.0);
+    let outside = spawn_collider(&mut app, Vec3::new(50.0, 0.0, 0.0), 1.0);
+    let ship = spawn_spaceship(&mut app, Vec3::new(0.0, 0.0, 15.0));
+    run_ticks(&mut app, 5);
+
+    assert!(app.world.get::<Velocity>(object).unwrap().value.x < 0.0);
+    assert!(app.world.get::<Transform>(object).unwrap().translation.x < 10.0);
+    assert_eq!(
+        app.world.get::<Velocity>(outside).unwrap().value,
+        Vec3::ZERO
+    );
+    // 飞船的速度仍然由输入决定，引力的效果保存在 `ExternalVelocity` 中
+    assert_eq!(app.world.get::<Velocity>(ship).unwrap().value, Vec3::ZERO);
+    assert!(app.world.get::<ExternalVelocity>(ship).unwrap().value.z < 0.0);
+    assert!(app.world.get::<Transform>(ship).unwrap().translation.z < 15.0);
+}
+
+#[test]
+fn black_hole_destroys_asteroids_past_its_event_horizon() {
+    let mut app = hazard_app(Hazard::BlackHole);
+    let asteroid = spawn_collider(&mut app, Vec3::new(8.0, 0.0, 0.0), 1.0);
+    app.world.entity_mut(asteroid).insert(Asteroid);
+    run_ticks(&mut app, 30);
+    assert!(app.world.get_entity(asteroid).is_none());
+}
+
+#[test]
+fn nebula_halves_spaceship_movement_and_disables_shields() {
+    let mut app = hazard_app(Hazard::Nebula);
+    app.init_resource::<ButtonInput<KeyCode>>()
+        .add_plugins(SpaceshipPlugin);
+    // 在星云内外各放一艘推进并开启护盾的飞船
+    let input = PlayerInput {
+        thrust: 1.0,
+        shield: true,
+        ..default()
+    };
+    let slowed = spawn_spaceship(&mut app, Vec3::new(0.0, 0.0, 5.0));
+    let free = spawn_spaceship(&mut app, Vec3::new(40.0, 0.0, 5.0));
+    app.world.entity_mut(slowed).insert(input);
+    app.world.entity_mut(free).insert(input);
+    run_ticks(&mut app, 3);
+
+    let translations = |app: &App| {
+        [slowed, free].map(|ship| app.world.get::<Transform>(ship).unwrap().translation)
+    };
+    let before = translations(&app);
+    app.update();
+    let after = translations(&app);
+    let slowed_distance = before[0].distance(after[0]);
+    let free_distance = before[1].distance(after[1]);
+    assert!(free_distance > 0.0);
+    assert!((slowed_distance - free_distance * 0.5).abs() < 1e-3);
+    assert!(app.world.get::<InNebula>(slowed).is_some());
+    assert!(app.world.get::<SpaceshipShield>(slowed).is_none());
+    assert!(app.world.get::<SpaceshipShield>(free).is_some());
+}
+
+#[test]
+fn particles_expiring_inside_a_nebula_are_left_alone() {
+    let mut app = particle_app();
+    app.add_plugins(HazardPlugin);
+    app.update();
+    spawn_hazard_at_origin(&mut app, Hazard::Nebula);
+    app.world.send_event(destroyed_event(
+        DestroyedKind::Spaceship,
+        Vec3::new(2.0, 0.0, 0.0),
+        3.0,
+    ));
+
+    // 碎片在星云中飞行直到全部消失，它们不会被标记为位于星云中
+    let mut tagged = app
+        .world
+        .query_filtered::<(), (With<Particle>, With<InNebula>)>();
+    for _ in 0..25 {
+        app.update();
+        assert_eq!(tagged.iter(&app.world).count(), 0);
+    }
+    assert_eq!(particle_count(&mut app), 0);
+}
+
+#[test]
+fn solar_flare_pushes_objects_away_only_while_erupting() {
+    let mut app = hazard_app(Hazard::SolarFlare);
+    let object = spawn_collider(&mut app, Vec3::new(10.0, 0.0, 0.0), 1.0);
+    run_ticks(&mut app, 5);
+    assert!(app.world.get::<Velocity>(object).unwrap().value.x > 0.0);
+
+    // 喷发结束后物体保持原来的速度
+    run_ticks(&mut app, 20);
+    let coasting = app.world.get::<Velocity>(object).unwrap().value.x;
+    run_ticks(&mut app, 5);
+    assert_eq!(app.world.get::<Velocity>(object).unwrap().value.x, coasting);
+}
+
+#[test]
+fn each_wave_after_the_first_replaces_the_hazard() {
+    let mut app = test_app();
+    app.insert_resource(GameRng::from_seed(5))
+        .add_plugins(HazardPlugin);
+    let mut hazards = app.world.query::<&Hazard>();
+    let counts: Vec<usize> = (1..=3)
+        .map(|number| {
+            app.world.send_event(WaveStarted { number });
+            app.update();
+            hazards.iter(&app.world).count()
+        })
+        .collect();
+    assert_eq!(counts, [0, 1, 1]);
+}